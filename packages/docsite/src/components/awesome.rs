@@ -65,6 +65,48 @@ impl Display for Category {
     }
 }
 
+impl Item {
+    /// The item as a search document. Items link to other sites, so the document links to the
+    /// awesome page instead
+    #[cfg(not(target_arch = "wasm32"))]
+    fn search_document(&self) -> dioxus_search::Document {
+        let section = match self.r#type {
+            AwesomeType::Awesome => "Awesome Dioxus",
+            AwesomeType::MadeWith => "Made with Dioxus",
+        };
+        dioxus_search::Document {
+            url: "/awesome".to_string(),
+            breadcrumb: vec![section.to_string(), self.category.to_string()],
+            title: self.name.clone(),
+            body: self.description.clone(),
+            keywords: self
+                .github
+                .iter()
+                .flat_map(|github| [github.username.clone(), github.repo.clone()])
+                .collect(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Download the awesome list and turn its items into search documents
+#[cfg(feature = "server")]
+pub(crate) fn awesome_search_documents() -> Result<Vec<dioxus_search::Document>, String> {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(|err| err.to_string())?;
+    let items = runtime
+        .block_on(async {
+            reqwest::get(ITEM_LIST_LINK)
+                .await?
+                .json::<Vec<Item>>()
+                .await
+        })
+        .map_err(|err| err.to_string())?;
+    Ok(items.iter().map(Item::search_document).collect())
+}
+
 #[derive(serde::Deserialize)]
 pub struct StarsResponse {
     pub stargazers_count: u64,
@@ -1,6 +1,80 @@
 use crate::DARK_MODE;
 use dioxus::prelude::*;
 
+/// The source of the component gallery. Every component has a `component.json` with its name and
+/// description, and the `component.json` at the root lists the directory of each component
+#[cfg(feature = "server")]
+const COMPONENT_REGISTRY: &str =
+    "https://raw.githubusercontent.com/DioxusLabs/dioxus-components/main";
+
+/// The `component.json` of the gallery or one of its components
+#[cfg(feature = "server")]
+#[derive(serde::Deserialize)]
+struct ComponentManifest {
+    name: String,
+    #[serde(default)]
+    description: String,
+    /// The directories of the components, relative to the root of the gallery
+    #[serde(default)]
+    members: Vec<String>,
+}
+
+#[cfg(feature = "server")]
+impl ComponentManifest {
+    /// The component as a search document. The gallery is rendered in an iframe, so the page
+    /// itself doesn't have any text to index
+    fn search_document(&self) -> dioxus_search::Document {
+        // Components are named like `alert_dialog`
+        let title = self
+            .name
+            .split(['_', '-'])
+            .map(|word| {
+                let mut chars = word.chars();
+                chars
+                    .next()
+                    .map(|first| first.to_uppercase().chain(chars).collect::<String>())
+                    .unwrap_or_default()
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        dioxus_search::Document {
+            url: "/components".to_string(),
+            breadcrumb: vec!["Components".to_string()],
+            title,
+            body: self.description.clone(),
+            keywords: vec![self.name.replace('-', "_")],
+            ..Default::default()
+        }
+    }
+}
+
+/// Download the manifest of every component in the gallery and turn them into search documents
+#[cfg(feature = "server")]
+pub(crate) fn component_search_documents() -> Result<Vec<dioxus_search::Document>, String> {
+    async fn manifest(url: String) -> Result<ComponentManifest, reqwest::Error> {
+        reqwest::get(url).await?.error_for_status()?.json().await
+    }
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(|err| err.to_string())?;
+    let components = runtime
+        .block_on(async {
+            let gallery = manifest(format!("{COMPONENT_REGISTRY}/component.json")).await?;
+            futures::future::try_join_all(gallery.members.iter().map(|member| {
+                let member = member.trim_end_matches('/');
+                manifest(format!("{COMPONENT_REGISTRY}/{member}/component.json"))
+            }))
+            .await
+        })
+        .map_err(|err| err.to_string())?;
+    Ok(components
+        .iter()
+        .map(ComponentManifest::search_document)
+        .collect())
+}
+
 #[component]
 pub(crate) fn Components() -> Element {
     let segments: ReadOnlySignal<Vec<String>> = Default::default();
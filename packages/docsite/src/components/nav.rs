@@ -1,8 +1,9 @@
-use crate::docs::AnyBookRoute;
 use crate::*;
 use dioxus::html::input_data::keyboard_types::Key;
+use dioxus::logger::tracing;
 // use dioxus_material_icons::{MaterialIcon, MaterialIconColor};
 use std::ops::Deref;
+use std::rc::Rc;
 
 pub(crate) static SHOW_SEARCH: GlobalSignal<bool> = Signal::global(|| false);
 
@@ -174,55 +175,256 @@ fn LinkList() -> Element {
     }
 }
 
-type Results = Result<Vec<dioxus_search::SearchResult<Route>>, stork_lib::SearchError>;
+type Results = Result<dioxus_search::MultiSearchResults<Route>, dioxus_search::SearchError>;
 
-fn SearchModal() -> Element {
-    let mut search_text = use_signal(String::new);
+/// The facet of each version of the docs and each other kind of content whose index loaded in the
+/// search worker, the current version first
+type SearchIndexes = Vec<&'static str>;
 
-    let search_index = use_resource(|| async move {
-        #[cfg(not(feature = "production"))]
-        let url_base = "http://localhost:8080/assets/dioxus_search";
-
-        #[cfg(feature = "production")]
-        let url_base = "https://dioxuslabs.com/assets/dioxus_search";
-
-        let current_route: Route = router().current();
-        // Only show search results from the version of the docs the user is currently on (or the latest if they
-        // are not on a doc page)
-        let docs_index_version = match &current_route {
-            Route::Docs07 { .. } => "0_7",
-            Route::Docs06 { .. } => "0_6",
-            Route::Docs05 { .. } => "0_5",
-            Route::Docs04 { .. } => "0_4",
-            Route::Docs03 { .. } => "0_3",
-            _ => "0_7",
-        };
+/// Every version of the docs that has a search index, newest first
+pub(crate) const SEARCH_VERSIONS: [&str; 5] = ["0.7", "0.6", "0.5", "0.4", "0.3"];
 
-        let url = format!("{url_base}/index_searchable_{docs_index_version}.bin");
+/// The search indexes of content outside the docs, which are searched along with every version
+pub(crate) const SEARCH_CONTENT: [&str; 2] = ["blog", "community"];
 
-        let data = reqwest::get(url).await.ok()?.bytes().await.ok()?;
+/// The kinds of content search results are grouped by
+#[derive(Clone, Copy, PartialEq)]
+enum ContentType {
+    Docs,
+    Blog,
+    Community,
+}
+
+impl ContentType {
+    const ALL: [Self; 3] = [Self::Docs, Self::Blog, Self::Community];
 
-        let (bytes, _) =
-            dioxus_search::yazi::decompress(&data, dioxus_search::yazi::Format::Zlib).ok()?;
+    /// The kind of content in the index with this facet. Each version of the docs is a facet of
+    /// its own
+    fn of_facet(facet: &str) -> Self {
+        match facet {
+            "blog" => Self::Blog,
+            "community" => Self::Community,
+            _ => Self::Docs,
+        }
+    }
 
-        let index: dioxus_search::SearchIndex<Route> =
-            dioxus_search::SearchIndex::from_bytes("search", bytes);
+    fn label(self) -> &'static str {
+        match self {
+            Self::Docs => "Docs",
+            Self::Blog => "Blog",
+            Self::Community => "Community",
+        }
+    }
+}
 
-        Some(index)
+/// The version of the docs the route is in, or the latest version if it isn't a docs page
+fn docs_version(route: &Route) -> &'static str {
+    match route {
+        Route::Docs07 { .. } => "0.7",
+        Route::Docs06 { .. } => "0.6",
+        Route::Docs05 { .. } => "0.5",
+        Route::Docs04 { .. } => "0.4",
+        Route::Docs03 { .. } => "0.3",
+        _ => "0.7",
+    }
+}
+
+/// The url the shards of a version's search index are served under
+fn search_index_url(version: &str) -> String {
+    #[cfg(not(feature = "production"))]
+    let url_base = "http://localhost:8080/assets/dioxus_search";
+
+    #[cfg(feature = "production")]
+    let url_base = "https://dioxuslabs.com/assets/dioxus_search";
+
+    let version = version.replace('.', "_");
+    format!("{url_base}/index_searchable_{version}")
+}
+
+/// Fetch a shard of a search index. This also runs in the search worker
+pub(crate) async fn fetch_search_shard(url: String) -> Result<Vec<u8>, dioxus_search::SearchError> {
+    let response = reqwest::get(url)
+        .await
+        .map_err(dioxus_search::SearchError::new)?;
+    let data = response
+        .bytes()
+        .await
+        .map_err(dioxus_search::SearchError::new)?;
+    Ok(data.to_vec())
+}
+
+/// Start the worker that searches the indexes off the main thread, or search on the main thread
+/// if the worker can't start
+fn spawn_search_worker() -> dioxus_search::SearchWorker<Route> {
+    dioxus_search::SearchWorker::new(fetch_search_shard).unwrap_or_else(|err| {
+        tracing::error!("Failed to start the search worker: {err}");
+        dioxus_search::SearchWorker::in_thread(fetch_search_shard)
+    })
+}
+
+/// Send a search to the analytics endpoint in the background. Failures are ignored, since the
+/// reader doesn't wait for it
+fn report_search(event: dioxus_search::SearchEvent) {
+    #[cfg(feature = "fullstack")]
+    spawn_forever(async move {
+        _ = super::search::record_search(event).await;
     });
 
-    let search = move || {
-        let query = &search_text.read();
-        let mut results = search_index
-            .value()
-            .as_ref()
-            .and_then(|search| search.as_ref().map(|s| s.search(query)))
-            .unwrap_or_else(|| Ok(vec![]));
+    #[cfg(not(feature = "fullstack"))]
+    drop(event);
+}
 
-        results
-    };
+/// Answer a query from the dictionaries in the search worker alone if it is short enough
+async fn instant_results(
+    worker: &dioxus_search::SearchWorker<Route>,
+    indexes: &SearchIndexes,
+    query: &str,
+) -> Option<dioxus_search::MultiSearchResults<Route>> {
+    let results = futures::future::join_all(indexes.iter().map(|facet| async move {
+        let results = worker.search_instant(facet, query).await.ok()??;
+        Some((facet.to_string(), results))
+    }))
+    .await;
+    let results = results.into_iter().collect::<Option<Vec<_>>>()?;
+    Some(dioxus_search::MultiSearchResults::merge(results))
+}
 
-    let mut results = use_signal(search);
+/// Search the pages or code examples of every version in the search worker. Dropping the future
+/// cancels the searches
+async fn search_indexes(
+    worker: &dioxus_search::SearchWorker<Route>,
+    indexes: &SearchIndexes,
+    query: &str,
+    mode: dioxus_search::SearchMode,
+) -> Results {
+    let results = futures::future::join_all(indexes.iter().map(|version| async move {
+        let results = match mode {
+            dioxus_search::SearchMode::Pages => worker.search(version, query).await?,
+            dioxus_search::SearchMode::Code => worker.search_code(version, query).await?,
+        };
+        Ok::<_, dioxus_search::SearchError>((version.to_string(), results))
+    }))
+    .await;
+    let results = results.into_iter().collect::<Result<Vec<_>, _>>()?;
+    Ok(dioxus_search::MultiSearchResults::merge(results))
+}
+
+fn SearchModal() -> Element {
+    let mut search_text = use_signal(String::new);
+    // Developers often remember a snippet rather than a page, so code examples can be searched
+    // on their own
+    let mut search_mode = use_signal(|| dioxus_search::SearchMode::Pages);
+
+    // Decompressing shards and searching them is slow enough to drop frames while typing, so full
+    // searches run in a web worker. Dropping an outdated search cancels it in the worker
+    let search_worker = use_hook(|| Rc::new(spawn_search_worker()));
+
+    // Each query is reported once the reader stops refining it, so we know what readers search
+    // for and don't find. Closing the search reports the last query
+    let analytics = use_hook(|| Rc::new(dioxus_search::SearchAnalytics::new(report_search)));
+    use_effect({
+        let analytics = analytics.clone();
+        move || {
+            if !SHOW_SEARCH() {
+                analytics.flush();
+            }
+        }
+    });
+    let open_result = use_callback({
+        let analytics = analytics.clone();
+        move |rank: usize| analytics.clicked(rank)
+    });
+
+    // Search every version of the docs so readers on an older version can still find pages that
+    // only exist in newer versions, and the blog and community pages after the docs. The indexes
+    // are loaded once: this doesn't read the route, so navigating doesn't load them again
+    let loaded_indexes = use_resource({
+        let search_worker = search_worker.clone();
+        move || {
+            let search_worker = search_worker.clone();
+            async move {
+                let facets: Vec<&'static str> =
+                    SEARCH_VERSIONS.into_iter().chain(SEARCH_CONTENT).collect();
+
+                // Only the worker downloads the indexes. Searches of a facet wait for its
+                // dictionary to load, and facets that fail to load aren't searched
+                let loaded = futures::future::join_all(facets.iter().map(|facet| {
+                    let load = search_worker.load(*facet, search_index_url(facet));
+                    async move {
+                        load.await
+                            .map_err(|err| {
+                                tracing::error!(
+                                    "Failed to load the search index for {facet}: {err}"
+                                )
+                            })
+                            .ok()
+                            .map(|()| *facet)
+                    }
+                }))
+                .await;
+
+                loaded.into_iter().flatten().collect::<SearchIndexes>()
+            }
+        }
+    });
+    // The current version is searched first so it wins ties
+    let search_index = use_memo(move || {
+        let current_version = docs_version(&router().current());
+        let mut facets = loaded_indexes.cloned().unwrap_or_default();
+        facets.sort_by_key(|facet| *facet != current_version);
+        facets
+    });
+
+    let mut results = use_signal(|| Ok(Default::default()));
+
+    // Popular searches, completions and suggestions come from the dictionary of the current
+    // version, which the worker loads first
+    let current_facet = move || search_index.read().first().copied();
+    let popular = use_resource({
+        let search_worker = search_worker.clone();
+        move || {
+            let search_worker = search_worker.clone();
+            async move {
+                let Some(facet) = current_facet() else {
+                    return Vec::new();
+                };
+                search_worker
+                    .popular_searches(facet)
+                    .await
+                    .unwrap_or_default()
+            }
+        }
+    });
+    let completions = use_resource({
+        let search_worker = search_worker.clone();
+        move || {
+            let search_worker = search_worker.clone();
+            async move {
+                let query = search_text();
+                let Some(facet) = current_facet() else {
+                    return Vec::new();
+                };
+                search_worker
+                    .complete(facet, &query, 4)
+                    .await
+                    .unwrap_or_default()
+            }
+        }
+    });
+    let did_you_mean = use_resource({
+        let search_worker = search_worker.clone();
+        move || {
+            let search_worker = search_worker.clone();
+            async move {
+                let query = search_text();
+                search_worker
+                    .did_you_mean(current_facet()?, &query)
+                    .await
+                    .ok()
+                    .flatten()
+            }
+        }
+    });
 
     let mut last_key_press = use_signal(|| {
         if cfg!(target_arch = "wasm32") {
@@ -233,17 +435,34 @@ fn SearchModal() -> Element {
     });
 
     _ = use_resource(move || {
+        let search_worker = search_worker.clone();
+        let analytics = analytics.clone();
         async move {
-            _ = search_text();
+            let query = search_text();
+            let mode = search_mode();
+            let indexes = search_index();
+
+            // Short queries don't need any shards, so they skip the debounce
+            if mode == dioxus_search::SearchMode::Pages {
+                if let Some(instant) = instant_results(&search_worker, &indexes, &query).await {
+                    results.set(Ok(instant));
+                    return;
+                }
+            }
 
             // debounce the search
             if *last_key_press.read() - js_sys::Date::now() > 100. {
-                results.set(search());
                 last_key_press.set(js_sys::Date::now());
             } else {
                 gloo_timers::future::TimeoutFuture::new(100).await;
-                results.set(search());
             }
+            let found = search_indexes(&search_worker, &indexes, &query, mode).await;
+            // Only searches for pages are reported, since they tell us which pages to write
+            if let (Ok(found), dioxus_search::SearchMode::Pages) = (&found, mode) {
+                let version = docs_version(&router().current());
+                analytics.searched(Some(version), &query, found.in_facet(version).count());
+            }
+            results.set(found);
         }
     });
 
@@ -286,14 +505,52 @@ fn SearchModal() -> Element {
                                         _ = evt.set_focus(true).await;
                                     },
                                     class: "flex-grow bg-transparent border-none outline-none pl-2 text-gray-500 dark:text-gray-100 py-2 placeholder-gray-400",
-                                    placeholder: "Search the docs...",
+                                    placeholder: match search_mode() {
+                                        dioxus_search::SearchMode::Pages => "Search the docs...",
+                                        dioxus_search::SearchMode::Code => "Search code examples...",
+                                    },
                                     value: "{search_text}",
                                 }
                             }
+                            for (mode , label) in [
+                                (dioxus_search::SearchMode::Pages, "Docs"),
+                                (dioxus_search::SearchMode::Code, "Code"),
+                            ]
+                            {
+                                button {
+                                    class: "rounded-full border px-2 py-1 ml-1 text-xs border-gray-300 dark:border-gray-700",
+                                    class: if search_mode() == mode { "bg-gray-100 dark:bg-gray-800" },
+                                    onclick: move |evt| {
+                                        evt.stop_propagation();
+                                        search_mode.set(mode);
+                                    },
+                                    "{label}"
+                                }
+                            }
+                        }
+                        if completions.read().as_ref().is_some_and(|completions| !completions.is_empty()) {
+                            div { class: "flex flex-row flex-wrap gap-2 pl-9 text-xs",
+                                for completion in completions.cloned().unwrap_or_default() {
+                                    button {
+                                        class: "rounded-full border px-2 py-1 border-gray-300 dark:border-gray-700",
+                                        onclick: move |evt| {
+                                            evt.stop_propagation();
+                                            search_text.set(completion.clone());
+                                        },
+                                        "{completion}"
+                                    }
+                                }
+                            }
                         }
                     }
 
-                    SearchResults { results, search_text }
+                    SearchResults {
+                        results,
+                        search_text,
+                        popular,
+                        did_you_mean,
+                        onopen: open_result,
+                    }
                 }
             }
         }
@@ -301,7 +558,17 @@ fn SearchModal() -> Element {
 }
 
 #[component]
-fn SearchResults(results: Signal<Results>, search_text: Signal<String>) -> Element {
+fn SearchResults(
+    results: Signal<Results>,
+    search_text: Signal<String>,
+    popular: Resource<Vec<dioxus_search::SearchResult<Route>>>,
+    did_you_mean: Resource<Option<String>>,
+    /// Called with the rank of a result in the list when it is opened
+    onopen: EventHandler<usize>,
+) -> Element {
+    let cur_route = use_route::<Route>();
+    let mut selected_version = use_signal(|| docs_version(&cur_route));
+
     if let Err(err) = results.read().as_ref() {
         return rsx! {
             div { class: "text-red-500", "{err}" }
@@ -310,86 +577,124 @@ fn SearchResults(results: Signal<Results>, search_text: Signal<String>) -> Eleme
 
     let _results = results.read();
     let results = _results.deref().as_ref().unwrap();
-    let cur_route = use_route::<Route>();
-    let results = results
-        .iter()
-        .filter(|route| match route.route {
-            Route::Docs03 { .. } => matches!(cur_route, Route::Docs03 { .. }),
-            Route::Docs04 { .. } => matches!(cur_route, Route::Docs04 { .. }),
-            Route::Docs05 { .. } => matches!(cur_route, Route::Docs05 { .. }),
-            Route::Docs06 { .. } => matches!(cur_route, Route::Docs06 { .. }),
-            _ => {
-                !matches!(cur_route, Route::Docs03 { .. })
-                    && !matches!(cur_route, Route::Docs04 { .. })
-                    && !matches!(cur_route, Route::Docs05 { .. })
-                    && !matches!(cur_route, Route::Docs06 { .. })
-            }
+    // Results are grouped by the kind of content. Only the selected version of the docs is shown
+    let groups = ContentType::ALL
+        .into_iter()
+        .map(|content| {
+            let facet = match content {
+                ContentType::Docs => selected_version(),
+                ContentType::Blog => "blog",
+                ContentType::Community => "community",
+            };
+            (content, results.in_facet(facet).collect::<Vec<_>>())
         })
         .collect::<Vec<_>>();
-
-    use crate::docs::router_06::BookRoute;
-
-    let default_searches = [
-        (
-            "Tutorial",
-            BookRoute::GuideIndex {
-                section: Default::default(),
-            },
-        ),
-        (
-            "Web",
-            BookRoute::GuidesWebIndex {
-                section: Default::default(),
-            },
-        ),
-        (
-            "Desktop",
-            BookRoute::GuidesDesktopIndex {
-                section: Default::default(),
-            },
-        ),
-        (
-            "Mobile",
-            BookRoute::GuidesMobileIndex {
-                section: Default::default(),
-            },
-        ),
-        (
-            "Fullstack",
-            BookRoute::GuidesFullstackIndex {
-                section: Default::default(),
-            },
-        ),
-        (
-            "Typesafe Routing",
-            BookRoute::RouterReferenceIndex {
-                section: Default::default(),
-            },
-        ),
-    ];
+    let version_results = &groups[0].1;
+    // The rank of the first result of each group in the whole list
+    let offsets = groups.iter().scan(0, |offset, (_, group)| {
+        let start = *offset;
+        *offset += group.len();
+        Some(start)
+    });
+    let version_facets = results.facets.iter().filter(|facet| {
+        facet.count > 0 && ContentType::of_facet(&facet.facet) == ContentType::Docs
+    });
 
     rsx! {
         ul { class: "p-2 flex flex-col",
             if search_text.read().is_empty() {
-                for (search , child) in default_searches {
-                    SearchResultItem {
-                        title: search.to_string(),
-                        route: child.global_route(),
-                    }
+                for search in popular.cloned().unwrap_or_default() {
+                    SearchResultItem { title: search.title, route: search.route }
                 }
             } else if results.is_empty() {
                 div { class: "text-center text-xlg p-4", "No results found for: {search_text}" }
+                if let Some(suggestion) = did_you_mean.cloned().flatten() {
+                    div { class: "text-center text-sm",
+                        "Did you mean "
+                        button {
+                            class: "text-blue-500 hover:underline",
+                            onclick: move |evt| {
+                                evt.stop_propagation();
+                                search_text.set(suggestion.clone());
+                            },
+                            "{suggestion}"
+                        }
+                        "?"
+                    }
+                }
             } else {
-                for result in results {
-                    SearchResultItem {
-                        title: result.title.clone(),
-                        route: result.route.clone(),
-                        span { class: "mt-1",
-                            for segment in result.excerpts.first().unwrap().text.iter() {
-                                if segment.highlighted {
-                                    span { class: "text-blue-500", "{segment.text}" }
-                                } else {
-                                    span { class: "text-gray-400", "{segment.text}" }
+                div { class: "flex flex-row flex-wrap gap-2 p-2 text-xs",
+                    for facet in version_facets {
+                        button {
+                            class: "rounded-full border px-2 py-1 border-gray-300 dark:border-gray-700",
+                            class: if facet.facet == selected_version() { "bg-gray-100 dark:bg-gray-800" },
+                            onclick: {
+                                let version = SEARCH_VERSIONS
+                                    .into_iter()
+                                    .find(|version| *version == facet.facet)
+                                    .unwrap_or_default();
+                                move |evt: MouseEvent| {
+                                    evt.stop_propagation();
+                                    selected_version.set(version);
+                                }
+                            },
+                            "{facet.count} results in {facet.facet}"
+                        }
+                    }
+                }
+                if version_results.is_empty() {
+                    div { class: "text-center text-xlg p-4",
+                        "No results found for: {search_text} in {selected_version}"
+                    }
+                }
+                for ((content , group) , offset) in groups.iter().zip(offsets).filter(|((_, group), _)| !group.is_empty()) {
+                    h3 { class: "text-xs font-semibold uppercase text-gray-400 px-2 pt-2",
+                        "{content.label()}"
+                    }
+                    for (index , result) in group.iter().enumerate() {
+                        SearchResultItem {
+                            title: result.title.clone(),
+                            breadcrumb: (!result.breadcrumb.is_empty()).then(|| result.breadcrumb.join(" › ")),
+                            route: result.route.clone(),
+                            onopen: move |_| onopen.call(offset + index),
+                            for correction in result.matches.iter().filter(|term_match| term_match.is_correction()) {
+                                span { class: "text-xs text-gray-400 ml-6",
+                                    "Showing results for "
+                                    span { class: "text-blue-500", "{correction.term}" }
+                                    " instead of "
+                                    s { "{correction.query}" }
+                                }
+                            }
+                            // Code examples show the matched lines of code and the file they came from.
+                            // Instant results for short queries don't have excerpts
+                            if let (Some(example), Some(excerpt)) = (&result.example, result.excerpts.first()) {
+                                if let Some(file) = &example.file {
+                                    span { class: "text-xs text-gray-400 ml-6 font-mono", "{file}" }
+                                }
+                                pre { class: "mt-1 text-xs overflow-x-auto font-mono",
+                                    for segment in excerpt.text.iter() {
+                                        if segment.highlighted {
+                                            span { class: "text-blue-500", "{segment.text}" }
+                                        } else {
+                                            span { class: "text-gray-400", "{segment.text}" }
+                                        }
+                                    }
+                                }
+                            } else if !result.excerpts.is_empty() {
+                                // Pages can match in a few places, so show each excerpt
+                                span { class: "mt-1",
+                                    for (index, excerpt) in result.excerpts.iter().enumerate() {
+                                        if index > 0 {
+                                            span { class: "text-gray-400", " … " }
+                                        }
+                                        for segment in excerpt.text.iter() {
+                                            if segment.highlighted {
+                                                span { class: "text-blue-500", "{segment.text}" }
+                                            } else {
+                                                span { class: "text-gray-400", "{segment.text}" }
+                                            }
+                                        }
+                                    }
                                 }
                             }
                         }
@@ -401,16 +706,28 @@ fn SearchResults(results: Signal<Results>, search_text: Signal<String>) -> Eleme
 }
 
 #[component]
-fn SearchResultItem(title: String, route: Route, children: Element) -> Element {
+fn SearchResultItem(
+    title: String,
+    breadcrumb: Option<String>,
+    route: Route,
+    onopen: Option<EventHandler<()>>,
+    children: Element,
+) -> Element {
     rsx! {
         li { class: "w-full rounded-lg hover:bg-gray-100 dark:hover:bg-gray-800 transition-colors duration-200 ease-in-out",
             Link {
                 to: route,
                 onclick: move |_| {
+                    if let Some(onopen) = onopen {
+                        onopen.call(());
+                    }
                     *SHOW_SEARCH.write() = false;
                 },
                 class: "flex flex-row items-center gap-x-2 p-2",
                 div { class: "flex flex-col mt-1 mb-1",
+                    if let Some(breadcrumb) = breadcrumb {
+                        span { class: "text-xs text-gray-400 ml-6", "{breadcrumb}" }
+                    }
                     span { class: "flex flex-row items-center gap-x-1",
                         icons::DocumentIcon {}
                         h2 { class: "dark:text-white ml-1", "{title}" }
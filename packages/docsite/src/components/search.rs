@@ -1,29 +1,264 @@
+#[cfg(feature = "fullstack")]
+use dioxus::prelude::*;
+
 pub fn generate_search_index() {
     #[cfg(not(target_arch = "wasm32"))]
     {
         use crate::{static_dir, Route};
+        use dioxus::logger::tracing;
 
         std::env::set_var("CARGO_MANIFEST_DIR", static_dir().join("assets"));
-        let version_filter: [(&str, fn(&Route) -> bool); 5] = [
-            ("0_3", |route| matches!(route, Route::Docs03 { .. })),
-            ("0_4", |route| matches!(route, Route::Docs04 { .. })),
-            ("0_5", |route| matches!(route, Route::Docs05 { .. })),
-            ("0_6", |route| matches!(route, Route::Docs06 { .. })),
-            ("0_7", |route| matches!(route, Route::Docs07 { .. })),
+        let ranking = match dioxus_search::RankingConfig::from_toml(include_str!(
+            "../../search_ranking.toml"
+        )) {
+            Ok(ranking) => ranking,
+            Err(err) => {
+                tracing::error!("Failed to read search_ranking.toml: {err}");
+                return;
+            }
+        };
+        let version_filter: [(&str, &str, fn(&Route) -> bool); 5] = [
+            (
+                "0_3",
+                include_str!("../../../../docs-src/0.3/book.toml"),
+                |route| matches!(route, Route::Docs03 { .. }),
+            ),
+            (
+                "0_4",
+                include_str!("../../../../docs-src/0.4/book.toml"),
+                |route| matches!(route, Route::Docs04 { .. }),
+            ),
+            (
+                "0_5",
+                include_str!("../../../../docs-src/0.5/book.toml"),
+                |route| matches!(route, Route::Docs05 { .. }),
+            ),
+            (
+                "0_6",
+                include_str!("../../../../docs-src/0.6/book.toml"),
+                |route| matches!(route, Route::Docs06 { .. }),
+            ),
+            (
+                "0_7",
+                include_str!("../../../../docs-src/0.7/book.toml"),
+                |route| matches!(route, Route::Docs07 { .. }),
+            ),
         ];
-        for (version, filter) in version_filter {
-            dioxus_search::SearchIndex::<Route>::create(
+        for (version, book_toml, filter) in version_filter {
+            let ranking = ranking.clone().with_version(&version.replace('_', "."));
+            let ranking = match ranking.with_book_config(book_toml) {
+                Ok(ranking) => ranking,
+                Err(err) => {
+                    tracing::error!("Failed to read the book.toml of {version}: {err}");
+                    continue;
+                }
+            };
+            let config = dioxus_search::IndexConfig {
+                ranking,
+                excerpts: dioxus_search::ExcerptConfig {
+                    max_excerpts: 2,
+                    ..Default::default()
+                },
+                ..Default::default()
+            };
+            let index = dioxus_search::SearchIndex::<Route>::create_with_config(
                 format!("searchable_{version}"),
-                dioxus_search::BaseDirectoryMapping::new(static_dir()).map(|route| {
-                    filter(&route).then(|| {
-                        let route = route.to_string();
-                        println!("route: {route}");
-                        let (route, _) = route.split_once('#').unwrap_or((&route, ""));
-                        let (route, _) = route.split_once('?').unwrap_or((&route, ""));
-                        std::path::PathBuf::from(route).join("index.html")
-                    })
-                }),
+                dioxus_search::BaseDirectoryMapping::new(static_dir())
+                    .map(|route| filter(&route).then(|| page_path(&route))),
+                config,
             );
+            if let Err(err) = index {
+                tracing::error!("Failed to build the search index for {version}: {err}");
+            }
+        }
+
+        // Blog posts and community content get their own indexes, so the search modal can group
+        // their results apart from the docs. The pinned results of the docs don't apply to them
+        let blog = dioxus_search::SearchIndex::<Route>::create(
+            "searchable_blog",
+            dioxus_search::BaseDirectoryMapping::new(static_dir())
+                .map(|route| matches!(route, Route::BlogPost { .. }).then(|| page_path(&route))),
+        );
+        if let Err(err) = blog {
+            tracing::error!("Failed to build the search index for the blog: {err}");
+        }
+
+        let community = dioxus_search::SearchIndex::<Route>::create(
+            "searchable_community",
+            dioxus_search::BaseDirectoryMapping::new(static_dir())
+                .with_documents(community_documents())
+                .map(|_: Route| None),
+        );
+        if let Err(err) = community {
+            tracing::error!("Failed to build the search index for the community pages: {err}");
+        }
+    }
+}
+
+/// The rendered page of a route in the static directory
+#[cfg(not(target_arch = "wasm32"))]
+fn page_path(route: &crate::Route) -> std::path::PathBuf {
+    let route = route.to_string();
+    let (route, _) = route.split_once('#').unwrap_or((&route, ""));
+    let (route, _) = route.split_once('?').unwrap_or((route, ""));
+    std::path::PathBuf::from(route).join("index.html")
+}
+
+/// The awesome list and the component gallery are loaded in the browser, so their rendered pages
+/// don't have anything to index. Each item is added as a document that links to the page it is
+/// listed on instead
+#[cfg(not(target_arch = "wasm32"))]
+fn community_documents() -> Vec<dioxus_search::Document> {
+    #[allow(unused_mut)]
+    let mut documents = Vec::new();
+
+    #[cfg(feature = "server")]
+    match super::component_demo::component_search_documents() {
+        Ok(components) => documents.extend(components),
+        Err(err) => dioxus::logger::tracing::error!("Failed to index the components: {err}"),
+    }
+
+    #[cfg(feature = "server")]
+    match super::awesome::awesome_search_documents() {
+        Ok(awesome) => documents.extend(awesome),
+        Err(err) => dioxus::logger::tracing::error!("Failed to index the awesome list: {err}"),
+    }
+
+    documents
+}
+
+/// Search the docs on the server for clients that can't load the index themselves, like editor
+/// plugins and chat bots. Served at `/api/search?q=use_signal&version=0.7&mode=code`, where
+/// `version` and `mode` are optional
+#[cfg(feature = "fullstack")]
+#[get("/api/search?q&version&mode")]
+pub async fn search_docs(
+    q: String,
+    version: Option<String>,
+    mode: Option<dioxus_search::SearchMode>,
+) -> Result<dioxus_search::SearchResponse, ServerFnError> {
+    let request = dioxus_search::SearchRequest {
+        q,
+        version,
+        mode: mode.unwrap_or_default(),
+    };
+    let response = search_api().search(&request).map_err(ServerFnError::new)?;
+    Ok((*response).clone())
+}
+
+/// Record a search a reader made, so we know which pages readers look for and can't find. Only
+/// the query, the version, the number of results and the rank of the opened result are stored.
+/// List the queries without results with
+/// `cargo run -p dioxus-search --bin search-report -- search_analytics.jsonl`
+#[cfg(feature = "fullstack")]
+#[server(endpoint = "search_analytics")]
+pub async fn record_search(event: dioxus_search::SearchEvent) -> Result<(), ServerFnError> {
+    match search_analytics_log() {
+        Some(log) => log.record(event).map_err(ServerFnError::new),
+        None => Ok(()),
+    }
+}
+
+/// The log searches are recorded in. It is written to `$SEARCH_ANALYTICS_PATH`, or next to the
+/// public directory so it isn't served. Only the facets the search shows are accepted
+#[cfg(feature = "server")]
+fn search_analytics_log() -> Option<&'static dioxus_search::AnalyticsLog> {
+    use dioxus::logger::tracing;
+
+    static LOG: std::sync::OnceLock<Option<dioxus_search::AnalyticsLog>> =
+        std::sync::OnceLock::new();
+    LOG.get_or_init(|| {
+        let path = std::env::var_os("SEARCH_ANALYTICS_PATH")
+            .map(std::path::PathBuf::from)
+            .unwrap_or_else(|| crate::static_dir().with_file_name("search_analytics.jsonl"));
+        dioxus_search::AnalyticsLog::open(&path)
+            .map(|log| {
+                log.with_facets(
+                    super::nav::SEARCH_VERSIONS
+                        .into_iter()
+                        .chain(super::nav::SEARCH_CONTENT),
+                )
+            })
+            .map_err(|err| {
+                tracing::error!("Search analytics are disabled, {}: {err}", path.display())
+            })
+            .ok()
+    })
+    .as_ref()
+}
+
+/// The search API for every version of the docs and the blog and community pages, loaded from
+/// the indexes written by [`generate_search_index`] the first time it is used. It searches the
+/// same facets as the search in the navbar
+#[cfg(feature = "server")]
+fn search_api() -> &'static dioxus_search::SearchApi<crate::Route> {
+    use dioxus::logger::tracing;
+
+    static SEARCH_API: std::sync::OnceLock<dioxus_search::SearchApi<crate::Route>> =
+        std::sync::OnceLock::new();
+    SEARCH_API.get_or_init(|| {
+        let directory = crate::static_dir().join("assets").join("dioxus_search");
+        let mut api = dioxus_search::SearchApi::new();
+        let facets = super::nav::SEARCH_VERSIONS
+            .into_iter()
+            .chain(super::nav::SEARCH_CONTENT);
+        for facet in facets {
+            let name = format!("searchable_{}", facet.replace('.', "_"));
+            let path = directory.join(format!("index_{name}.bin"));
+            let index = std::fs::read(&path)
+                .map_err(|err| format!("Failed to read {}: {err}", path.display()))
+                .and_then(|bytes| {
+                    dioxus_search::SearchIndex::from_compressed(&name, &bytes)
+                        .map_err(|err| err.to_string())
+                });
+            match index {
+                Ok(index) => api.insert(facet, index),
+                Err(err) => tracing::error!("Search is disabled for {facet}: {err}"),
+            }
         }
+        api
+    })
+}
+
+#[cfg(all(test, feature = "server"))]
+mod tests {
+    use dioxus::fullstack::{
+        axum::Router,
+        body::Body,
+        http::{Request, StatusCode},
+    };
+    use dioxus::prelude::DioxusRouterExt;
+    use tower::ServiceExt;
+
+    fn search_request(method: &str, uri: &str) -> Request<Body> {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn search_is_served_over_get() {
+        let router = Router::new().register_server_functions();
+
+        // Clients that can't call server functions search with a plain GET and a query string
+        let response = router
+            .clone()
+            .oneshot(search_request("GET", "/api/search?q=use_signal&mode=code"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = dioxus::fullstack::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let response: dioxus_search::SearchResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(response.query, "use_signal");
+
+        let response = router
+            .oneshot(search_request("POST", "/api/search?q=use_signal"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
     }
 }
@@ -151,7 +151,7 @@ impl AnyBookRoute for router_03::BookRoute {
     }
 
     fn full_version() -> &'static str {
-        "0.3.2"
+        Self::DIOXUS_VERSION
     }
 
     fn index() -> Self {
@@ -192,7 +192,7 @@ impl AnyBookRoute for router_04::BookRoute {
     }
 
     fn full_version() -> &'static str {
-        "0.4.3"
+        Self::DIOXUS_VERSION
     }
 
     fn index() -> Self {
@@ -233,7 +233,7 @@ impl AnyBookRoute for router_05::BookRoute {
     }
 
     fn full_version() -> &'static str {
-        "0.5.7"
+        Self::DIOXUS_VERSION
     }
 
     fn index() -> Self {
@@ -274,7 +274,7 @@ impl AnyBookRoute for router_06::BookRoute {
         "0.6"
     }
     fn full_version() -> &'static str {
-        "0.6.3"
+        Self::DIOXUS_VERSION
     }
     fn index() -> Self {
         Self::Index {
@@ -314,7 +314,7 @@ impl AnyBookRoute for router_07::BookRoute {
         "0.7"
     }
     fn full_version() -> &'static str {
-        "0.7.0"
+        Self::DIOXUS_VERSION
     }
     fn index() -> Self {
         Self::Index {
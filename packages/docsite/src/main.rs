@@ -16,6 +16,14 @@ fn main() {
         div { class: "mx-2 mx-4" }
     };
 
+    // The search worker loads this same wasm module, so it hosts the search indexes instead of
+    // launching the app
+    #[cfg(target_arch = "wasm32")]
+    if dioxus_search::in_web_worker() {
+        dioxus_search::run_search_worker(components::nav::fetch_search_shard);
+        return;
+    }
+
     // If we are just building the search index, we don't need to launch the app
     #[cfg(feature = "server")]
     if std::env::args().any(|arg| arg == "--generate-search-index") {
@@ -0,0 +1,206 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use anyhow::Context;
+use mdbook_shared::BookVars;
+use serde::Deserialize;
+
+/// The name of the symbol map file in the root of a book
+pub const API_LINKS_FILE: &str = "api.toml";
+
+/// A map from API item paths to their documentation pages.
+///
+/// The map is loaded from an `api.toml` file next to the `book.toml` of a book:
+/// ```toml
+/// base-url = "https://docs.rs/dioxus/{{ dioxus_version }}/"
+///
+/// [items]
+/// "dioxus::prelude::use_signal" = "dioxus/prelude/fn.use_signal.html"
+/// "dioxus::prelude::Signal::read" = "dioxus/prelude/struct.Signal.html#method.read"
+/// ```
+///
+/// Book variables like `{{ dioxus_version }}` are substituted into the urls, so the links follow
+/// the version of the docs.
+///
+/// Items are looked up either by their full path or by any unambiguous trailing part of
+/// their path, so `Signal::read` and `read` both resolve to `dioxus::prelude::Signal::read`
+/// as long as no other item ends with the same segments.
+#[derive(Debug, Default)]
+pub struct ApiLinks {
+    base_url: String,
+    items: BTreeMap<String, String>,
+    suffixes: HashMap<String, Vec<String>>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct ApiLinksFile {
+    #[serde(default)]
+    base_url: String,
+    #[serde(default)]
+    items: BTreeMap<String, String>,
+}
+
+impl ApiLinks {
+    /// Load the symbol map for the book at `book_path`. Books without an `api.toml` get an empty map.
+    pub fn load(book_path: &Path, vars: &BookVars) -> anyhow::Result<Self> {
+        let path = book_path.join(API_LINKS_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let contents = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read the API symbol map at {}", path.display()))?;
+        Self::from_toml(&contents, vars)
+            .with_context(|| format!("Failed to parse the API symbol map at {}", path.display()))
+    }
+
+    /// Parse a symbol map from the contents of an `api.toml` file, substituting the book
+    /// variables into its urls
+    pub fn from_toml(contents: &str, vars: &BookVars) -> anyhow::Result<Self> {
+        let ApiLinksFile { base_url, items } = toml::from_str(contents)?;
+        let base_url = vars.substitute(&base_url)?;
+        let items = items
+            .into_iter()
+            .map(|(path, url)| Ok((path, vars.substitute(&url)?)))
+            .collect::<anyhow::Result<BTreeMap<_, _>>>()?;
+
+        let mut suffixes: HashMap<String, Vec<String>> = HashMap::new();
+        for path in items.keys() {
+            let segments: Vec<_> = path.split("::").collect();
+            for start in 1..segments.len() {
+                suffixes
+                    .entry(segments[start..].join("::"))
+                    .or_default()
+                    .push(path.clone());
+            }
+        }
+
+        Ok(Self {
+            base_url,
+            items,
+            suffixes,
+        })
+    }
+
+    /// Returns true if the map doesn't contain any items
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Resolve an item path to the url of its documentation page
+    pub fn resolve(&self, path: &str) -> Option<String> {
+        let path = path.trim();
+        let full_path = match self.items.contains_key(path) {
+            true => path,
+            // Only resolve a partial path if there is exactly one item it could refer to
+            false => match self.suffixes.get(path).map(Vec::as_slice) {
+                Some([full_path]) => full_path.as_str(),
+                _ => return None,
+            },
+        };
+
+        let url = &self.items[full_path];
+        if url.starts_with("http://") || url.starts_with("https://") {
+            return Some(url.clone());
+        }
+        Some(format!(
+            "{}/{}",
+            self.base_url.trim_end_matches('/'),
+            url.trim_start_matches('/')
+        ))
+    }
+
+    /// Resolve the contents of an inline code span like `use_signal`, `use_signal()`,
+    /// `Signal::read`, `rsx!` or `#[server]` to the url of its documentation page.
+    ///
+    /// Code that doesn't look like a single item path is never linked.
+    pub fn resolve_inline_code(&self, code: &str) -> Option<String> {
+        if self.is_empty() {
+            return None;
+        }
+
+        let code = code.trim();
+        let code = code
+            .strip_prefix("#[")
+            .and_then(|code| code.strip_suffix(']'))
+            .unwrap_or(code);
+        let code = code.strip_suffix("()").unwrap_or(code);
+        let code = code.strip_suffix('!').unwrap_or(code);
+
+        let is_path = !code.is_empty()
+            && code.split("::").all(|segment| {
+                let mut chars = segment.chars();
+                chars
+                    .next()
+                    .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+                    && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+            });
+        if !is_path {
+            return None;
+        }
+
+        self.resolve(code)
+    }
+}
+
+#[test]
+fn resolve_api_links() {
+    let vars = BookVars::from_toml(
+        r#"
+[preprocessor.vars]
+dioxus_version = "0.7.0"
+"#,
+    )
+    .unwrap();
+    let links = ApiLinks::from_toml(
+        r#"
+base-url = "https://docs.rs/dioxus/{{ dioxus_version }}/"
+
+[items]
+"dioxus::prelude::use_signal" = "dioxus/prelude/fn.use_signal.html"
+"dioxus::prelude::Signal" = "dioxus/prelude/struct.Signal.html"
+"dioxus::prelude::Signal::read" = "dioxus/prelude/struct.Signal.html#method.read"
+"dioxus::prelude::Memo::read" = "dioxus/prelude/struct.Memo.html#method.read"
+"dioxus::prelude::server" = "dioxus/prelude/attr.server.html"
+"dioxus::prelude::rsx" = "dioxus/prelude/macro.rsx.html"
+"dioxus_router::Routable" = "https://docs.rs/dioxus-router/{{ dioxus_version }}/dioxus_router/trait.Routable.html"
+"#,
+        &vars,
+    )
+    .unwrap();
+
+    assert_eq!(
+        links.resolve_inline_code("use_signal").as_deref(),
+        Some("https://docs.rs/dioxus/0.7.0/dioxus/prelude/fn.use_signal.html")
+    );
+    assert_eq!(
+        links.resolve_inline_code("use_signal()").as_deref(),
+        Some("https://docs.rs/dioxus/0.7.0/dioxus/prelude/fn.use_signal.html")
+    );
+    assert_eq!(
+        links.resolve_inline_code("Signal::read").as_deref(),
+        Some("https://docs.rs/dioxus/0.7.0/dioxus/prelude/struct.Signal.html#method.read")
+    );
+    assert_eq!(
+        links.resolve_inline_code("#[server]").as_deref(),
+        Some("https://docs.rs/dioxus/0.7.0/dioxus/prelude/attr.server.html")
+    );
+    assert_eq!(
+        links.resolve_inline_code("rsx!").as_deref(),
+        Some("https://docs.rs/dioxus/0.7.0/dioxus/prelude/macro.rsx.html")
+    );
+    assert_eq!(
+        links.resolve("dioxus_router::Routable").as_deref(),
+        Some("https://docs.rs/dioxus-router/0.7.0/dioxus_router/trait.Routable.html")
+    );
+
+    // `read` is ambiguous between `Signal::read` and `Memo::read`
+    assert_eq!(links.resolve_inline_code("read"), None);
+    // Expressions are never linked
+    assert_eq!(links.resolve_inline_code("use_signal(|| 0)"), None);
+    assert_eq!(links.resolve_inline_code("dx serve"), None);
+
+    // Urls can't use variables the book doesn't declare
+    assert!(ApiLinks::from_toml(r#"base-url = "{{ dioxus_versoin }}""#, &vars).is_err());
+}
@@ -4,7 +4,7 @@ use std::path::PathBuf;
 
 use anyhow::Context;
 use convert_case::{Case, Casing};
-use mdbook_shared::MdBook;
+use mdbook_shared::{BookVars, MdBook};
 use proc_macro2::Ident;
 use proc_macro2::Span;
 use proc_macro2::TokenStream as TokenStream2;
@@ -12,15 +12,21 @@ use quote::quote;
 use quote::ToTokens;
 use syn::LitStr;
 
+use crate::api_links::ApiLinks;
 use crate::transform_book::write_book_with_routes;
 
+mod api_links;
 mod rsx;
 mod transform_book;
 
-pub fn make_docs_from_ws(version: &str) {
+/// Generate the router for the book in `docs-src/{version}` into `src/docsgen.rs`.
+///
+/// Every variable in `required_vars` must be declared in the `[preprocessor.vars]` table of the
+/// book.toml, or the generated router is a compile error.
+pub fn make_docs_from_ws(version: &str, required_vars: &[&str]) {
     let mdbook_dir = PathBuf::from("../../docs-src").join(version);
     let out_dir = std::env::current_dir().unwrap().join("src");
-    let mut out = generate_router_build_script(mdbook_dir);
+    let mut out = generate_router_build_script(mdbook_dir, required_vars);
     out.push_str("use dioxus_docs_examples::*;\n");
     out.push_str("use dioxus::prelude::*;\n");
     let filename = format!("docsgen.rs");
@@ -28,8 +34,12 @@ pub fn make_docs_from_ws(version: &str) {
 }
 
 /// Generate the contents of the mdbook from a router
-pub fn generate_router_build_script(mdbook_dir: PathBuf) -> String {
-    let file_src = generate_router_as_file(mdbook_dir.clone(), MdBook::new(mdbook_dir).unwrap());
+pub fn generate_router_build_script(mdbook_dir: PathBuf, required_vars: &[&str]) -> String {
+    let file_src = generate_router_as_file(
+        mdbook_dir.clone(),
+        MdBook::new(mdbook_dir).unwrap(),
+        required_vars,
+    );
     prettyplease::unparse(&file_src)
 }
 
@@ -57,24 +67,59 @@ pub fn load_book_from_fs(
 pub fn generate_router_as_file(
     mdbook_dir: PathBuf,
     book: mdbook_shared::MdBook<PathBuf>,
+    required_vars: &[&str],
 ) -> syn::File {
-    let router = generate_router(mdbook_dir, book);
+    let router = generate_router(mdbook_dir, book, required_vars);
 
     syn::parse_quote! {
         #router
     }
 }
 
-pub fn generate_router(mdbook_dir: PathBuf, book: mdbook_shared::MdBook<PathBuf>) -> TokenStream2 {
+/// Generate the router for a book. Each variable of the book gets a constant on the router, like
+/// `BookRoute::DIOXUS_VERSION`, and every variable in `required_vars` must be declared.
+pub fn generate_router(
+    mdbook_dir: PathBuf,
+    book: mdbook_shared::MdBook<PathBuf>,
+    required_vars: &[&str],
+) -> TokenStream2 {
     let mdbook = write_book_with_routes(&book);
 
+    let vars = match BookVars::load(&mdbook_dir) {
+        Ok(vars) => vars,
+        Err(err) => {
+            let err = format!("{err:#}");
+            return quote! { compile_error!(#err); };
+        }
+    };
+    for name in required_vars {
+        if let Err(err) = vars.require(name) {
+            let err = format!("{err:#}");
+            return quote! { compile_error!(#err); };
+        }
+    }
+
+    let api_links = match ApiLinks::load(&mdbook_dir, &vars) {
+        Ok(api_links) => api_links,
+        Err(err) => {
+            let err = format!("{err:#}");
+            return quote! { compile_error!(#err); };
+        }
+    };
+
     let mut page_markdown_map = BTreeMap::new();
 
     let book_pages = book.pages().iter().map(|(_, page)| {
         let name = path_to_route_variant(&page.url).unwrap();
 
         // Rsx doesn't work very well in macros because the path for all the routes generated point to the same characters. We manually expand rsx here to get around that issue.
-        match rsx::parse_markdown(mdbook_dir.clone(), page.url.clone(), &page.raw) {
+        match rsx::parse_markdown(
+            mdbook_dir.clone(),
+            page.url.clone(),
+            &page.raw,
+            &api_links,
+            &vars,
+        ) {
             Ok(parsed) => {
                 // insert the parsed markdown into the page_markdown map
                 page_markdown_map.insert(page.id.0, parsed.resolved_markdown);
@@ -219,6 +264,18 @@ pub fn generate_router(mdbook_dir: PathBuf, book: mdbook_shared::MdBook<PathBuf>
         }
     };
 
+    let var_names: Vec<_> = vars.iter().map(|(name, _)| name).collect();
+    let var_values: Vec<_> = vars.iter().map(|(_, value)| value).collect();
+    let var_consts = var_names.iter().map(|name| {
+        Ident::new(
+            &name.replace('-', "_").to_case(Case::UpperSnake),
+            Span::call_site(),
+        )
+    });
+    let var_docs = var_names.iter().map(|name| {
+        format!(" The `{name}` variable from the `[preprocessor.vars]` table of the book.toml")
+    });
+
     quote! {
         #[derive(Clone, Copy, dioxus_router::Routable, PartialEq, Eq, Hash, Debug, serde::Serialize, serde::Deserialize)]
         pub enum BookRoute {
@@ -228,6 +285,21 @@ pub fn generate_router(mdbook_dir: PathBuf, book: mdbook_shared::MdBook<PathBuf>
         impl BookRoute {
             #page_markdown
 
+            #(
+                #[doc = #var_docs]
+                pub const #var_consts: &'static str = #var_values;
+            )*
+
+            /// Get the value of a variable from the `[preprocessor.vars]` table of the book.toml
+            pub fn var(name: &str) -> Option<&'static str> {
+                match name {
+                    #(
+                        #var_names => Some(#var_values),
+                    )*
+                    _ => None,
+                }
+            }
+
             pub fn sections(&self) -> &'static [use_mdbook::mdbook_shared::Section] {
                 &self.page().sections
             }
@@ -1,4 +1,4 @@
-use mdbook_shared::get_book_content_path;
+use mdbook_shared::{get_book_content_path, BookVars, FrontMatter};
 use proc_macro2::{Span, TokenStream as TokenStream2};
 use quote::{quote, ToTokens};
 use std::{
@@ -13,6 +13,7 @@ use pulldown_cmark::{Alignment, CodeBlockKind, Event, Options, Parser, Tag};
 use syn::{parse_quote, parse_str, Ident};
 
 use crate::{
+    api_links::{ApiLinks, API_LINKS_FILE},
     path_to_route_enum, path_to_route_enum_with_section, to_upper_camel_case_for_ident,
     EmptyIdentError,
 };
@@ -68,7 +69,17 @@ pub fn parse_markdown(
     book_path: PathBuf,
     path: PathBuf,
     markdown: &str,
+    api_links: &ApiLinks,
+    vars: &BookVars,
 ) -> syn::Result<ParsedMarkdown> {
+    // The front matter is only used by search, so it isn't rendered
+    let (_, markdown) = FrontMatter::split(markdown);
+
+    // Substitute any `{{ variables }}` before parsing so they are replaced in both prose and code blocks
+    let markdown = vars
+        .substitute(markdown)
+        .map_err(|err| syn::Error::new(Span::call_site(), err))?;
+
     let mut options = Options::empty();
     options.insert(
         Options::ENABLE_TABLES
@@ -77,7 +88,7 @@ pub fn parse_markdown(
             | Options::ENABLE_TASKLISTS,
     );
 
-    let mut parser = Parser::new_ext(markdown, options);
+    let mut parser = Parser::new_ext(&markdown, options);
     let parser_by_ref = parser.by_ref().peekable();
     let mut resolved = ResolveCodeBlock::new(path.clone(), parser_by_ref);
     let all_resolved: Vec<_> = resolved.by_ref().collect();
@@ -109,6 +120,7 @@ pub fn parse_markdown(
         iter,
         book_path,
         path,
+        api_links,
         phantom: std::marker::PhantomData,
     };
     rsx_parser.parse()?;
@@ -139,6 +151,7 @@ struct RsxMarkdownParser<'a, I: Iterator<Item = Event<'a>>> {
     book_path: PathBuf,
     path: PathBuf,
     sections: Vec<Section>,
+    api_links: &'a ApiLinks,
     phantom: std::marker::PhantomData<&'a ()>,
 }
 
@@ -167,12 +180,8 @@ impl<'a, I: Iterator<Item = Event<'a>>> RsxMarkdownParser<'a, I> {
                 self.create_node(BodyNode::Text(parse_quote!(#text)));
             }
             pulldown_cmark::Event::Code(code) => {
-                let code = escape_text(&code);
-                self.create_node(parse_quote! {
-                    code {
-                        #code
-                    }
-                })
+                let node = self.inline_code(&code);
+                self.create_node(node)
             }
             pulldown_cmark::Event::Html(node) | pulldown_cmark::Event::InlineHtml(node) => {
                 let trimmed = node.trim();
@@ -244,12 +253,8 @@ impl<'a, I: Iterator<Item = Event<'a>>> RsxMarkdownParser<'a, I> {
                 }
                 Some(pulldown_cmark::Event::Code(code)) => {
                     let code = code.to_string();
-                    let code = escape_text(&code);
-                    self.create_node(parse_quote! {
-                        code {
-                            #code
-                        }
-                    });
+                    let node = self.inline_code(&code);
+                    self.create_node(node);
 
                     // Take the text or code event we just inserted
                     let _ = self.iter.next().unwrap();
@@ -263,6 +268,41 @@ impl<'a, I: Iterator<Item = Event<'a>>> RsxMarkdownParser<'a, I> {
         }
     }
 
+    /// Create a node for an inline code span, linking it to the API docs if it names a known item
+    fn inline_code(&self, code: &str) -> BodyNode {
+        let text = escape_text(code);
+        // Links can't be nested, so code inside of a link is never linked to the API docs
+        let url = match self.in_link() {
+            true => None,
+            false => self.api_links.resolve_inline_code(code),
+        };
+        match url {
+            Some(url) => {
+                let url = escape_text(&url);
+                parse_quote! {
+                    Link {
+                        to: #url,
+                        class: "api-link",
+                        code {
+                            #text
+                        }
+                    }
+                }
+            }
+            None => parse_quote! {
+                code {
+                    #text
+                }
+            },
+        }
+    }
+
+    fn in_link(&self) -> bool {
+        self.element_stack.iter().any(|node| {
+            matches!(node, BodyNode::Component(component) if component.name.is_ident("Link"))
+        })
+    }
+
     fn take_text(&mut self) -> String {
         let mut current_text = String::new();
         // pulldown_cmark will create a new text node for each newline. We insert a space
@@ -429,6 +469,19 @@ impl<'a, I: Iterator<Item = Event<'a>>> RsxMarkdownParser<'a, I> {
             } => {
                 let href = match ty {
                     pulldown_cmark::LinkType::Email => format!("mailto:{}", dest).to_token_stream(),
+                    _ if dest.starts_with("api:") => {
+                        let item = &dest["api:".len()..];
+                        let url = self.api_links.resolve(item).ok_or_else(|| {
+                            syn::Error::new(
+                                Span::call_site(),
+                                format!(
+                                    "Failed to resolve the API link {dest} in {}. Add {item} to the {API_LINKS_FILE} file in the root of the book",
+                                    self.path.display()
+                                ),
+                            )
+                        })?;
+                        escape_text(&url).to_token_stream()
+                    }
                     _ => {
                         if dest.starts_with("http") || dest.starts_with("https") {
                             escape_text(&dest).to_token_stream()
@@ -914,6 +967,7 @@ Some assets:
         iter: parser.by_ref().peekable(),
         path: PathBuf::from("../../example-book/en/chapter_1.md"),
         book_path: PathBuf::from("../../example-book"),
+        api_links: &ApiLinks::default(),
         phantom: std::marker::PhantomData,
     };
 
@@ -974,6 +1028,7 @@ We can get a navigator with the `navigator` function which returns a `Navigator`
         iter: parser.by_ref().peekable(),
         path: PathBuf::from("../../example-book/en/chapter_1.md"),
         book_path: PathBuf::from("../../example-book"),
+        api_links: &ApiLinks::default(),
         phantom: std::marker::PhantomData,
     };
 
@@ -1041,6 +1096,7 @@ fn parse_code_headers() {
         iter: parser.by_ref().peekable(),
         path: PathBuf::from("../../example-book/en/chapter_1.md"),
         book_path: PathBuf::from("../../example-book"),
+        api_links: &ApiLinks::default(),
         phantom: std::marker::PhantomData,
     };
 
@@ -1068,6 +1124,81 @@ fn parse_code_headers() {
     assert_eq!(expected_tokens.body, body.body);
 }
 
+#[test]
+fn parse_api_links() {
+    let markdown = r#"Call `use_signal` or [`Signal`](api:dioxus::prelude::Signal)."#;
+
+    let api_links = ApiLinks::from_toml(
+        r#"
+base-url = "https://docs.rs/dioxus/0.7.0"
+
+[items]
+"dioxus::prelude::use_signal" = "dioxus/prelude/fn.use_signal.html"
+"dioxus::prelude::Signal" = "dioxus/prelude/struct.Signal.html"
+"#,
+        &BookVars::default(),
+    )
+    .unwrap();
+
+    let mut parser = Parser::new_ext(markdown, Options::empty());
+
+    let mut rsx_parser = RsxMarkdownParser {
+        element_stack: vec![],
+        root_nodes: vec![],
+        current_table: vec![],
+        sections: vec![],
+        in_table_header: false,
+        iter: parser.by_ref().peekable(),
+        path: PathBuf::from("../../example-book/en/chapter_1.md"),
+        book_path: PathBuf::from("../../example-book"),
+        api_links: &api_links,
+        phantom: std::marker::PhantomData,
+    };
+
+    rsx_parser.parse().unwrap();
+    while !rsx_parser.element_stack.is_empty() {
+        rsx_parser.end_node();
+    }
+
+    let body = CallBody::new(TemplateBody::new(rsx_parser.root_nodes));
+
+    let expected_tokens: CallBody = parse_quote! {
+        p {
+            "Call  "
+            Link {
+                to: "https://docs.rs/dioxus/0.7.0/dioxus/prelude/fn.use_signal.html",
+                class: "api-link",
+                code { "use_signal" }
+            }
+            " or "
+            Link {
+                to: "https://docs.rs/dioxus/0.7.0/dioxus/prelude/struct.Signal.html",
+                code { "Signal" }
+            }
+            "."
+        }
+    };
+
+    assert_eq!(expected_tokens.body, body.body);
+
+    // Explicit links to items that aren't in the symbol map are errors
+    let markdown = r#"See [`Store`](api:dioxus::prelude::Store)."#;
+    let mut parser = Parser::new_ext(markdown, Options::empty());
+    let mut rsx_parser = RsxMarkdownParser {
+        element_stack: vec![],
+        root_nodes: vec![],
+        current_table: vec![],
+        sections: vec![],
+        in_table_header: false,
+        iter: parser.by_ref().peekable(),
+        path: PathBuf::from("../../example-book/en/chapter_1.md"),
+        book_path: PathBuf::from("../../example-book"),
+        api_links: &api_links,
+        phantom: std::marker::PhantomData,
+    };
+    assert!(rsx_parser.parse().is_err());
+}
+
 #[test]
 fn syn_parsing_race() {
     let alt1 = "some_alt_text";
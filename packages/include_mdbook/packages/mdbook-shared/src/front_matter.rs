@@ -0,0 +1,77 @@
+/// Metadata at the top of a page between two `---` lines:
+///
+/// ```markdown
+/// ---
+/// keywords: state, hooks, use_signal
+/// language: pt-BR
+/// ---
+///
+/// # Signals
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FrontMatter {
+    /// Extra words the page should be found by in search
+    pub keywords: Vec<String>,
+    /// The language tag of the page, like `en` or `pt-BR`, if it isn't the language of the book
+    pub language: Option<String>,
+}
+
+impl FrontMatter {
+    /// Split the front matter from the start of a page. Returns the default front matter and the
+    /// whole page if it doesn't start with a front matter block.
+    pub fn split(markdown: &str) -> (Self, &str) {
+        let Some(rest) = markdown
+            .strip_prefix("---\n")
+            .or_else(|| markdown.strip_prefix("---\r\n"))
+        else {
+            return (Self::default(), markdown);
+        };
+
+        let mut front_matter = Self::default();
+        let mut offset = 0;
+        for line in rest.split_inclusive('\n') {
+            offset += line.len();
+            let line = line.trim();
+            if line == "---" {
+                return (front_matter, &rest[offset..]);
+            }
+            if let Some(keywords) = line.strip_prefix("keywords:") {
+                front_matter.keywords.extend(parse_list(keywords));
+            }
+            if let Some(language) = line.strip_prefix("language:") {
+                let language = language.trim().trim_matches(['"', '\'']);
+                front_matter.language = (!language.is_empty()).then(|| language.to_string());
+            }
+        }
+
+        // Without a closing line this is just a horizontal rule
+        (Self::default(), markdown)
+    }
+}
+
+/// Parse a list of comma separated values with optional brackets and quotes
+fn parse_list(value: &str) -> impl Iterator<Item = String> + '_ {
+    let value = value.trim();
+    let value = value
+        .strip_prefix('[')
+        .and_then(|value| value.strip_suffix(']'))
+        .unwrap_or(value);
+    value
+        .split(',')
+        .map(|item| item.trim().trim_matches(['"', '\'']).to_string())
+        .filter(|item| !item.is_empty())
+}
+
+#[test]
+fn split_front_matter() {
+    let (front_matter, rest) = FrontMatter::split(
+        "---\nkeywords: [state, \"use_signal\"]\nauthor: me\nlanguage: pt-BR\n---\n# Signals\n",
+    );
+    assert_eq!(front_matter.keywords, ["state", "use_signal"]);
+    assert_eq!(front_matter.language.as_deref(), Some("pt-BR"));
+    assert_eq!(rest, "# Signals\n");
+
+    let (front_matter, rest) = FrontMatter::split("---\n# Not front matter\n");
+    assert_eq!(front_matter, FrontMatter::default());
+    assert_eq!(rest, "---\n# Not front matter\n");
+}
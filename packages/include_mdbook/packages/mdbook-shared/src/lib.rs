@@ -6,3 +6,9 @@ pub use query::*;
 
 pub mod errors;
 pub use errors::*;
+
+mod front_matter;
+pub use front_matter::*;
+
+mod vars;
+pub use vars::*;
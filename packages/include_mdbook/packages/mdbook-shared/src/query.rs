@@ -167,7 +167,8 @@ impl MdBook<PathBuf> {
             )
         })?;
 
-        let parser = pulldown_cmark::Parser::new(&body);
+        let (_, markdown) = FrontMatter::split(&body);
+        let parser = pulldown_cmark::Parser::new(markdown);
 
         let mut last_heading = None;
 
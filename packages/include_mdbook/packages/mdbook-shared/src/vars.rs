@@ -0,0 +1,177 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use anyhow::Context;
+
+/// The variables that can be substituted into a book with `{{ name }}`.
+///
+/// Variables are declared in the `[preprocessor.vars]` table of the book.toml:
+/// ```toml
+/// [preprocessor.vars]
+/// dioxus_version = "0.7.0"
+/// ```
+///
+/// The `version` variable defaults to the name of the book directory (e.g. `0.7`) if it isn't declared.
+#[derive(Debug, Default, Clone)]
+pub struct BookVars {
+    vars: BTreeMap<String, String>,
+}
+
+impl BookVars {
+    /// Load the variables for the book at `book_path`
+    pub fn load(book_path: &Path) -> anyhow::Result<Self> {
+        let config_path = book_path.join("book.toml");
+        let mut vars = match config_path.exists() {
+            true => {
+                let contents = std::fs::read_to_string(&config_path)
+                    .with_context(|| format!("Failed to read {}", config_path.display()))?;
+                Self::from_toml(&contents)
+                    .with_context(|| format!("Failed to parse {}", config_path.display()))?
+            }
+            false => Self::default(),
+        };
+
+        if let Some(name) = book_path.file_name() {
+            vars.vars
+                .entry("version".to_string())
+                .or_insert_with(|| name.to_string_lossy().to_string());
+        }
+
+        Ok(vars)
+    }
+
+    /// Read the `[preprocessor.vars]` table from the contents of a book.toml
+    pub fn from_toml(contents: &str) -> anyhow::Result<Self> {
+        let config: toml::Table = toml::from_str(contents)?;
+        let table = config
+            .get("preprocessor")
+            .and_then(|preprocessors| preprocessors.get("vars"))
+            .and_then(|vars| vars.as_table());
+
+        let mut vars = BTreeMap::new();
+        for (name, value) in table.into_iter().flatten() {
+            let value = match value {
+                toml::Value::String(value) => value.clone(),
+                toml::Value::Integer(_) | toml::Value::Float(_) | toml::Value::Boolean(_) => {
+                    value.to_string()
+                }
+                _ => anyhow::bail!("The book variable {name} must be a string, number or boolean"),
+            };
+            vars.insert(name.clone(), value);
+        }
+
+        Ok(Self { vars })
+    }
+
+    /// Get the value of a variable
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.vars.get(name).map(String::as_str)
+    }
+
+    /// Get the value of a variable the book must declare
+    pub fn require(&self, name: &str) -> anyhow::Result<&str> {
+        self.get(name).ok_or_else(|| {
+            let available = self.vars.keys().cloned().collect::<Vec<_>>().join(", ");
+            anyhow::anyhow!(
+                "Unknown variable {{{{ {name} }}}}. Declare it in the [preprocessor.vars] table of the book.toml. Available variables: {available}"
+            )
+        })
+    }
+
+    /// Iterate over all variables and their values
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.vars
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+    }
+
+    /// Replace every `{{ name }}` in the markdown with the value of the variable.
+    ///
+    /// Preprocessor directives like `{{#include file.rs}}` and anything that isn't a plain
+    /// identifier (like `{{ secrets.TOKEN }}` in a GitHub workflow) are left untouched. A
+    /// variable can be written literally by escaping it as `\{{ name }}`.
+    pub fn substitute(&self, markdown: &str) -> anyhow::Result<String> {
+        let mut output = String::with_capacity(markdown.len());
+        let mut rest = markdown;
+
+        while let Some(start) = rest.find("{{") {
+            let (before, after) = rest.split_at(start);
+            let Some(end) = after.find("}}") else {
+                break;
+            };
+            let inner = &after[2..end];
+            let name = inner.trim();
+
+            if !is_var_name(name) {
+                output.push_str(before);
+                output.push_str(&after[..end + 2]);
+            } else if let Some(before) = before.strip_suffix('\\') {
+                output.push_str(before);
+                output.push_str(&after[..end + 2]);
+            } else {
+                let value = self.require(name)?;
+                output.push_str(before);
+                output.push_str(value);
+            }
+
+            rest = &after[end + 2..];
+        }
+
+        output.push_str(rest);
+        Ok(output)
+    }
+}
+
+fn is_var_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+#[test]
+fn substitute_vars() {
+    let vars = BookVars::from_toml(
+        r#"
+[book]
+title = "Dioxus Documentation"
+
+[preprocessor.vars]
+version = "0.7"
+dioxus_version = "0.7.0"
+"#,
+    )
+    .unwrap();
+
+    let markdown = r#"Welcome to the {{version}} docs!
+
+```toml
+dioxus = { version = "{{ dioxus_version }}" }
+```
+
+```rust
+{{#include src/doc_examples/hello_world.rs}}
+```
+
+Write \{{ version }} to insert the version. Secrets look like {{ secrets.FLY_API_TOKEN }}."#;
+
+    assert_eq!(
+        vars.substitute(markdown).unwrap(),
+        r#"Welcome to the 0.7 docs!
+
+```toml
+dioxus = { version = "0.7.0" }
+```
+
+```rust
+{{#include src/doc_examples/hello_world.rs}}
+```
+
+Write {{ version }} to insert the version. Secrets look like {{ secrets.FLY_API_TOKEN }}."#
+    );
+
+    assert!(vars.substitute("{{ dioxus_versoin }}").is_err());
+    assert_eq!(vars.require("dioxus_version").unwrap(), "0.7.0");
+    assert!(vars.require("dioxus_versoin").is_err());
+}
@@ -2,10 +2,12 @@ use std::ops::{Deref, DerefMut};
 
 use serde::{Deserialize, Serialize};
 
+use crate::ProjectFiles;
+
 /// API request to share a project.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ShareProjectReq {
-    pub code: String,
+    pub files: ProjectFiles,
 }
 
 /// API response for sharing a project.
@@ -18,7 +20,7 @@ pub struct ShareProjectRes {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct GetSharedProjectRes {
     pub id: String,
-    pub code: String,
+    pub files: ProjectFiles,
 }
 
 /// An api client for the Dioxus Playground server.
@@ -1,4 +1,6 @@
 use reqwest::StatusCode;
+use std::collections::BTreeMap;
+use thiserror::Error;
 use uuid::Uuid;
 
 use crate::{
@@ -6,25 +8,69 @@ use crate::{
     AppError,
 };
 
+/// The file every project has, which `dx` builds.
+pub const MAIN_FILE: &str = "src/main.rs";
+
+/// The optional file a project declares its dependencies in.
+pub const MANIFEST_FILE: &str = "Cargo.toml";
+
+/// The files of a project by their path from the project root, like `src/main.rs`.
+pub type ProjectFiles = BTreeMap<String, String>;
+
+/// A file path a project can't have.
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum ProjectError {
+    #[error("projects must have a `{MAIN_FILE}` file")]
+    MissingMainFile,
+
+    #[error("`{0}` is not a valid project file; files must be `.rs` files in `src/`, assets in `assets/` or `Cargo.toml`")]
+    InvalidPath(String),
+}
+
+/// A project as a small tree of files, with at least a [`MAIN_FILE`].
 #[derive(Debug, Clone, PartialEq)]
 pub struct Project {
     pub description: Option<String>,
     pub path: String,
-    contents: String,
+    files: ProjectFiles,
     pub prebuilt: bool,
     id: Uuid,
     shared_id: Option<String>,
 }
 
 impl Project {
+    /// Create a project with `contents` as its [`MAIN_FILE`].
     pub fn new(contents: impl ToString, description: Option<String>, path: Option<String>) -> Self {
-        let contents = contents.to_string();
+        let files = ProjectFiles::from([(MAIN_FILE.to_string(), contents.to_string())]);
+        Self::from_valid_files(files, description, path)
+    }
 
+    /// Create a project from its files, checking every path with [`validate_path`].
+    pub fn from_files(
+        files: ProjectFiles,
+        description: Option<String>,
+        path: Option<String>,
+    ) -> Result<Self, ProjectError> {
+        if !files.contains_key(MAIN_FILE) {
+            return Err(ProjectError::MissingMainFile);
+        }
+        for path in files.keys() {
+            validate_path(path)?;
+        }
+
+        Ok(Self::from_valid_files(files, description, path))
+    }
+
+    fn from_valid_files(
+        files: ProjectFiles,
+        description: Option<String>,
+        path: Option<String>,
+    ) -> Self {
         // Generate a unique id for the example.
-        let id = Uuid::new_v3(&Uuid::NAMESPACE_URL, contents.as_bytes());
+        let id = files_id(&files);
         Self {
             prebuilt: false,
-            contents,
+            files,
             description,
             path: path.unwrap_or("main.rs".to_string()),
             id,
@@ -36,13 +82,47 @@ impl Project {
         self.id
     }
 
+    /// The contents of the [`MAIN_FILE`].
     pub fn contents(&self) -> String {
-        self.contents.clone()
+        self.file(MAIN_FILE).unwrap_or_default().to_string()
     }
 
+    /// Set the contents of the [`MAIN_FILE`].
     pub fn set_contents(&mut self, new_contents: impl ToString) {
-        self.contents = new_contents.to_string();
+        self.files
+            .insert(MAIN_FILE.to_string(), new_contents.to_string());
+        self.shared_id = None;
+    }
+
+    pub fn files(&self) -> &ProjectFiles {
+        &self.files
+    }
+
+    pub fn into_files(self) -> ProjectFiles {
+        self.files
+    }
+
+    pub fn file(&self, path: &str) -> Option<&str> {
+        self.files.get(path).map(String::as_str)
+    }
+
+    /// Add or change a file.
+    pub fn set_file(&mut self, path: &str, contents: impl ToString) -> Result<(), ProjectError> {
+        validate_path(path)?;
+        self.files.insert(path.to_string(), contents.to_string());
         self.shared_id = None;
+        Ok(())
+    }
+
+    /// Remove a file. The [`MAIN_FILE`] can't be removed.
+    pub fn remove_file(&mut self, path: &str) -> Result<(), ProjectError> {
+        if path == MAIN_FILE {
+            return Err(ProjectError::MissingMainFile);
+        }
+        if self.files.remove(path).is_some() {
+            self.shared_id = None;
+        }
+        Ok(())
     }
 
     /// Retrieve the shared project information from a share code.
@@ -56,16 +136,11 @@ impl Project {
 
         // Decode
         let shared = res.json::<GetSharedProjectRes>().await?;
-        let id = Uuid::new_v3(&Uuid::NAMESPACE_URL, shared.code.as_bytes());
+        let mut project = Self::from_files(shared.files, None, None)
+            .map_err(|error| AppError::Parse(Box::new(error)))?;
+        project.shared_id = Some(shared.id);
 
-        Ok(Self {
-            description: None,
-            path: "main.rs".to_string(),
-            contents: shared.code.clone(),
-            prebuilt: false,
-            id,
-            shared_id: Some(shared.id),
-        })
+        Ok(project)
     }
 
     pub async fn share_project(&mut self, client: &ApiClient) -> Result<String, AppError> {
@@ -79,7 +154,7 @@ impl Project {
         let res = client
             .post(url)
             .json(&ShareProjectReq {
-                code: self.contents.clone(),
+                files: self.files.clone(),
             })
             .send()
             .await?;
@@ -90,3 +165,125 @@ impl Project {
         Ok(res.id)
     }
 }
+
+/// Check that a project can have a file at `path`.
+///
+/// Paths are relative to the project root with `/` separators. Projects can have Rust files in
+/// `src/`, assets, like stylesheets, in `assets/` and a [`MANIFEST_FILE`] for dependencies. Each
+/// part of a path is made of ASCII letters, digits, `_`, `-` and `.`, so paths can't leave the
+/// project.
+pub fn validate_path(path: &str) -> Result<(), ProjectError> {
+    if path == MANIFEST_FILE {
+        return Ok(());
+    }
+
+    let invalid = || ProjectError::InvalidPath(path.to_string());
+    let parts: Vec<&str> = path.split('/').collect();
+
+    let valid_parts = parts.iter().all(|part| {
+        !part.is_empty()
+            && !part.starts_with('.')
+            && part
+                .bytes()
+                .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'_' | b'-' | b'.'))
+    });
+    if !valid_parts || parts.len() < 2 {
+        return Err(invalid());
+    }
+
+    match parts[0] {
+        "src" if path.ends_with(".rs") => Ok(()),
+        "assets" => Ok(()),
+        _ => Err(invalid()),
+    }
+}
+
+/// The id of a project's files.
+///
+/// A project with only a main file has the id of the main file's contents, so single file
+/// projects keep the ids they had before projects had several files.
+fn files_id(files: &ProjectFiles) -> Uuid {
+    if let (1, Some(main)) = (files.len(), files.get(MAIN_FILE)) {
+        return Uuid::new_v3(&Uuid::NAMESPACE_URL, main.as_bytes());
+    }
+
+    let mut bytes = Vec::new();
+    for (path, contents) in files {
+        bytes.extend_from_slice(path.as_bytes());
+        bytes.push(0);
+        bytes.extend_from_slice(contents.as_bytes());
+        bytes.push(0);
+    }
+    Uuid::new_v3(&Uuid::NAMESPACE_URL, &bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn files(files: &[(&str, &str)]) -> ProjectFiles {
+        files
+            .iter()
+            .map(|(path, contents)| (path.to_string(), contents.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn validates_paths() {
+        for path in [
+            "src/main.rs",
+            "src/components.rs",
+            "src/components/button.rs",
+            "assets/main.css",
+            "assets/images/logo.png",
+            "Cargo.toml",
+        ] {
+            assert_eq!(validate_path(path), Ok(()), "{path}");
+        }
+
+        for path in [
+            "main.rs",
+            "Cargo.lock",
+            "src/Cargo.toml",
+            "build.rs",
+            "src/notes.txt",
+            "src/../Cargo.toml",
+            "assets/../../etc/passwd",
+            "/src/main.rs",
+            "src//main.rs",
+            "src/.hidden.rs",
+            "assets\\main.css",
+            "target/main.rs",
+        ] {
+            assert!(validate_path(path).is_err(), "{path}");
+        }
+    }
+
+    #[test]
+    fn needs_a_main_file() {
+        let result = Project::from_files(files(&[("src/app.rs", "")]), None, None);
+        assert_eq!(result, Err(ProjectError::MissingMainFile));
+
+        let mut project = Project::new("fn main() {}", None, None);
+        assert!(project.remove_file(MAIN_FILE).is_err());
+    }
+
+    #[test]
+    fn ids_depend_on_every_file() {
+        let main = "fn main() {}";
+        let single = Project::new(main, None, None);
+        let from_files = Project::from_files(files(&[(MAIN_FILE, main)]), None, None).unwrap();
+        assert_eq!(single.id(), from_files.id());
+        assert_eq!(
+            single.id(),
+            Uuid::new_v3(&Uuid::NAMESPACE_URL, main.as_bytes())
+        );
+
+        let with_css = files(&[(MAIN_FILE, main), ("assets/main.css", "body {}")]);
+        let with_other_css = files(&[(MAIN_FILE, main), ("assets/main.css", "p {}")]);
+        let with_css = Project::from_files(with_css, None, None).unwrap();
+        let with_other_css = Project::from_files(with_other_css, None, None).unwrap();
+        assert_ne!(with_css.id(), single.id());
+        assert_ne!(with_css.id(), with_other_css.id());
+    }
+}
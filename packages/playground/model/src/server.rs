@@ -36,7 +36,7 @@ impl SocketMessage {
         let msg = self
             .as_json_string()
             .expect("socket message should be valid json");
-        ws::Message::Text(msg)
+        ws::Message::Text(msg.into())
     }
 }
 
@@ -45,7 +45,7 @@ impl TryFrom<ws::Message> for SocketMessage {
 
     fn try_from(value: ws::Message) -> Result<Self, Self::Error> {
         let text = value.into_text()?;
-        SocketMessage::try_from(text)
+        SocketMessage::try_from(text.to_string())
     }
 }
 
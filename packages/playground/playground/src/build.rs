@@ -1,6 +1,6 @@
 use crate::ws;
 use dioxus::prelude::*;
-use model::{AppError, CargoDiagnostic, SocketMessage};
+use model::{AppError, CargoDiagnostic, ProjectFiles, SocketMessage};
 use uuid::Uuid;
 
 #[derive(Debug, Clone, PartialEq)]
@@ -113,7 +113,7 @@ impl BuildState {
 pub async fn start_build(
     mut build: BuildState,
     socket_url: String,
-    code: String,
+    files: ProjectFiles,
 ) -> Result<bool, AppError> {
     // Reset build state
     if build.stage().is_running() {
@@ -124,7 +124,7 @@ pub async fn start_build(
 
     // Send socket compile request
     let mut socket = ws::Socket::new(&socket_url)?;
-    socket.send(SocketMessage::BuildRequest(code)).await?;
+    socket.send(SocketMessage::BuildRequest(files)).await?;
 
     // Handle socket messages
     loop {
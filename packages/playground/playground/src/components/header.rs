@@ -4,8 +4,9 @@ use crate::share_code::copy_share_link;
 use crate::{Errors, PlaygroundUrls};
 use dioxus::prelude::*;
 // use dioxus_sdk::utils::timing::use_debounce;
+use dioxus_document::eval;
 use model::api::ApiClient;
-use model::Project;
+use model::{Project, MAIN_FILE};
 use std::time::Duration;
 
 #[component]
@@ -15,11 +16,11 @@ pub fn Header(
     pane_left_width: Signal<Option<i32>>,
     pane_right_width: Signal<Option<i32>>,
     mut show_examples: Signal<bool>,
-    file_name: ReadOnlySignal<String>,
+    mut selected_file: Signal<String>,
 ) -> Element {
     let build = use_context::<BuildState>();
     let api_client = use_context::<Signal<ApiClient>>();
-    let project = use_context::<Signal<Project>>();
+    let mut project = use_context::<Signal<Project>>();
     let mut errors = use_context::<Errors>();
 
     let mut share_btn_text = use_signal(|| "Share");
@@ -43,7 +44,54 @@ pub fn Header(
                     onclick: move |_| show_examples.toggle(),
                     crate::components::icons::MenuIcon {}
                 }
-                button { class: "dxp-ctrl-btn dxp-file-btn dxp-selected-file", {file_name} }
+
+                // File tabs
+                for path in project.read().files().keys().cloned() {
+                    button {
+                        key: "{path}",
+                        class: "dxp-ctrl-btn dxp-file-btn",
+                        class: if selected_file() == path { "dxp-selected-file" },
+                        onclick: {
+                            let path = path.clone();
+                            move |_| selected_file.set(path.clone())
+                        },
+                        {file_label(&path)}
+                        if path != MAIN_FILE {
+                            span {
+                                class: "dxp-close-file-btn",
+                                title: "Remove file",
+                                onclick: {
+                                    let path = path.clone();
+                                    move |event: MouseEvent| {
+                                        event.stop_propagation();
+                                        let _ = project.write().remove_file(&path);
+                                        if selected_file() == path {
+                                            selected_file.set(MAIN_FILE.to_string());
+                                        }
+                                    }
+                                },
+                                "×"
+                            }
+                        }
+                    }
+                }
+
+                // New file button
+                button {
+                    class: "dxp-ctrl-btn dxp-file-btn dxp-new-file-btn",
+                    title: "New file",
+                    onclick: move |_| async move {
+                        let Some(path) = prompt_file_path().await else {
+                            return;
+                        };
+                        let result = project.write().set_file(&path, "");
+                        match result {
+                            Ok(()) => selected_file.set(path),
+                            Err(error) => errors.push_error(("Invalid File", error.to_string())),
+                        }
+                    },
+                    "+"
+                }
             }
 
             // Right pane header
@@ -104,3 +152,22 @@ pub fn Header(
         }
     }
 }
+
+/// The name of a file in its tab. Rust files are shown without `src/`, like `main.rs`.
+fn file_label(path: &str) -> &str {
+    path.strip_prefix("src/").unwrap_or(path)
+}
+
+/// Ask for the path of a new file, like `src/components.rs` or `assets/main.css`.
+async fn prompt_file_path() -> Option<String> {
+    let mut e = eval(
+        r#"
+        const path = prompt("New file path, like `src/components.rs` or `assets/main.css`:");
+        dioxus.send(path ?? "");
+        "#,
+    );
+
+    let path = e.recv::<String>().await.ok()?;
+    let path = path.trim();
+    (!path.is_empty()).then(|| path.to_string())
+}
@@ -1,7 +1,7 @@
 use crate::hotreload::HotReload;
 use dioxus::prelude::*;
 // use dioxus_sdk::utils::timing::UseDebounce;
-use model::{CargoDiagnostic, CargoLevel};
+use model::{CargoDiagnostic, CargoLevel, Project};
 use serde::{Deserialize, Serialize};
 use wasm_bindgen::prelude::*;
 
@@ -57,7 +57,7 @@ pub fn set_monaco_markers(diagnostics: Signal<Vec<CargoDiagnostic>>) {
 // pub fn on_monaco_load(
 //     folder: Asset,
 //     system_theme: SystemTheme,
-//     contents: &str,
+//     project: &Project,
 //     mut hot_reload: HotReload,
 //     mut monaco_ready: Signal<bool>,
 //     mut on_model_changed: UseDebounce<String>,
@@ -68,11 +68,11 @@ pub fn set_monaco_markers(diagnostics: Signal<Vec<CargoDiagnostic>>) {
 //         &monaco_prefix,
 //         super::EDITOR_ELEMENT_ID,
 //         system_theme,
-//         contents,
+//         &project.contents(),
 //         &on_ready_callback,
 //     );
 
-//     hot_reload.set_starting_code(contents);
+//     hot_reload.set_starting_project(project);
 
 //     let model_change_callback =
 //         Closure::new(move |new_code: String| on_model_changed.action(new_code));
@@ -1,4 +1,4 @@
-//! Simplified hot reloading for a single main.rs file.
+//! Simplified hot reloading for the Rust files of a project, keyed by their path.
 use dioxus::{logger::tracing::error, prelude::*};
 use dioxus_core::internal::{
     HotReloadTemplateWithLocation, HotReloadedTemplate, TemplateGlobalKey,
@@ -8,13 +8,15 @@ use dioxus_document::eval;
 use dioxus_html::HtmlCtx;
 use dioxus_rsx::CallBody;
 use dioxus_rsx_hotreload::{diff_rsx, ChangedRsx};
+use model::Project;
 use std::{collections::HashMap, fmt::Display, path::Path};
 use syn::spanned::Spanned as _;
 
-/// Atempts to hot reload and returns true if a full rebuild is needed.
-pub fn attempt_hot_reload(mut hot_reload: HotReload, new_code: &str) {
+/// Atempts to hot reload a change to the file at `path`, marking that a full rebuild is needed
+/// if it can't be hot reloaded.
+pub fn attempt_hot_reload(mut hot_reload: HotReload, path: &str, new_code: &str) {
     // Process any potential hot -eloadable changes and send them to the iframe web client.
-    let result = hot_reload.process_file_change(new_code.to_string());
+    let result = hot_reload.process_file_change(path, new_code.to_string());
     match result {
         Ok(templates) => {
             let hr_msg = HotReloadMsg {
@@ -51,7 +53,8 @@ pub fn attempt_hot_reload(mut hot_reload: HotReload, new_code: &str) {
 #[derive(Clone, Copy)]
 pub struct HotReload {
     needs_rebuild: Signal<bool>,
-    cached_parse: Signal<CachedParse>,
+    /// The last built or hot reloaded code of each Rust file by its path.
+    cached_parse: Signal<HashMap<String, CachedParse>>,
 }
 
 struct CachedParse {
@@ -62,12 +65,7 @@ struct CachedParse {
 impl HotReload {
     pub fn new() -> Self {
         Self {
-            cached_parse: {
-                Signal::new(CachedParse {
-                    raw: String::new(),
-                    templates: HashMap::new(),
-                })
-            },
+            cached_parse: Signal::new(HashMap::new()),
             needs_rebuild: Signal::new(true),
         }
     }
@@ -76,35 +74,52 @@ impl HotReload {
         self.needs_rebuild.set(needs_rebuild);
     }
 
-    pub fn set_starting_code(&mut self, code: &str) {
-        *self.cached_parse.write() = CachedParse {
-            raw: code.to_string(),
-            templates: HashMap::new(),
-        };
+    /// Set the code the project was built with.
+    pub fn set_starting_project(&mut self, project: &Project) {
+        *self.cached_parse.write() = project
+            .files()
+            .iter()
+            .filter(|(path, _)| path.ends_with(".rs"))
+            .map(|(path, code)| {
+                let cached = CachedParse {
+                    raw: code.clone(),
+                    templates: HashMap::new(),
+                };
+                (path.clone(), cached)
+            })
+            .collect();
     }
 
-    fn full_rebuild(&mut self, code: String) -> HotReloadError {
-        *self.cached_parse.write() = CachedParse {
-            raw: code,
-            templates: HashMap::new(),
-        };
+    fn full_rebuild(&mut self, path: &str, code: String) -> HotReloadError {
+        self.cached_parse.write().insert(
+            path.to_string(),
+            CachedParse {
+                raw: code,
+                templates: HashMap::new(),
+            },
+        );
         HotReloadError::NeedsRebuild
     }
 
     pub fn process_file_change(
         &mut self,
+        path: &str,
         new_code: String,
     ) -> Result<Vec<HotReloadTemplateWithLocation>, HotReloadError> {
-        let new_file = syn::parse_file(&new_code).map_err(|_err| HotReloadError::Parse)?;
-
-        let cached_file = {
-            let cached = &mut self.cached_parse.read();
-            syn::parse_file(&cached.raw).map_err(|_err| HotReloadError::Parse)?
+        // Only rsx in Rust files can be hot reloaded. New files need a rebuild too.
+        if !path.ends_with(".rs") {
+            return Err(HotReloadError::NeedsRebuild);
+        }
+        let Some(cached_raw) = self.cached_parse.read().get(path).map(|c| c.raw.clone()) else {
+            return Err(self.full_rebuild(path, new_code));
         };
 
+        let new_file = syn::parse_file(&new_code).map_err(|_err| HotReloadError::Parse)?;
+        let cached_file = syn::parse_file(&cached_raw).map_err(|_err| HotReloadError::Parse)?;
+
         let changes = match diff_rsx(&new_file, &cached_file) {
             Some(rsx_calls) => rsx_calls,
-            None => return Err(self.full_rebuild(new_code)),
+            None => return Err(self.full_rebuild(path, new_code)),
         };
 
         let mut out_templates = Vec::new();
@@ -118,7 +133,7 @@ impl HotReload {
                 continue;
             };
 
-            let template_location = template_location(old_start);
+            let template_location = template_location(path, old_start);
 
             // Returns a list of templates that are hotreloadable
             let hotreload_result = dioxus_rsx_hotreload::HotReloadResult::new::<HtmlCtx>(
@@ -129,10 +144,13 @@ impl HotReload {
 
             // if the template is not hotreloadable, we need to do a full rebuild
             let Some(results) = hotreload_result else {
-                return Err(self.full_rebuild(new_code));
+                return Err(self.full_rebuild(path, new_code));
             };
 
-            let mut cached = self.cached_parse.write();
+            let mut cached_parse = self.cached_parse.write();
+            let cached = cached_parse
+                .get_mut(path)
+                .expect("the file was cached above");
             for (index, template) in results.templates {
                 if template.roots.is_empty() {
                     continue;
@@ -140,7 +158,7 @@ impl HotReload {
 
                 // Create the key we're going to use to identify this template
                 let key = TemplateGlobalKey {
-                    file: "src/main.rs".to_string(),
+                    file: path.to_string(),
                     line: old_start.line,
                     column: old_start.column + 1,
                     index,
@@ -159,8 +177,8 @@ impl HotReload {
     }
 }
 
-fn template_location(old_start: proc_macro2::LineColumn) -> String {
-    let file = Path::new("src/main.rs");
+fn template_location(path: &str, old_start: proc_macro2::LineColumn) -> String {
+    let file = Path::new(path);
     let line = old_start.line;
     let column = old_start.column + 1;
 
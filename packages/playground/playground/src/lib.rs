@@ -6,7 +6,7 @@
 // // use dioxus_sdk::utils::timing::use_debounce;
 // use editor::monaco::{self, monaco_loader_src, set_monaco_markers};
 // use hotreload::{attempt_hot_reload, HotReload};
-// use model::{api::ApiClient, AppError, Project, SocketError};
+// use model::{api::ApiClient, AppError, Project, SocketError, MAIN_FILE};
 // use std::time::Duration;
 
 // // #[cfg(target_arch = "wasm32")]
@@ -51,10 +51,17 @@
 //     // Project dirty determines whether the Rust-project is synced with the project in the editor.
 //     let mut project = use_context_provider(|| Signal::new(example_projects::get_welcome_project()));
 //     let mut project_dirty = use_signal(|| false);
+//     // The file open in the editor.
+//     let mut selected_file = use_signal(|| MAIN_FILE.to_string());
+//     use_effect(move || {
+//         let _selected_file = selected_file();
+//         project_dirty.set(true);
+//     });
 //     use_effect(move || {
 //         if project_dirty() && monaco_ready() {
 //             let project = project.read();
-//             monaco::set_current_model_value(&project.contents());
+//             let contents = project.file(&selected_file.peek()).unwrap_or_default();
+//             monaco::set_current_model_value(contents);
 //             project_dirty.set(false);
 //         }
 //     });
@@ -67,6 +74,8 @@
 //                 let shared_project = Project::from_share_code(&api_client, share_code).await;
 //                 if let Ok(shared_project) = shared_project {
 //                     show_share_warning.set(true);
+//                     hot_reload.set_starting_project(&shared_project);
+//                     selected_file.set(MAIN_FILE.to_string());
 //                     project_dirty.set(true);
 //                     project.set(shared_project);
 //                 }
@@ -76,13 +85,14 @@
 
 //     // // Handle events when code changes.
 //     // let on_model_changed = use_debounce(Duration::from_millis(250), move |new_code: String| {
-//     //     // Update the project
-//     //     project.write().set_contents(new_code.clone());
+//     //     // Update the file open in the editor
+//     //     let path = selected_file.peek().clone();
+//     //     let _ = project.write().set_file(&path, new_code.clone());
 //     //     spawn(async move {
 //     //         editor::monaco::set_markers(&[]);
 
 //     //         if build.stage().is_finished() {
-//     //             attempt_hot_reload(hot_reload, &new_code);
+//     //             attempt_hot_reload(hot_reload, &path, &new_code);
 //     //         }
 //     //     });
 //     // });
@@ -106,10 +116,13 @@
 //         hot_reload.set_needs_rebuild(false);
 
 //         // Update hot reload
-//         let code = editor::monaco::get_current_model_value();
+//         let path = selected_file.peek().clone();
+//         let _ = project.write().set_file(&path, editor::monaco::get_current_model_value());
+//         hot_reload.set_starting_project(&project.read());
+//         let files = project.read().files().clone();
 
 //         let socket_url = urls.socket.to_string();
-//         match start_build(build, socket_url, code).await {
+//         match start_build(build, socket_url, files).await {
 //             Ok(success) => hot_reload.set_needs_rebuild(!success),
 //             Err(error) => errors.push_from_app_error(error),
 //         }
@@ -149,7 +162,7 @@
 //                     monaco::on_monaco_load(
 //                         MONACO_FOLDER,
 //                         system_theme().unwrap_or(SystemTheme::Light),
-//                         &project.read().contents(),
+//                         &project.read(),
 //                         hot_reload,
 //                         monaco_ready,
 //                         on_model_changed,
@@ -191,7 +204,7 @@
 //                 show_examples,
 //                 pane_left_width,
 //                 pane_right_width,
-//                 file_name: project.read().path.clone(),
+//                 selected_file,
 //             }
 //             div { id: "dxp-lower-half",
 //                 div {
@@ -203,8 +216,9 @@
 //                             onclick: move |_| {
 //                                 project.set(example.clone());
 //                                 build.set_stage(BuildStage::Finished(Ok(example.id())));
+//                                 selected_file.set(MAIN_FILE.to_string());
 //                                 monaco::set_current_model_value(&example.contents());
-//                                 hot_reload.set_starting_code(&example.contents());
+//                                 hot_reload.set_starting_project(example);
 //                             },
 //                             h3 { {example.path.clone()} }
 //                             p { {example.description.clone()} }
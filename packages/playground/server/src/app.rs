@@ -1,13 +1,21 @@
 //! Initialization of the server application and environment configurations.
 
 use crate::{
-    build::{watcher::start_build_watcher, BuildCommand, BuildRequest},
-    start_cleanup_services,
+    build::{
+        dependencies::{self, DependencyAllowlist},
+        toolchain::{Dx, Toolchain},
+        watcher::start_build_watcher,
+        BuildCommand, BuildRequest,
+    },
+    cache::BuildCache,
+    share::{GistStore, LocalStore, ShareStore},
+    start_shutdown_watcher,
 };
 use dioxus_logger::tracing::{info, warn};
+use model::Project;
 use std::{
     env,
-    path::PathBuf,
+    path::{Path, PathBuf},
     sync::{atomic::AtomicBool, Arc},
     time::Duration,
 };
@@ -15,14 +23,31 @@ use tokio::{
     sync::{mpsc::UnboundedSender, Mutex},
     time::Instant,
 };
+use uuid::Uuid;
 
 const DEFAULT_PORT: u16 = 3000;
 
 // Paths
 const DEFAULT_BUILD_TEMPLATE_PATH: &str = "./template";
+const DEFAULT_DEPENDENCY_ALLOWLIST_PATH: &str = "snippets/dependencies.toml";
 
-// Duration after built projects are created to be removed.
-const DEFAULT_BUILT_CLEANUP_DELAY: Duration = Duration::from_secs(20);
+// The number of builds that can run at the same time.
+const DEFAULT_BUILD_WORKERS: usize = 1;
+
+// The size in megabytes the built projects can take up before the least recently used are removed.
+const DEFAULT_BUILT_CACHE_SIZE_MB: u64 = 2048;
+
+// The number of seconds in a day, for the share expiry.
+const SECONDS_PER_DAY: u64 = 60 * 60 * 24;
+
+/// Where shared projects are stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShareStoreKind {
+    /// As GitHub Gists, with the [`EnvVars::gist_auth_token`].
+    Gist,
+    /// As files in the [`EnvVars::share_path`].
+    Local,
+}
 
 /// A group of environment configurations for the application.
 #[derive(Clone)]
@@ -36,16 +61,38 @@ pub struct EnvVars {
     /// The path to the build template.
     pub build_template_path: PathBuf,
 
-    /// The path where built projects are temporarily stored.
+    /// The path to the TOML file of the dependencies projects can declare in their
+    /// `Cargo.toml`. See [`DependencyAllowlist`].
+    pub dependency_allowlist_path: PathBuf,
+
+    /// The number of builds that can run at the same time. Each worker builds in its own copy
+    /// of the build template.
+    pub build_workers: usize,
+
+    /// The path where built projects are cached.
     pub built_path: PathBuf,
 
-    /// The time after creation each built project should be removed.
-    pub built_cleanup_delay: Duration,
+    /// The size in bytes the built projects can take up. Once a build goes over it, the least
+    /// recently served builds are removed.
+    pub built_cache_size: u64,
+
+    /// An optional directory of `.rs` snippets, like the ones in the docs, that are built on
+    /// startup and never removed from the cache, like the examples.
+    pub pinned_projects_path: Option<PathBuf>,
 
     /// The optional shutdown delay that specifies how many seconds after
     /// inactivity to shut down the server.
     pub shutdown_delay: Option<Duration>,
 
+    /// Where shared projects are stored.
+    pub share_store: ShareStoreKind,
+
+    /// The path where shared projects are stored with [`ShareStoreKind::Local`].
+    pub share_path: PathBuf,
+
+    /// The optional time after sharing that shared projects expire. Gists don't expire.
+    pub share_expiry: Option<Duration>,
+
     pub gist_auth_token: String,
 }
 
@@ -55,20 +102,32 @@ impl EnvVars {
         let production = Self::get_production_env();
         let port = Self::get_port_env();
         let build_template_path = Self::get_build_template_path();
+        let dependency_allowlist_path = Self::get_dependency_allowlist_path(&build_template_path);
+        let build_workers = Self::get_build_workers();
+        let built_cache_size = Self::get_built_cache_size();
+        let pinned_projects_path = Self::get_pinned_projects_path();
         let shutdown_delay = Self::get_shutdown_delay();
         let gist_auth_token = Self::get_gist_auth_token();
+        let share_store = Self::get_share_store(gist_auth_token.is_some());
+        let share_expiry = Self::get_share_expiry();
 
         Self {
             production,
             port,
             build_template_path,
+            dependency_allowlist_path,
+            build_workers,
             built_path: if production {
                 PathBuf::from("/usr/src/app/temp/")
             } else {
                 PathBuf::from("./temp/")
             },
+            built_cache_size,
+            pinned_projects_path,
             shutdown_delay,
-            built_cleanup_delay: DEFAULT_BUILT_CLEANUP_DELAY,
+            share_store,
+            share_path: Self::get_share_path(production),
+            share_expiry,
             gist_auth_token: gist_auth_token.unwrap_or_default(),
         }
     }
@@ -116,6 +175,70 @@ impl EnvVars {
         build_template_path
     }
 
+    /// Get the dependency allowlist path from environment or default, which is in the build
+    /// template's snippets.
+    fn get_dependency_allowlist_path(build_template_path: &Path) -> PathBuf {
+        match env::var("DEPENDENCY_ALLOWLIST_PATH") {
+            Ok(v) => PathBuf::from(v),
+            Err(_) => {
+                let path = build_template_path.join(DEFAULT_DEPENDENCY_ALLOWLIST_PATH);
+                info!(
+                    "`DEPENDENCY_ALLOWLIST_PATH` environment variable is not set; defaulting to `{:?}`",
+                    path
+                );
+                path
+            }
+        }
+    }
+
+    /// Get the number of build workers from environment or default.
+    fn get_build_workers() -> usize {
+        let mut build_workers = DEFAULT_BUILD_WORKERS;
+        match env::var("BUILD_WORKERS") {
+            Ok(v) => {
+                build_workers = v
+                    .parse()
+                    .expect("the `BUILD_WORKERS` environment variable should be a number")
+            }
+            Err(_) => info!(
+                "`BUILD_WORKERS` environment variable not set; defaulting to `{}`",
+                build_workers
+            ),
+        }
+
+        build_workers.max(1)
+    }
+
+    /// Get the size of the built projects cache in bytes from the size in megabytes in the
+    /// environment or default.
+    fn get_built_cache_size() -> u64 {
+        let mut built_cache_size_mb = DEFAULT_BUILT_CACHE_SIZE_MB;
+        match env::var("BUILT_CACHE_SIZE_MB") {
+            Ok(v) => {
+                built_cache_size_mb = v
+                    .parse()
+                    .expect("the `BUILT_CACHE_SIZE_MB` environment variable should be a number")
+            }
+            Err(_) => info!(
+                "`BUILT_CACHE_SIZE_MB` environment variable not set; defaulting to `{}`",
+                built_cache_size_mb
+            ),
+        }
+
+        built_cache_size_mb * 1024 * 1024
+    }
+
+    /// Get the path of the pinned projects from the environment.
+    fn get_pinned_projects_path() -> Option<PathBuf> {
+        let pinned_projects_path = env::var("PINNED_PROJECTS_PATH").ok().map(PathBuf::from);
+
+        if pinned_projects_path.is_none() {
+            info!("`PINNED_PROJECTS_PATH` environment variable is not set; only the examples are pinned")
+        }
+
+        pinned_projects_path
+    }
+
     /// Get the server shutdown delay from the environment.
     fn get_shutdown_delay() -> Option<Duration> {
         let shutdown_delay = env::var("SHUTDOWN_DELAY")
@@ -140,8 +263,70 @@ impl EnvVars {
 
         gist_auth_token
     }
+
+    /// Get the share store from the environment, defaulting to gists if there is a token for them.
+    fn get_share_store(has_gist_auth_token: bool) -> ShareStoreKind {
+        let default = match has_gist_auth_token {
+            true => ShareStoreKind::Gist,
+            false => ShareStoreKind::Local,
+        };
+
+        match env::var("SHARE_STORE").as_deref() {
+            Ok("gist") => ShareStoreKind::Gist,
+            Ok("local") => ShareStoreKind::Local,
+            Ok(v) => panic!(
+                "the `SHARE_STORE` environment variable should be `gist` or `local`, not `{v}`"
+            ),
+            Err(_) => {
+                info!("`SHARE_STORE` environment variable not set; defaulting to `{default:?}`");
+                default
+            }
+        }
+    }
+
+    /// Get the local share store path from environment or default.
+    fn get_share_path(production: bool) -> PathBuf {
+        match env::var("SHARE_PATH") {
+            Ok(v) => PathBuf::from(v),
+            Err(_) if production => PathBuf::from("/usr/src/app/shared/"),
+            Err(_) => PathBuf::from("./shared/"),
+        }
+    }
+
+    /// Get the share expiry in days from the environment.
+    fn get_share_expiry() -> Option<Duration> {
+        let share_expiry = env::var("SHARE_EXPIRY_DAYS")
+            .ok()
+            .and_then(|v| v.parse::<u64>().ok())
+            .map(|days| Duration::from_secs(days * SECONDS_PER_DAY));
+
+        if share_expiry.is_none() {
+            info!("`SHARE_EXPIRY_DAYS` environment variable is not set; shared projects will not expire")
+        }
+
+        share_expiry
+    }
+
+    /// The template a build worker builds in. The first worker uses the build template and the
+    /// others use a copy of it, like `template-1` next to `template`.
+    pub fn worker_template_path(&self, worker: usize) -> PathBuf {
+        if worker == 0 {
+            return self.build_template_path.clone();
+        }
+
+        let name = self
+            .build_template_path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| "template".to_string());
+        self.build_template_path
+            .with_file_name(format!("{name}-{worker}"))
+    }
 }
 
+/// The client that requests the example builds on startup.
+const EXAMPLES_CLIENT: &str = "examples";
+
 /// The state of the server application.
 #[derive(Clone)]
 pub struct AppState {
@@ -154,13 +339,20 @@ pub struct AppState {
     /// The build command channel.
     pub build_queue_tx: UnboundedSender<BuildCommand>,
 
+    /// The built projects that are served.
+    pub cache: Arc<BuildCache>,
+
     /// Prevents the server from shutting down during an active build.
     pub is_building: Arc<AtomicBool>,
 
     /// A list of connected sockets by ip. Used to disallow extra socket connections.
     pub _connected_sockets: Arc<Mutex<Vec<String>>>,
 
-    pub reqwest_client: reqwest::Client,
+    /// Where shared projects are stored.
+    pub share_store: Arc<dyn ShareStore>,
+
+    /// The dependencies projects can declare.
+    pub dependencies: Arc<DependencyAllowlist>,
 }
 
 impl AppState {
@@ -168,10 +360,6 @@ impl AppState {
     pub async fn new() -> Self {
         let mut env = EnvVars::new().await;
 
-        // Build the app state
-        let is_building = Arc::new(AtomicBool::new(false));
-        let build_queue_tx = start_build_watcher(env.clone(), is_building.clone());
-
         // Get prebuild arg
         let prebuild = std::env::args()
             .collect::<Vec<String>>()
@@ -184,33 +372,127 @@ impl AppState {
             env.shutdown_delay = Some(Duration::from_secs(1));
         }
 
-        let state = Self {
-            env,
-            build_queue_tx,
-            last_request_time: Arc::new(Mutex::new(Instant::now())),
-            is_building,
-            _connected_sockets: Arc::new(Mutex::new(Vec::new())),
-            reqwest_client: reqwest::Client::new(),
-        };
+        // Vendor the allowed dependencies so builds don't need the network. This is done before
+        // the build template is copied for the other workers, so they get the vendored crates.
+        let allowlist = DependencyAllowlist::load_or_default(
+            &env.dependency_allowlist_path,
+            &env.build_template_path,
+        );
+        if let Err(err) = dependencies::vendor(&env.build_template_path, &allowlist).await {
+            warn!(
+                ?err,
+                "failed to vendor the allowed dependencies; builds will use the network"
+            );
+        }
 
-        // Queue the examples to be built on startup.
+        // Queue the examples and pinned snippets to be built on startup.
         // This ensures the cache is hot before users try to use it, meaning the examples will be ready to go.
+        // They are pinned so they are never evicted from the cache, even by the eviction when the
+        // cache is opened.
+        let mut projects = example_projects::get_example_projects().to_vec();
+        if let Some(path) = &env.pinned_projects_path {
+            projects.extend(read_pinned_projects(path));
+        }
+        let pinned = projects.iter().map(Project::id);
+        let state = Self::with_pinned_builds(env, Arc::new(Dx), pinned);
+
         let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
         std::mem::forget(rx);
-        for project in example_projects::get_example_projects() {
+        for project in projects {
             dioxus::logger::tracing::trace!(example = ?project, "queueing example project");
             let _ = state.build_queue_tx.send(BuildCommand::Start {
                 request: BuildRequest {
                     id: project.id(),
+                    client: EXAMPLES_CLIENT.to_string(),
+                    socket_id: Uuid::new_v4(),
                     project: project.clone(),
+                    dependencies: Default::default(),
                     ws_msg_tx: tx.clone(),
                 },
             });
         }
 
         // Start the app services
-        start_cleanup_services(state.clone());
+        start_shutdown_watcher(state.clone());
 
         state
     }
+
+    /// Build the app state with a toolchain, starting only the build watcher.
+    ///
+    /// Examples are not prebuilt and the server never shuts down, which lets tests drive builds
+    /// with a fake toolchain.
+    pub fn with_toolchain(env: EnvVars, toolchain: Arc<dyn Toolchain>) -> Self {
+        Self::with_pinned_builds(env, toolchain, [])
+    }
+
+    /// Build the app state with builds that are pinned in the cache before it is opened.
+    fn with_pinned_builds(
+        env: EnvVars,
+        toolchain: Arc<dyn Toolchain>,
+        pinned: impl IntoIterator<Item = Uuid>,
+    ) -> Self {
+        let cache = BuildCache::open(&env.built_path, env.built_cache_size, pinned)
+            .expect("the built projects cache should open");
+        let cache = Arc::new(cache);
+
+        let is_building = Arc::new(AtomicBool::new(false));
+        let build_queue_tx =
+            start_build_watcher(env.clone(), cache.clone(), toolchain, is_building.clone());
+
+        let share_store: Arc<dyn ShareStore> = match env.share_store {
+            ShareStoreKind::Gist => Arc::new(GistStore::new(
+                reqwest::Client::new(),
+                env.gist_auth_token.clone(),
+            )),
+            ShareStoreKind::Local => Arc::new(
+                LocalStore::open(&env.share_path).expect("the local share store should open"),
+            ),
+        };
+
+        let dependencies = DependencyAllowlist::load_or_default(
+            &env.dependency_allowlist_path,
+            &env.build_template_path,
+        );
+
+        Self {
+            env,
+            build_queue_tx,
+            cache,
+            last_request_time: Arc::new(Mutex::new(Instant::now())),
+            is_building,
+            _connected_sockets: Arc::new(Mutex::new(Vec::new())),
+            share_store,
+            dependencies: Arc::new(dependencies),
+        }
+    }
+}
+
+/// Read the `.rs` snippets in a directory as projects.
+fn read_pinned_projects(path: &Path) -> Vec<Project> {
+    let entries = match std::fs::read_dir(path) {
+        Ok(entries) => entries,
+        Err(err) => {
+            warn!(?err, ?path, "failed to read the pinned projects");
+            return Vec::new();
+        }
+    };
+
+    let mut projects = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some("rs") {
+            continue;
+        }
+
+        match std::fs::read_to_string(&path) {
+            Ok(contents) => {
+                let name = entry.file_name().to_string_lossy().to_string();
+                projects.push(Project::new(contents, None, Some(name)));
+            }
+            Err(err) => warn!(?err, ?path, "failed to read pinned project"),
+        }
+    }
+
+    projects
 }
@@ -1,40 +1,44 @@
-use super::{BuildError, BuildRequest};
-use crate::app::EnvVars;
+use super::{toolchain::Toolchain, BuildError, BuildRequest};
 use crate::build::{BuildMessage, CliMessage};
+use crate::cache::BuildCache;
 use dioxus_dx_wire_format::StructuredOutput;
 use dioxus_logger::tracing;
-use dioxus_logger::tracing::debug;
-use fs_extra::dir::CopyOptions;
-use model::{BuildStage, CargoDiagnostic};
+use model::{BuildStage, CargoDiagnostic, MANIFEST_FILE};
 use std::path::{Path, PathBuf};
-use std::process::Stdio;
-use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use tokio::io::{AsyncBufReadExt as _, BufReader};
-use tokio::process::Command;
+use tokio::sync::mpsc;
 use tokio::task::JoinHandle;
 use tokio::{fs, select};
 
 const BUILD_ID_ID: &str = "{BUILD_ID}";
 
+/// The template directories that project files are written to. See [`model::validate_path`].
+const PROJECT_DIRS: [&str; 2] = ["src", "assets"];
+
 // TODO: We need some way of cleaning up any stopped builds.
 /// The builder provides a convenient interface for controlling builds running in another task.
+///
+/// Each builder has its own template directory, so several builders can build at the same time.
 pub struct Builder {
     template_path: PathBuf,
-    built_path: PathBuf,
-    is_building: Arc<AtomicBool>,
+    cache: Arc<BuildCache>,
+    toolchain: Arc<dyn Toolchain>,
     current_build: Option<BuildRequest>,
-    task: JoinHandle<Result<(), BuildError>>,
+    task: Option<JoinHandle<Result<(), BuildError>>>,
 }
 
 impl Builder {
-    pub fn new(env: EnvVars, is_building: Arc<AtomicBool>) -> Self {
+    pub fn new(
+        template_path: PathBuf,
+        cache: Arc<BuildCache>,
+        toolchain: Arc<dyn Toolchain>,
+    ) -> Self {
         Self {
-            template_path: env.build_template_path,
-            built_path: env.built_path,
-            is_building,
+            template_path,
+            cache,
+            toolchain,
             current_build: None,
-            task: tokio::spawn(std::future::pending()),
+            task: None,
         }
     }
 
@@ -43,35 +47,45 @@ impl Builder {
         let _ = request.ws_msg_tx.send(BuildMessage::QueuePosition(0));
 
         self.stop_current();
-        self.is_building.store(true, Ordering::SeqCst);
         self.current_build = Some(request.clone());
-        self.task = tokio::spawn(build(
+        self.task = Some(tokio::spawn(build(
             self.template_path.clone(),
-            self.built_path.clone(),
+            self.cache.clone(),
+            self.toolchain.clone(),
             request,
-        ));
+        )));
     }
 
     /// Stop the current build.
     pub fn stop_current(&mut self) {
-        self.task.abort();
-        self.task = tokio::spawn(std::future::pending());
+        if let Some(task) = self.task.take() {
+            task.abort();
+        }
         self.current_build = None;
-        self.is_building.store(false, Ordering::SeqCst);
     }
 
-    /// Wait for the current build to finish.
-    pub async fn finished(&mut self) -> Result<BuildRequest, BuildError> {
-        // Ensure we don't poll a completed task.
-        if self.task.is_finished() {
-            self.stop_current();
-        }
+    /// Wait for the current build to finish, returning the request with the result of the build.
+    /// Never finishes if the builder doesn't have a build.
+    ///
+    /// This is cancel safe: a build that finishes while nothing is waiting on it is returned
+    /// the next time this is called.
+    pub async fn finished(&mut self) -> (BuildRequest, Result<(), BuildError>) {
+        let Some(task) = &mut self.task else {
+            return std::future::pending().await;
+        };
 
         // Make progress on the build task.
-        let task = &mut self.task;
-        task.await??;
-
-        self.current_build.take().ok_or(BuildError::NotStarted)
+        let result = match task.await {
+            Ok(result) => result,
+            Err(e) => Err(e.into()),
+        };
+        self.task = None;
+
+        let request = self
+            .current_build
+            .take()
+            .expect("a builder with a task should have a build");
+        (request, result)
     }
 
     /// Check if the builder has an ongoing build.
@@ -80,27 +94,28 @@ impl Builder {
     }
 
     /// Get the current ongoing build if it exists.
-    pub fn current_build(&self) -> Option<BuildRequest> {
-        self.current_build.clone()
+    pub fn current_build(&self) -> Option<&BuildRequest> {
+        self.current_build.as_ref()
     }
 }
 
 /// Run the steps to produce a build for a [`BuildRequest`]
 async fn build(
     template_path: PathBuf,
-    built_path: PathBuf,
+    cache: Arc<BuildCache>,
+    toolchain: Arc<dyn Toolchain>,
     request: BuildRequest,
 ) -> Result<(), BuildError> {
     // If the project already exists, don't build it again.
-    if std::fs::exists(built_path.join(request.id.to_string())).unwrap_or_default() {
+    if cache.get(request.id) {
         tracing::trace!("Skipping build for {request:?} since it already exists");
         return Ok(());
     }
 
     setup_template(&template_path, &request).await?;
-    dx_build(&template_path, &request).await?;
-    tracing::trace!("Noving build from {template_path:?} to {built_path:?}");
-    move_to_built(&template_path, &built_path, &request).await?;
+    dx_build(toolchain.as_ref(), &template_path, &request).await?;
+    tracing::trace!("Moving build from {template_path:?} to the cache");
+    move_to_built(&template_path, cache, &request).await?;
 
     Ok(())
 }
@@ -126,68 +141,82 @@ async fn setup_template(template_path: &Path, request: &BuildRequest) -> Result<
         fs::write(new_path, contents).await?;
     }
 
-    // Write the user's code to main.rs
-    fs::write(
-        template_path.join("src/main.rs"),
-        request.project.contents(),
-    )
-    .await?;
+    // Add the project's dependencies to the template's manifest.
+    if !request.dependencies.is_empty() {
+        let manifest_path = template_path.join(MANIFEST_FILE);
+        let manifest = fs::read_to_string(&manifest_path).await?;
+        let manifest = request
+            .dependencies
+            .add_to_manifest(&manifest)
+            .map_err(std::io::Error::other)?;
+        fs::write(manifest_path, manifest).await?;
+    }
+
+    // Remove the files of the last project, then write the files of this one.
+    for dir in PROJECT_DIRS {
+        clear_dir(&template_path.join(dir)).await?;
+    }
+    for (path, contents) in request.project.files() {
+        // The project's manifest only declares dependencies, which are added above.
+        if path == MANIFEST_FILE {
+            continue;
+        }
+        let path = template_path.join(path);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        fs::write(path, contents).await?;
+    }
+
+    Ok(())
+}
+
+/// Remove everything in a directory except hidden files like `.gitkeep`.
+async fn clear_dir(path: &Path) -> Result<(), BuildError> {
+    let mut entries = match fs::read_dir(path).await {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(err) => return Err(err.into()),
+    };
+
+    while let Some(entry) = entries.next_entry().await? {
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+        match entry.file_type().await?.is_dir() {
+            true => fs::remove_dir_all(entry.path()).await?,
+            false => fs::remove_file(entry.path()).await?,
+        }
+    }
 
     Ok(())
 }
 
-/// Run the build command provided by the DX CLI.
-/// Returns if DX built the project successfully.
-async fn dx_build(template_path: &PathBuf, request: &BuildRequest) -> Result<(), BuildError> {
-    let mut child = Command::new("dx")
-        .arg("build")
-        .arg("--platform")
-        .arg("web")
-        .arg("--json-output")
-        .arg("--verbose")
-        .arg("--trace")
-        .current_dir(template_path)
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .spawn()?;
-
-    let stdout = child.stdout.take().expect("dx stdout should exist");
-    let mut stdout_reader = BufReader::new(stdout).lines();
-
-    let mut logs = Vec::new();
+/// Run the build with the toolchain, forwarding its output to the socket as it arrives.
+/// Returns if the toolchain built the project successfully.
+async fn dx_build(
+    toolchain: &dyn Toolchain,
+    template_path: &Path,
+    request: &BuildRequest,
+) -> Result<(), BuildError> {
+    let (output_tx, mut output_rx) = mpsc::unbounded_channel();
+    let mut build = toolchain.build(template_path, request.id, output_tx);
 
     loop {
         select! {
-            // Read stdout lines from DX.
-            result = stdout_reader.next_line() => {
-                let Ok(Some(line)) = result else {
-                    continue;
-                };
-
-                logs.push(line.clone());
-                process_dx_message(request, line);
-            }
-            // Wait for the DX process to exit.
-            status = child.wait() => {
-                // Check if the build was successful.
-                let exit_code = status.map(|c| c.code());
-                if let Ok(Some(code)) = exit_code {
-                    if code == 0 {
-                        break;
-                    } else {
-                        // Dump logs in debug.
-                        for log in logs {
-                            debug!("{log}");
-                        }
-
-                        return Err(BuildError::DxFailed(Some(code)));
-                    }
-                }
-                return Err(BuildError::DxFailed(None));
+            Some(line) = output_rx.recv() => process_dx_message(request, line),
+            result = &mut build => {
+                result?;
+                break;
             }
         }
     }
 
+    // Process any output written right before the build finished.
+    while let Ok(line) = output_rx.try_recv() {
+        process_dx_message(request, line);
+    }
+
     Ok(())
 }
 
@@ -228,36 +257,26 @@ fn process_dx_message(request: &BuildRequest, message: String) {
     };
 }
 
-/// Moves the project built by `dx` to the final location for serving.
+/// Publishes the project built by `dx` to the cache for serving.
 async fn move_to_built(
     template_path: &Path,
-    built_path: &Path,
+    cache: Arc<BuildCache>,
     request: &BuildRequest,
 ) -> Result<(), BuildError> {
-    let id_string = request.id.to_string();
+    let id = request.id;
 
     // The path to the built project from DX
-    let play_build_id = format!("play-{}", &id_string);
+    let play_build_id = format!("play-{id}");
     let built_project_parent = template_path.join("target/dx").join(play_build_id);
 
     // The public folder of the built project (what we want).
-    let debug_web = built_project_parent.join("debug/web/");
-    let public_folder = debug_web.join("public");
-
-    let built_path = built_path.to_path_buf();
+    let public_folder = built_project_parent.join("debug/web/public");
 
-    // Rename the built project public folder to the build id.
-    // Move the built project to the built projects folder to be served.
+    // Move the built project to the cache to be served.
     // Delete the built project in the target directory to prevent a storage leak.
     // We use `spawn_blocking` to batch call `std::fs` as recommended by Tokio.
     tokio::task::spawn_blocking::<_, Result<(), BuildError>>(move || {
-        // Rename to be the build id
-        let built_project = debug_web.join(&id_string);
-        std::fs::rename(&public_folder, &built_project)?;
-
-        // Copy to the built project folder for serving.
-        let options = CopyOptions::new().overwrite(true);
-        fs_extra::dir::move_dir(&built_project, &built_path, &options)?;
+        cache.publish(id, &public_folder)?;
         std::fs::remove_dir_all(&built_project_parent)?;
         Ok(())
     })
@@ -0,0 +1,670 @@
+//! Dependencies that projects can declare in their `Cargo.toml`, from a server-side allowlist.
+
+use super::BuildError;
+use dioxus_logger::tracing::{info, warn};
+use model::{Project, MANIFEST_FILE};
+use semver::{Version, VersionReq};
+use serde::Deserialize;
+use std::{collections::BTreeMap, io, path::Path, process::Stdio};
+use thiserror::Error;
+use tokio::{fs, process::Command};
+use uuid::Uuid;
+
+/// The template's own manifest, which the dependencies of a project are added to.
+pub const TEMPLATE_MANIFEST: &str = "snippets/Cargo.toml";
+
+/// The directory in the template that the allowed crates are vendored in.
+const VENDOR_DIR: &str = "vendor";
+/// The file in the vendor directory that records what was vendored.
+const VENDOR_STAMP: &str = ".stamp";
+
+/// A crate that projects can depend on, with the version and features every project builds it
+/// with.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct AllowedCrate {
+    pub version: Version,
+    #[serde(default)]
+    pub features: Vec<String>,
+    #[serde(default = "default_features")]
+    pub default_features: bool,
+}
+
+fn default_features() -> bool {
+    true
+}
+
+/// A dependency the template already has, with the version requirement and features the
+/// template declares it with.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct ProvidedCrate {
+    /// The version requirement, or `None` if the template doesn't set a version.
+    version: Option<VersionReq>,
+    features: Vec<String>,
+    default_features: bool,
+}
+
+impl ProvidedCrate {
+    /// Read a dependency of the template's manifest. The template can set any other keys, like
+    /// `path`, which projects can't.
+    fn from_template(spec: &toml::Value) -> Result<Self, DependencyError> {
+        let (version, table) = match spec {
+            toml::Value::String(version) => (Some(version.as_str()), None),
+            toml::Value::Table(table) => {
+                (table.get("version").and_then(|v| v.as_str()), Some(table))
+            }
+            _ => {
+                return Err(DependencyError::template(
+                    "a dependency must be a version or a table",
+                ))
+            }
+        };
+        let version = version
+            .map(VersionReq::parse)
+            .transpose()
+            .map_err(DependencyError::template)?;
+        let features = table
+            .and_then(|table| table.get("features"))
+            .and_then(|features| features.as_array())
+            .map(|features| {
+                features
+                    .iter()
+                    .filter_map(|feature| feature.as_str().map(str::to_string))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let default_features = table
+            .and_then(|table| table.get("default-features"))
+            .and_then(|default_features| default_features.as_bool())
+            .unwrap_or(true);
+        Ok(Self {
+            version,
+            features,
+            default_features,
+        })
+    }
+}
+
+/// The version, features and default features a project's `Cargo.toml` asks for.
+struct RequestedCrate<'a> {
+    version: Option<&'a str>,
+    features: Vec<&'a str>,
+    default_features: Option<bool>,
+}
+
+impl<'a> RequestedCrate<'a> {
+    /// Parse a dependency, returning why it isn't valid if it sets anything other than a
+    /// version, features and default features.
+    fn parse(name: &str, spec: &'a toml::Value) -> Result<Self, String> {
+        let table = match spec {
+            toml::Value::String(version) => {
+                return Ok(Self {
+                    version: Some(version),
+                    features: Vec::new(),
+                    default_features: None,
+                })
+            }
+            toml::Value::Table(table) => table,
+            _ => return Err(format!("`{name}` must be a version or a table")),
+        };
+
+        if let Some(key) = table
+            .keys()
+            .find(|key| !matches!(key.as_str(), "version" | "features" | "default-features"))
+        {
+            return Err(format!(
+                "`{name}` can only set `version`, `features` and `default-features`, not `{key}`"
+            ));
+        }
+
+        let version = match table.get("version") {
+            None => None,
+            Some(toml::Value::String(version)) => Some(version.as_str()),
+            Some(_) => return Err(format!("the `version` of `{name}` must be a string")),
+        };
+        let features = match table.get("features") {
+            None => Vec::new(),
+            Some(toml::Value::Array(features)) => features
+                .iter()
+                .map(|feature| feature.as_str())
+                .collect::<Option<_>>()
+                .ok_or_else(|| format!("the `features` of `{name}` must be strings"))?,
+            Some(_) => return Err(format!("the `features` of `{name}` must be a list")),
+        };
+        let default_features = match table.get("default-features") {
+            None => None,
+            Some(toml::Value::Boolean(default_features)) => Some(*default_features),
+            Some(_) => return Err(format!("`default-features` of `{name}` must be a boolean")),
+        };
+
+        Ok(Self {
+            version,
+            features,
+            default_features,
+        })
+    }
+}
+
+/// Whether two version requirements like `0.6` and `0.6.3` have a version in common.
+fn overlaps(a: &VersionReq, b: &VersionReq) -> bool {
+    // The lowest version a requirement like `0.6` or `^0.6.1` matches.
+    let lowest = |req: &VersionReq| {
+        req.comparators.first().map(|comparator| {
+            Version::new(
+                comparator.major,
+                comparator.minor.unwrap_or(0),
+                comparator.patch.unwrap_or(0),
+            )
+        })
+    };
+    lowest(b).is_some_and(|version| a.matches(&version))
+        || lowest(a).is_some_and(|version| b.matches(&version))
+}
+
+impl AllowedCrate {
+    /// The dependency table for the crate in a `Cargo.toml`, pinned to the exact version.
+    fn to_toml(&self) -> toml::Value {
+        let mut table = toml::Table::new();
+        table.insert("version".into(), format!("={}", self.version).into());
+        if !self.features.is_empty() {
+            table.insert("features".into(), self.features.clone().into());
+        }
+        if !self.default_features {
+            table.insert("default-features".into(), false.into());
+        }
+        table.into()
+    }
+}
+
+/// The crates projects can depend on, loaded from a TOML file like:
+///
+/// ```toml
+/// [dependencies]
+/// serde = { version = "1.0.219", features = ["derive"] }
+/// ```
+///
+/// Every project builds an allowed crate with the same version and features, so the crates can
+/// be vendored into the template and built once. Projects can also list the dependencies the
+/// template already has, like `dioxus`, which are built as the template declares them.
+#[derive(Debug, Clone, Default)]
+pub struct DependencyAllowlist {
+    allowed: BTreeMap<String, AllowedCrate>,
+    /// The dependencies of the template.
+    provided: BTreeMap<String, ProvidedCrate>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AllowlistFile {
+    #[serde(default)]
+    dependencies: BTreeMap<String, AllowedCrate>,
+}
+
+/// The dependencies a project builds with, besides the template's own.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Dependencies(BTreeMap<String, AllowedCrate>);
+
+impl Dependencies {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Add the dependencies to the `[dependencies]` of a `Cargo.toml`.
+    pub fn add_to_manifest(&self, manifest: &str) -> Result<String, DependencyError> {
+        let mut manifest: toml::Table = manifest.parse().map_err(DependencyError::template)?;
+        let dependencies = manifest
+            .entry("dependencies")
+            .or_insert_with(|| toml::Table::new().into())
+            .as_table_mut()
+            .ok_or_else(|| DependencyError::template("`dependencies` is not a table"))?;
+
+        for (name, krate) in &self.0 {
+            dependencies.insert(name.clone(), krate.to_toml());
+        }
+
+        toml::to_string(&manifest).map_err(DependencyError::template)
+    }
+}
+
+/// A project declares dependencies that aren't allowed, or the allowlist couldn't be loaded.
+#[derive(Debug, Error)]
+pub enum DependencyError {
+    #[error("`Cargo.toml` is not valid: {reason}. {allowed}")]
+    InvalidManifest { reason: String, allowed: String },
+
+    #[error("`{name}` is not an allowed dependency. {allowed}")]
+    NotAllowed { name: String, allowed: String },
+
+    #[error("`{name}` can't be `{requested}`. {allowed}")]
+    Version {
+        name: String,
+        requested: String,
+        allowed: String,
+    },
+
+    #[error("`{name}` can't have the `{feature}` feature. {allowed}")]
+    Feature {
+        name: String,
+        feature: String,
+        allowed: String,
+    },
+
+    #[error("invalid dependency allowlist: {0}")]
+    Allowlist(String),
+
+    #[error("invalid template manifest: {0}")]
+    Template(String),
+
+    #[error("io error: {0}")]
+    Io(#[from] io::Error),
+}
+
+impl DependencyError {
+    fn template(reason: impl ToString) -> Self {
+        Self::Template(reason.to_string())
+    }
+}
+
+impl DependencyAllowlist {
+    /// Load the allowlist, and the dependencies the template has from its manifest.
+    pub fn load(allowlist_path: &Path, template_path: &Path) -> Result<Self, DependencyError> {
+        let allowlist = std::fs::read_to_string(allowlist_path)?;
+        let allowlist: AllowlistFile = toml::from_str(&allowlist)
+            .map_err(|err| DependencyError::Allowlist(err.to_string()))?;
+
+        let manifest = std::fs::read_to_string(template_path.join(TEMPLATE_MANIFEST))?;
+        let manifest: toml::Table = manifest.parse().map_err(DependencyError::template)?;
+        let provided = manifest
+            .get("dependencies")
+            .and_then(|dependencies| dependencies.as_table())
+            .into_iter()
+            .flatten()
+            .map(|(name, spec)| Ok((name.clone(), ProvidedCrate::from_template(spec)?)))
+            .collect::<Result<_, DependencyError>>()?;
+
+        Ok(Self {
+            allowed: allowlist.dependencies,
+            provided,
+        })
+    }
+
+    /// Load the allowlist, or allow no dependencies if it doesn't exist.
+    pub fn load_or_default(allowlist_path: &Path, template_path: &Path) -> Self {
+        match Self::load(allowlist_path, template_path) {
+            Ok(allowlist) => allowlist,
+            Err(DependencyError::Io(err)) if err.kind() == io::ErrorKind::NotFound => {
+                warn!(
+                    ?allowlist_path,
+                    "the dependency allowlist doesn't exist; projects can't add dependencies"
+                );
+                Self::default()
+            }
+            Err(err) => panic!("the dependency allowlist should load: {err}"),
+        }
+    }
+
+    /// Check the dependencies a project declares in its `Cargo.toml` and get the dependencies
+    /// to build it with.
+    ///
+    /// A project can only have a `[dependencies]` table, and each dependency can only set a
+    /// `version`, `features` and `default-features` that the allowed crate, or the template's
+    /// own dependency, matches.
+    pub fn resolve(&self, project: &Project) -> Result<Dependencies, DependencyError> {
+        let Some(manifest) = project.file(MANIFEST_FILE) else {
+            return Ok(Dependencies::default());
+        };
+        let invalid = |reason: String| DependencyError::InvalidManifest {
+            reason,
+            allowed: self.describe(),
+        };
+
+        let manifest: toml::Table = manifest.parse().map_err(|err| invalid(format!("{err}")))?;
+        let mut dependencies = BTreeMap::new();
+
+        for (key, value) in manifest {
+            // The package is set up by the server, so it's fine to leave it in.
+            if key == "package" {
+                continue;
+            }
+            if key != "dependencies" {
+                return Err(invalid(format!(
+                    "it can only have a `[dependencies]` table, not `{key}`"
+                )));
+            }
+            let Some(table) = value.as_table() else {
+                return Err(invalid("`dependencies` must be a table".to_string()));
+            };
+
+            for (name, spec) in table {
+                if let Some(krate) = self.check(name, spec)? {
+                    dependencies.insert(name.clone(), krate.clone());
+                }
+            }
+        }
+
+        Ok(Dependencies(dependencies))
+    }
+
+    /// Check a dependency, getting the allowed crate to build with, or `None` if the template
+    /// already has the dependency.
+    fn check(
+        &self,
+        name: &str,
+        spec: &toml::Value,
+    ) -> Result<Option<&AllowedCrate>, DependencyError> {
+        let allowed = || self.describe();
+        let invalid = |reason: String| DependencyError::InvalidManifest {
+            reason,
+            allowed: allowed(),
+        };
+
+        // The template's own dependencies are built as the template declares them, so a project
+        // can only ask for what the template already builds.
+        if let Some(provided) = self.provided.get(name) {
+            let requested = RequestedCrate::parse(name, spec).map_err(invalid)?;
+            self.check_request(
+                name,
+                &requested,
+                |req| {
+                    provided
+                        .version
+                        .as_ref()
+                        .is_none_or(|template| overlaps(req, template))
+                },
+                &provided.features,
+                provided.default_features,
+            )?;
+            return Ok(None);
+        }
+
+        let Some(krate) = self.allowed.get(name) else {
+            return Err(DependencyError::NotAllowed {
+                name: name.to_string(),
+                allowed: allowed(),
+            });
+        };
+        let requested = RequestedCrate::parse(name, spec).map_err(invalid)?;
+        self.check_request(
+            name,
+            &requested,
+            |req| req.matches(&krate.version),
+            &krate.features,
+            krate.default_features,
+        )?;
+        Ok(Some(krate))
+    }
+
+    /// Check that a project only asks for a version, features and default features that the
+    /// crate is built with.
+    fn check_request(
+        &self,
+        name: &str,
+        requested: &RequestedCrate,
+        version_matches: impl Fn(&VersionReq) -> bool,
+        features: &[String],
+        default_features: bool,
+    ) -> Result<(), DependencyError> {
+        if let Some(version) = requested.version {
+            if !VersionReq::parse(version).is_ok_and(|req| version_matches(&req)) {
+                return Err(DependencyError::Version {
+                    name: name.to_string(),
+                    requested: version.to_string(),
+                    allowed: self.describe(),
+                });
+            }
+        }
+
+        if let Some(feature) = requested
+            .features
+            .iter()
+            .find(|f| !features.iter().any(|a| a == *f))
+        {
+            return Err(DependencyError::Feature {
+                name: name.to_string(),
+                feature: feature.to_string(),
+                allowed: self.describe(),
+            });
+        }
+
+        // The crate is built once for every project, so it can't be built without its default
+        // features for one project and with them for another.
+        if requested
+            .default_features
+            .is_some_and(|requested| requested != default_features)
+        {
+            return Err(DependencyError::InvalidManifest {
+                reason: format!("`{name}` is built with `default-features = {default_features}`"),
+                allowed: self.describe(),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// A description of the crates projects can use, for error messages.
+    pub fn describe(&self) -> String {
+        if self.allowed.is_empty() {
+            return "Projects can't add dependencies.".to_string();
+        }
+
+        let crates = self
+            .allowed
+            .iter()
+            .map(|(name, krate)| match krate.features.is_empty() {
+                true => format!("{name} {}", krate.version),
+                false => format!(
+                    "{name} {} (features: {})",
+                    krate.version,
+                    krate.features.join(", ")
+                ),
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!("Projects can use: {crates}.")
+    }
+
+    /// All of the allowed crates, to vendor them.
+    fn all(&self) -> Dependencies {
+        Dependencies(self.allowed.clone())
+    }
+}
+
+/// Vendor the allowed crates and the template's own dependencies into the template, so builds
+/// don't need the network.
+///
+/// The crates are vendored with `cargo vendor` into `vendor` in the template, and
+/// `.cargo/config.toml` in the template replaces crates.io with them. Vendoring is skipped if
+/// the crates were already vendored for the same allowlist and template manifest.
+pub async fn vendor(
+    template_path: &Path,
+    allowlist: &DependencyAllowlist,
+) -> Result<(), BuildError> {
+    let template_path = std::path::absolute(template_path)?;
+    let vendor_path = template_path.join(VENDOR_DIR);
+    let config_path = template_path.join(".cargo/config.toml");
+
+    // A manifest with every crate a project can build with.
+    let manifest = fs::read_to_string(template_path.join(TEMPLATE_MANIFEST)).await?;
+    let manifest = manifest.replace("{BUILD_ID}", "vendor");
+    let manifest = allowlist
+        .all()
+        .add_to_manifest(&manifest)
+        .map_err(io::Error::other)?;
+
+    let stamp = Uuid::new_v3(&Uuid::NAMESPACE_URL, manifest.as_bytes()).to_string();
+    let vendored = fs::read_to_string(vendor_path.join(VENDOR_STAMP)).await;
+    if vendored.is_ok_and(|vendored| vendored == stamp) && config_path.exists() {
+        info!("the allowed dependencies are already vendored");
+        return Ok(());
+    }
+
+    info!(?vendor_path, "vendoring the allowed dependencies");
+    // The old config would make cargo use the old vendored crates.
+    if config_path.exists() {
+        fs::remove_file(&config_path).await?;
+    }
+
+    let manifest_dir = template_path.join(".vendor");
+    fs::create_dir_all(manifest_dir.join("src")).await?;
+    fs::write(manifest_dir.join("Cargo.toml"), manifest).await?;
+    fs::write(manifest_dir.join("src/main.rs"), "fn main() {}").await?;
+
+    let status = Command::new("cargo")
+        .arg("vendor")
+        .arg("--quiet")
+        .arg("--manifest-path")
+        .arg(manifest_dir.join("Cargo.toml"))
+        .arg(&vendor_path)
+        .stdout(Stdio::null())
+        .status()
+        .await;
+    fs::remove_dir_all(&manifest_dir).await?;
+
+    let status = status?;
+    if !status.success() {
+        return Err(BuildError::VendorFailed(status.code()));
+    }
+
+    let config = format!(
+        "[source.crates-io]\n\
+         replace-with = \"vendored-sources\"\n\n\
+         [source.vendored-sources]\n\
+         directory = {:?}\n\n\
+         [net]\n\
+         offline = true\n",
+        vendor_path.to_string_lossy()
+    );
+    fs::create_dir_all(template_path.join(".cargo")).await?;
+    fs::write(&config_path, config).await?;
+    fs::write(vendor_path.join(VENDOR_STAMP), stamp).await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use model::ProjectFiles;
+
+    const ALLOWLIST: &str = r#"
+        [dependencies]
+        serde = { version = "1.0.219", features = ["derive", "rc"] }
+        chrono = { version = "0.4.41", default-features = false }
+    "#;
+
+    fn allowlist() -> DependencyAllowlist {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("snippets")).unwrap();
+        std::fs::write(dir.path().join("allowlist.toml"), ALLOWLIST).unwrap();
+        std::fs::write(
+            dir.path().join(TEMPLATE_MANIFEST),
+            "[package]\nname = \"play-{BUILD_ID}\"\n\n[dependencies]\ndioxus = { version = \"0.6\", features = [\"web\", \"router\"] }\n",
+        )
+        .unwrap();
+        DependencyAllowlist::load(&dir.path().join("allowlist.toml"), dir.path()).unwrap()
+    }
+
+    fn project(manifest: &str) -> Project {
+        let files = ProjectFiles::from([
+            (model::MAIN_FILE.to_string(), "fn main() {}".to_string()),
+            (MANIFEST_FILE.to_string(), manifest.to_string()),
+        ]);
+        Project::from_files(files, None, None).unwrap()
+    }
+
+    #[test]
+    fn resolves_allowed_dependencies() {
+        let allowlist = allowlist();
+        let manifest = r#"
+            [package]
+            name = "my-project"
+
+            [dependencies]
+            dioxus = { version = "0.6.3", features = ["router"] }
+            serde = { version = "1", features = ["derive"] }
+            chrono = { version = "0.4", default-features = false }
+        "#;
+
+        let dependencies = allowlist.resolve(&project(manifest)).unwrap();
+        let names: Vec<&String> = dependencies.0.keys().collect();
+        assert_eq!(names, ["chrono", "serde"]);
+
+        // Every dependency is pinned with the allowed features.
+        let manifest = dependencies
+            .add_to_manifest("[dependencies]\ndioxus = \"0.6\"\n")
+            .unwrap();
+        let manifest: toml::Table = manifest.parse().unwrap();
+        let serde = &manifest["dependencies"]["serde"];
+        assert_eq!(serde["version"].as_str(), Some("=1.0.219"));
+        assert_eq!(serde["features"].as_array().unwrap().len(), 2);
+        let chrono = &manifest["dependencies"]["chrono"];
+        assert_eq!(chrono["default-features"].as_bool(), Some(false));
+        assert_eq!(manifest["dependencies"]["dioxus"].as_str(), Some("0.6"));
+    }
+
+    #[test]
+    fn projects_without_a_manifest_have_no_dependencies() {
+        let project = Project::new("fn main() {}", None, None);
+        assert!(allowlist().resolve(&project).unwrap().is_empty());
+    }
+
+    #[test]
+    fn rejects_dependencies_that_are_not_allowed() {
+        let allowlist = allowlist();
+
+        for (manifest, error) in [
+            (
+                "[dependencies]\ntokio = \"1\"",
+                "`tokio` is not an allowed dependency",
+            ),
+            ("[dependencies]\nserde = \"2\"", "`serde` can't be `2`"),
+            (
+                "[dependencies]\nserde = { version = \"1\", features = [\"std\"] }",
+                "`serde` can't have the `std` feature",
+            ),
+            (
+                "[dependencies]\nserde = { git = \"https://github.com/serde-rs/serde\" }",
+                "can only set `version`, `features` and `default-features`, not `git`",
+            ),
+            (
+                "[dependencies]\nchrono = { version = \"0.4\", default-features = true }",
+                "`chrono` is built with `default-features = false`",
+            ),
+            (
+                "[dependencies]\nserde = { version = \"1\", features = [\"derive\", 1] }",
+                "the `features` of `serde` must be strings",
+            ),
+            // The template's own dependencies are checked the same way.
+            (
+                "[dependencies]\ndioxus = \"0.5\"",
+                "`dioxus` can't be `0.5`",
+            ),
+            (
+                "[dependencies]\ndioxus = { version = \"0.6\", features = [\"desktop\"] }",
+                "`dioxus` can't have the `desktop` feature",
+            ),
+            (
+                "[dependencies]\ndioxus = { git = \"https://github.com/DioxusLabs/dioxus\" }",
+                "can only set `version`, `features` and `default-features`, not `git`",
+            ),
+            (
+                "[build-dependencies]\nserde = \"1\"",
+                "not `build-dependencies`",
+            ),
+            ("[dependencies", "`Cargo.toml` is not valid"),
+        ] {
+            let err = allowlist
+                .resolve(&project(manifest))
+                .unwrap_err()
+                .to_string();
+            assert!(err.contains(error), "{err}");
+            // Every error lists what projects can use.
+            assert!(
+                err.ends_with(
+                    "Projects can use: chrono 0.4.41, serde 1.0.219 (features: derive, rc)."
+                ),
+                "{err}"
+            );
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use dependencies::Dependencies;
 use model::CargoDiagnostic;
 use model::Project;
 use std::io;
@@ -6,20 +7,35 @@ use tokio::{sync::mpsc::UnboundedSender, task::JoinError};
 use uuid::Uuid;
 
 pub mod builder;
+pub mod dependencies;
+mod queue;
+pub mod toolchain;
 pub mod watcher;
 
 /// A build command which allows consumers of the builder api to submit and stop builds.
 #[derive(Debug, Clone)]
 pub enum BuildCommand {
-    Start { request: BuildRequest },
-    Stop { id: Uuid },
+    Start {
+        request: BuildRequest,
+    },
+    /// Stop the build with the id, if it was requested by the socket.
+    Stop {
+        id: Uuid,
+        socket_id: Uuid,
+    },
 }
 
 /// A build request which contains the id of the build, the code to be built, and a socket to send build updates.
 #[derive(Debug, Clone)]
 pub struct BuildRequest {
     pub id: Uuid,
+    /// Who requested the build, like the ip of the client. Builds are shared fairly between clients.
+    pub client: String,
+    /// The socket that requested the build. A socket can only stop its own builds.
+    pub socket_id: Uuid,
     pub project: Project,
+    /// The allowed dependencies the project declares, checked before the build is queued.
+    pub dependencies: Dependencies,
     pub ws_msg_tx: UnboundedSender<BuildMessage>,
 }
 
@@ -44,9 +60,10 @@ pub enum BuildError {
     /// DX ClI returned a non-success status code. This could be caused by invalid user-submitted code.
     #[error("dx returned a non-success status code: {0:?}")]
     DxFailed(Option<i32>),
-    /// The builder doesn't have a build to finish.
-    #[error("the builder doesn't have a build to finish")]
-    NotStarted,
+
+    /// `cargo vendor` returned a non-success status code while vendoring the allowed dependencies.
+    #[error("cargo vendor returned a non-success status code: {0:?}")]
+    VendorFailed(Option<i32>),
 
     #[error("build panicked: {0}")]
     Panicked(JoinError),
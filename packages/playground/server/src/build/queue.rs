@@ -0,0 +1,249 @@
+//! Fair scheduling of pending builds between clients.
+
+use super::{BuildMessage, BuildRequest};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// The builds waiting for a free builder.
+///
+/// Builds are started round-robin between clients: the next build is from the client whose last
+/// build started the longest time ago, so a client that submits many builds can't make everyone
+/// else wait. Each client's builds start in the order they were submitted. A client is only
+/// remembered while it has pending builds, so a client that comes back later goes first.
+#[derive(Default)]
+pub(crate) struct BuildQueue {
+    /// The pending builds in the order they were submitted.
+    pending: Vec<PendingBuild>,
+    /// The turn each client with pending builds last had a build started at. Clients that
+    /// haven't had a build started yet are missing and go first.
+    last_started: HashMap<String, u64>,
+    turn: u64,
+}
+
+struct PendingBuild {
+    request: BuildRequest,
+    /// The last queue position sent to the socket, or 0 if none was sent yet.
+    position: usize,
+}
+
+impl BuildQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a build to the end of its client's queue.
+    pub fn push(&mut self, request: BuildRequest) {
+        self.pending.push(PendingBuild {
+            request,
+            position: 0,
+        });
+    }
+
+    /// Remove the pending build with the id if it was requested by the socket.
+    pub fn remove(&mut self, id: Uuid, socket_id: Uuid) -> Option<BuildRequest> {
+        let index = self
+            .pending
+            .iter()
+            .position(|p| p.request.id == id && p.request.socket_id == socket_id)?;
+        let request = self.pending.remove(index).request;
+        self.forget_idle_client(&request.client);
+        Some(request)
+    }
+
+    /// Take the next build to start, skipping any builds that `is_blocked` returns true for.
+    pub fn next(&mut self, is_blocked: impl Fn(&BuildRequest) -> bool) -> Option<BuildRequest> {
+        let index = self
+            .order()
+            .into_iter()
+            .find(|&i| !is_blocked(&self.pending[i].request))?;
+        let request = self.pending.remove(index).request;
+
+        self.turn += 1;
+        self.last_started.insert(request.client.clone(), self.turn);
+        self.forget_idle_client(&request.client);
+
+        Some(request)
+    }
+
+    /// Stop tracking the client if it has no pending builds left.
+    fn forget_idle_client(&mut self, client: &str) {
+        if !self.pending.iter().any(|p| p.request.client == client) {
+            self.last_started.remove(client);
+        }
+    }
+
+    /// Send each pending build its position in the queue if it changed since it was last sent.
+    pub fn update_positions(&mut self) {
+        for (i, index) in self.order().into_iter().enumerate() {
+            let pending = &mut self.pending[index];
+            let position = i + 1;
+            if pending.position != position {
+                pending.position = position;
+                let _ = pending
+                    .request
+                    .ws_msg_tx
+                    .send(BuildMessage::QueuePosition(position));
+            }
+        }
+    }
+
+    /// The indices of the pending builds in the order they will start.
+    fn order(&self) -> Vec<usize> {
+        let mut last_started = self.last_started.clone();
+        let mut turn = self.turn;
+        let mut remaining: Vec<usize> = (0..self.pending.len()).collect();
+        let mut order = Vec::with_capacity(remaining.len());
+
+        while !remaining.is_empty() {
+            // The client that waited the longest goes next. Ties go to the oldest build.
+            let (next, &index) = remaining
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, &i)| {
+                    let client = &self.pending[i].request.client;
+                    last_started.get(client).copied().unwrap_or_default()
+                })
+                .expect("remaining builds should not be empty");
+
+            turn += 1;
+            last_started.insert(self.pending[index].request.client.clone(), turn);
+            order.push(index);
+            remaining.remove(next);
+        }
+
+        order
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use model::Project;
+    use tokio::sync::mpsc::{self, UnboundedReceiver};
+
+    fn request(client: &str, code: &str) -> (BuildRequest, UnboundedReceiver<BuildMessage>) {
+        let (ws_msg_tx, rx) = mpsc::unbounded_channel();
+        let project = Project::new(code, None, None);
+        let request = BuildRequest {
+            id: project.id(),
+            client: client.to_string(),
+            socket_id: Uuid::new_v4(),
+            project,
+            dependencies: Default::default(),
+            ws_msg_tx,
+        };
+        (request, rx)
+    }
+
+    fn positions(rx: &mut UnboundedReceiver<BuildMessage>) -> Vec<usize> {
+        let mut positions = Vec::new();
+        while let Ok(BuildMessage::QueuePosition(position)) = rx.try_recv() {
+            positions.push(position);
+        }
+        positions
+    }
+
+    #[test]
+    fn alternates_between_clients() {
+        let mut queue = BuildQueue::new();
+        let mut receivers = Vec::new();
+        for (client, code) in [("a", "1"), ("a", "2"), ("a", "3"), ("b", "4"), ("c", "5")] {
+            let (request, rx) = request(client, code);
+            queue.push(request);
+            receivers.push(rx);
+        }
+
+        let mut started = Vec::new();
+        while let Some(request) = queue.next(|_| false) {
+            started.push(request.project.contents());
+        }
+        assert_eq!(started, ["1", "4", "5", "2", "3"]);
+    }
+
+    #[test]
+    fn clients_that_just_built_wait_their_turn() {
+        let mut queue = BuildQueue::new();
+        let (a1, _a1) = request("a", "1");
+        let (a2, _a2) = request("a", "2");
+        queue.push(a1);
+        queue.push(a2);
+        assert_eq!(queue.next(|_| false).unwrap().project.contents(), "1");
+
+        let (b1, _b1) = request("b", "3");
+        queue.push(b1);
+        assert_eq!(queue.next(|_| false).unwrap().project.contents(), "3");
+        assert_eq!(queue.next(|_| false).unwrap().project.contents(), "2");
+    }
+
+    #[test]
+    fn forgets_clients_without_pending_builds() {
+        let mut queue = BuildQueue::new();
+        let (a1, _a1) = request("a", "1");
+        let (a2, _a2) = request("a", "2");
+        let (b1, _b1) = request("b", "3");
+        let (id, socket_id) = (a2.id, a2.socket_id);
+        queue.push(a1);
+        queue.push(a2);
+        queue.push(b1);
+
+        queue.next(|_| false);
+        assert!(queue.last_started.contains_key("a"));
+        queue.remove(id, socket_id).unwrap();
+        assert!(queue.last_started.is_empty());
+        queue.next(|_| false);
+        assert!(queue.last_started.is_empty());
+    }
+
+    #[test]
+    fn sends_positions_when_they_change() {
+        let mut queue = BuildQueue::new();
+        let (a1, mut a1_rx) = request("a", "1");
+        let (a2, mut a2_rx) = request("a", "2");
+        queue.push(a1);
+        queue.push(a2);
+        queue.update_positions();
+        assert_eq!(positions(&mut a1_rx), [1]);
+        assert_eq!(positions(&mut a2_rx), [2]);
+
+        // Another client cuts in front of the second build of `a`.
+        let (b1, mut b1_rx) = request("b", "3");
+        queue.push(b1);
+        queue.update_positions();
+        assert!(positions(&mut a1_rx).is_empty());
+        assert_eq!(positions(&mut b1_rx), [2]);
+        assert_eq!(positions(&mut a2_rx), [3]);
+
+        queue.next(|_| false);
+        queue.update_positions();
+        assert_eq!(positions(&mut b1_rx), [1]);
+        assert_eq!(positions(&mut a2_rx), [2]);
+    }
+
+    #[test]
+    fn skips_blocked_builds() {
+        let mut queue = BuildQueue::new();
+        let (a1, _a1) = request("a", "1");
+        let (b1, _b1) = request("b", "2");
+        let blocked = a1.id;
+        queue.push(a1);
+        queue.push(b1);
+
+        let next = queue.next(|request| request.id == blocked).unwrap();
+        assert_eq!(next.project.contents(), "2");
+        assert!(queue.next(|request| request.id == blocked).is_none());
+    }
+
+    #[test]
+    fn only_removes_builds_of_the_socket() {
+        let mut queue = BuildQueue::new();
+        let (a1, _a1) = request("a", "same code");
+        let (b1, _b1) = request("b", "same code");
+        let (id, socket_id) = (b1.id, b1.socket_id);
+        queue.push(a1);
+        queue.push(b1);
+
+        assert!(queue.remove(id, Uuid::new_v4()).is_none());
+        assert_eq!(queue.remove(id, socket_id).unwrap().client, "b");
+        assert_eq!(queue.next(|_| false).unwrap().client, "a");
+    }
+}
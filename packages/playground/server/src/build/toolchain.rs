@@ -0,0 +1,86 @@
+//! The toolchain that compiles playground projects.
+
+use super::BuildError;
+use dioxus_logger::tracing::debug;
+use futures::future::BoxFuture;
+use std::path::Path;
+use std::process::Stdio;
+use tokio::io::{AsyncBufReadExt as _, BufReader};
+use tokio::process::Command;
+use tokio::sync::mpsc::UnboundedSender;
+use uuid::Uuid;
+
+/// Compiles the project in a build template for the web.
+///
+/// The server builds with [`Dx`]. Tests can swap in a fake toolchain that writes canned output
+/// instead of compiling anything.
+pub trait Toolchain: Send + Sync {
+    /// Build the project set up in `template_path`, sending each line of `dx build --json-output`
+    /// to `output` as it is written.
+    ///
+    /// A successful build must leave the app in `target/dx/play-{build_id}/debug/web/public`
+    /// inside the template.
+    fn build(
+        &self,
+        template_path: &Path,
+        build_id: Uuid,
+        output: UnboundedSender<String>,
+    ) -> BoxFuture<'static, Result<(), BuildError>>;
+}
+
+/// The DX CLI, found on the `PATH`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Dx;
+
+impl Toolchain for Dx {
+    fn build(
+        &self,
+        template_path: &Path,
+        _build_id: Uuid,
+        output: UnboundedSender<String>,
+    ) -> BoxFuture<'static, Result<(), BuildError>> {
+        let template_path = template_path.to_path_buf();
+
+        Box::pin(async move {
+            let mut child = Command::new("dx")
+                .arg("build")
+                .arg("--platform")
+                .arg("web")
+                .arg("--json-output")
+                .arg("--verbose")
+                .arg("--trace")
+                .current_dir(template_path)
+                // Stopping a build drops it, which should stop dx too.
+                .kill_on_drop(true)
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .spawn()?;
+
+            let stdout = child.stdout.take().expect("dx stdout should exist");
+            let mut stdout_reader = BufReader::new(stdout).lines();
+
+            let mut logs = Vec::new();
+
+            // Read stdout lines from DX until it closes stdout.
+            while let Ok(Some(line)) = stdout_reader.next_line().await {
+                logs.push(line.clone());
+                let _ = output.send(line);
+            }
+
+            // Check if the build was successful.
+            let exit_code = child.wait().await.map(|c| c.code());
+            match exit_code {
+                Ok(Some(0)) => Ok(()),
+                Ok(Some(code)) => {
+                    // Dump logs in debug.
+                    for log in logs {
+                        debug!("{log}");
+                    }
+
+                    Err(BuildError::DxFailed(Some(code)))
+                }
+                _ => Err(BuildError::DxFailed(None)),
+            }
+        })
+    }
+}
@@ -1,9 +1,19 @@
-use super::{builder::Builder, BuildCommand, BuildError, BuildMessage, BuildRequest};
+use super::{
+    builder::Builder, queue::BuildQueue, toolchain::Toolchain, BuildCommand, BuildError,
+    BuildMessage, BuildRequest,
+};
 use crate::app::EnvVars;
+use crate::cache::BuildCache;
+use dioxus_logger::tracing::{error, info, trace, warn};
+use fs_extra::dir::CopyOptions;
+use futures::future::select_all;
 use std::{
-    collections::VecDeque,
     error::Error as _,
-    sync::{atomic::AtomicBool, Arc},
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
 };
 use tokio::{
     select,
@@ -11,144 +21,159 @@ use tokio::{
 };
 use uuid::Uuid;
 
+/// Something the build watcher has to handle.
+enum WatcherEvent {
+    Command(BuildCommand),
+    Finished(BuildRequest, Result<(), BuildError>),
+}
+
 /// Start the build watcher.
 ///
 /// The build watcher receives [`BuildCommand`]s through a channel and handles
 /// the build queue, providing queue positions, and stopping/cancelling builds.
+/// Builds run on a pool of [`EnvVars::build_workers`] builders, each with its own template.
 pub fn start_build_watcher(
     env: EnvVars,
+    cache: Arc<BuildCache>,
+    toolchain: Arc<dyn Toolchain>,
     is_building: Arc<AtomicBool>,
 ) -> UnboundedSender<BuildCommand> {
     let (tx, mut rx) = mpsc::unbounded_channel();
 
     tokio::spawn(async move {
-        let mut builder = Builder::new(env, is_building);
-        let mut pending_builds = VecDeque::new();
+        let mut builders: Vec<Builder> = prepare_worker_templates(&env)
+            .await
+            .into_iter()
+            .map(|template_path| Builder::new(template_path, cache.clone(), toolchain.clone()))
+            .collect();
+        let mut pending_builds = BuildQueue::new();
 
         loop {
-            select! {
+            // The builders are borrowed while waiting, so events are handled after the select.
+            let event = select! {
+                Some(command) = rx.recv() => WatcherEvent::Command(command),
+                ((request, result), _, _) = select_all(builders.iter_mut().map(|b| Box::pin(b.finished()))) => {
+                    WatcherEvent::Finished(request, result)
+                }
+            };
+
+            match event {
                 // Handle incoming build commands.
-                Some(command) = rx.recv() => {
-                    match command {
-                        BuildCommand::Start { request } => start_build(&mut builder, &mut pending_builds, request),
-                        BuildCommand::Stop { id } => stop_build(&mut builder, &mut pending_builds, id),
-                    }
+                WatcherEvent::Command(BuildCommand::Start { request }) => {
+                    pending_builds.push(request)
+                }
+                WatcherEvent::Command(BuildCommand::Stop { id, socket_id }) => {
+                    stop_build(&mut builders, &mut pending_builds, id, socket_id)
                 }
-                // Handle finished build or make progress on current build.
-                build_result = builder.finished() => handle_finished_build(&mut builder, &mut pending_builds, build_result),
+                // Handle finished builds or make progress on current builds.
+                WatcherEvent::Finished(request, result) => handle_finished_build(request, result),
             }
+
+            start_pending_builds(&mut builders, &mut pending_builds);
+            pending_builds.update_positions();
+            is_building.store(builders.iter().any(Builder::has_build), Ordering::SeqCst);
         }
     });
 
     tx
 }
 
-/// Start a build or add it to the queue.
-fn start_build(
-    builder: &mut Builder,
-    pending_builds: &mut VecDeque<BuildRequest>,
-    request: BuildRequest,
-) {
-    // If the builder has a build, add to queue, otherwise start the build.
-    match builder.has_build() {
-        false => builder.start(request),
-        true => {
-            let _ = request
-                .ws_msg_tx
-                .send(BuildMessage::QueuePosition(pending_builds.len() + 1));
-
-            pending_builds.push_back(request);
+/// Get a template for each worker.
+///
+/// The first worker uses the build template and every other worker uses a copy of it next to
+/// the template, so their builds don't share a target directory. Copies are made from the
+/// template, including any dependencies it already compiled, if they don't exist yet.
+async fn prepare_worker_templates(env: &EnvVars) -> Vec<PathBuf> {
+    let template_path = env.build_template_path.clone();
+    let mut template_paths = vec![template_path.clone()];
+
+    for worker in 1..env.build_workers.max(1) {
+        let worker_path = env.worker_template_path(worker);
+        if !worker_path.exists() {
+            info!(
+                ?worker_path,
+                "copying the build template for worker {worker}"
+            );
+            let from = template_path.clone();
+            let to = worker_path.clone();
+            let copied = tokio::task::spawn_blocking(move || {
+                std::fs::create_dir_all(&to)?;
+                let options = CopyOptions::new().content_only(true);
+                fs_extra::dir::copy(from, to, &options)?;
+                Ok::<_, BuildError>(())
+            })
+            .await;
+
+            if let Err(err) = copied.map_err(BuildError::from).and_then(|r| r) {
+                error!(?err, ?worker_path, "failed to copy the build template");
+                let _ = std::fs::remove_dir_all(&worker_path);
+                continue;
+            }
         }
-    };
-}
-
-/// Stop the current build by:
-/// - Checking if it's the current build and if so, stop it, update queue positions, and return early.
-/// - Iterate through queue looking for a matching id.
-///   If matching id found, update queue positions *behind* matching queue and remove matched item.
-fn stop_build(builder: &mut Builder, pending_builds: &mut VecDeque<BuildRequest>, id: Uuid) {
-    // Check if the ongoing build is the cancelled build.
-    let current_build_id = builder.current_build().map(|b| b.id);
-    if let Some(current_build_id) = current_build_id {
-        if id == current_build_id {
-            builder.stop_current();
+        template_paths.push(worker_path);
+    }
 
-            // Start the next build request.
-            let next_request = pending_builds.pop_front();
-            if let Some(request) = next_request {
-                builder.start(request);
-            }
+    template_paths
+}
 
-            update_queue_positions(pending_builds);
+/// Start pending builds on every idle builder.
+///
+/// A build isn't started while another builder builds the same project, since both would
+/// publish to the same place. It starts once that build finishes and is then served as is.
+fn start_pending_builds(builders: &mut [Builder], pending_builds: &mut BuildQueue) {
+    loop {
+        let building: Vec<Uuid> = builders
+            .iter()
+            .filter_map(|b| b.current_build().map(|r| r.id))
+            .collect();
+        let Some(builder) = builders.iter_mut().find(|b| !b.has_build()) else {
             return;
-        }
+        };
+        let Some(request) = pending_builds.next(|r| building.contains(&r.id)) else {
+            return;
+        };
+        builder.start(request);
     }
+}
 
-    // Try finding the build in the queue
-    let mut matching_id = None;
-
-    for (i, build_request) in pending_builds.iter_mut().enumerate() {
-        if build_request.id == id {
-            matching_id = Some(i);
-            continue;
-        }
+/// Stop a build by:
+/// - Stopping the build if a builder is building it for the socket.
+/// - Otherwise, removing the build from the queue if the socket queued it.
+///
+/// Builds of the same project requested by other sockets keep going.
+fn stop_build(
+    builders: &mut [Builder],
+    pending_builds: &mut BuildQueue,
+    id: Uuid,
+    socket_id: Uuid,
+) {
+    let current = builders.iter_mut().find(|b| {
+        b.current_build()
+            .is_some_and(|r| r.id == id && r.socket_id == socket_id)
+    });
 
-        // Tell any other requests behind the removed that they're moving up.
-        if matching_id.is_some() {
-            let _ = build_request
-                .ws_msg_tx
-                .send(BuildMessage::QueuePosition(i - 1));
+    match current {
+        Some(builder) => builder.stop_current(),
+        None => {
+            pending_builds.remove(id, socket_id);
         }
     }
-
-    // Remove the stopped build.
-    if let Some(id) = matching_id {
-        pending_builds.remove(id);
-    }
 }
 
-/// Handle a finished build by:
-/// - Finishing the current build, sending the BuildMessage::Finnished to the socket.
-/// - Start the next build.
-/// - Update queue positions.
-fn handle_finished_build(
-    builder: &mut Builder,
-    pending_builds: &mut VecDeque<BuildRequest>,
-    build_result: Result<BuildRequest, BuildError>,
-) {
-    // Tell the socket the result of their build.
-    let _ = match build_result {
-        Ok(request) => {
-            dioxus::logger::tracing::trace!(request = ?request, "build finished");
+/// Handle a finished build by sending the result of the build to the socket.
+fn handle_finished_build(request: BuildRequest, result: Result<(), BuildError>) {
+    let _ = match result {
+        Ok(()) => {
+            trace!(request = ?request, "build finished");
             request
                 .ws_msg_tx
                 .send(BuildMessage::Finished(Ok(request.id)))
         }
         Err(e) => {
-            dioxus::logger::tracing::warn!(err = ?e, src = ?e.source(), "build failed");
-            match builder.current_build() {
-                Some(request) => request
-                    .ws_msg_tx
-                    .send(BuildMessage::Finished(Err(e.to_string()))),
-                None => Ok(()),
-            }
+            warn!(err = ?e, src = ?e.source(), "build failed");
+            request
+                .ws_msg_tx
+                .send(BuildMessage::Finished(Err(e.to_string())))
         }
     };
-
-    // Start the next build.
-    let next_request = pending_builds.pop_front();
-    if let Some(request) = next_request {
-        builder.start(request);
-    }
-
-    update_queue_positions(pending_builds);
-}
-
-/// Iterate through the queue and alert each request with their current queue position.
-fn update_queue_positions(pending_builds: &mut VecDeque<BuildRequest>) {
-    for (i, build_request) in pending_builds.iter_mut().enumerate() {
-        let _ = build_request
-            .ws_msg_tx
-            .send(BuildMessage::QueuePosition(i + 1));
-    }
 }
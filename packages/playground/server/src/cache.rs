@@ -0,0 +1,391 @@
+//! The cache of built projects that the server serves.
+
+use dioxus_logger::tracing::{info, trace, warn};
+use std::{
+    collections::{HashMap, HashSet},
+    io,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+use uuid::Uuid;
+
+/// The directory in the cache that builds are staged and evicted in. It is on the same file
+/// system as the cache, so builds can be moved in and out of the cache atomically.
+const STAGING_DIR: &str = ".staging";
+
+/// Built projects, stored under `built_path/<id>` where the id is the hash of the project.
+///
+/// The cache holds builds up to a size budget. Once a new build goes over the budget, the
+/// builds that were served the longest time ago are evicted. Pinned builds, like the examples,
+/// are never evicted.
+pub struct BuildCache {
+    root: PathBuf,
+    budget: u64,
+    state: Mutex<CacheState>,
+}
+
+#[derive(Default)]
+struct CacheState {
+    entries: HashMap<Uuid, CacheEntry>,
+    pinned: HashSet<Uuid>,
+    size: u64,
+    /// A logical clock that orders accesses to the builds.
+    clock: u64,
+}
+
+struct CacheEntry {
+    size: u64,
+    last_access: u64,
+}
+
+impl BuildCache {
+    /// Open the cache in `root`, recovering the builds that are already in it.
+    ///
+    /// Builds are recovered oldest first by their modification time, and any builds that were
+    /// staged or evicted when the server stopped are removed. The `pinned` builds are pinned
+    /// before builds over the budget are evicted, so a smaller budget never evicts the examples.
+    pub fn open(
+        root: impl Into<PathBuf>,
+        budget: u64,
+        pinned: impl IntoIterator<Item = Uuid>,
+    ) -> io::Result<Self> {
+        let root = root.into();
+        std::fs::create_dir_all(&root)?;
+
+        let staging = root.join(STAGING_DIR);
+        if staging.exists() {
+            std::fs::remove_dir_all(&staging)?;
+        }
+
+        let mut builds = Vec::new();
+        for item in std::fs::read_dir(&root)? {
+            let item = item?;
+            let Some(id) = item
+                .file_name()
+                .to_str()
+                .and_then(|name| Uuid::parse_str(name).ok())
+            else {
+                continue;
+            };
+            if !item.file_type()?.is_dir() {
+                continue;
+            }
+
+            let modified = item.metadata()?.modified()?;
+            builds.push((modified, id, dir_size(&item.path())?));
+        }
+        builds.sort();
+
+        let cache = Self {
+            root,
+            budget,
+            state: Mutex::new(CacheState {
+                pinned: pinned.into_iter().collect(),
+                ..Default::default()
+            }),
+        };
+
+        let mut state = cache.lock();
+        for (_, id, size) in builds {
+            state.insert(id, size);
+        }
+        info!(
+            builds = state.entries.len(),
+            size = state.size,
+            "recovered the built projects cache"
+        );
+        let evicted = cache.evict(&mut state, None);
+        drop(state);
+        remove_evicted(evicted);
+
+        Ok(cache)
+    }
+
+    /// The directory a build is served from.
+    pub fn path(&self, id: Uuid) -> PathBuf {
+        self.root.join(id.to_string())
+    }
+
+    /// Check if the cache has a build, counting it as an access.
+    pub fn get(&self, id: Uuid) -> bool {
+        self.lock().touch(id)
+    }
+
+    /// Count an access to a build, like when its files are served.
+    pub fn touch(&self, id: Uuid) {
+        self.lock().touch(id);
+    }
+
+    /// Never evict the build. The build can be pinned before it is published.
+    pub fn pin(&self, id: Uuid) {
+        self.lock().pinned.insert(id);
+    }
+
+    /// Move a finished build from `dir` into the cache and evict old builds over the budget.
+    ///
+    /// The build is moved into a staging directory next to the builds first, and then renamed
+    /// to its id, so the build is either served in full or not at all.
+    pub fn publish(&self, id: Uuid, dir: &Path) -> io::Result<()> {
+        let staging = self.root.join(STAGING_DIR);
+        std::fs::create_dir_all(&staging)?;
+        let staged = staging.join(format!("{id}-{}", Uuid::new_v4()));
+
+        // A rename is only possible on the same file system, otherwise copy the build over.
+        if std::fs::rename(dir, &staged).is_err() {
+            std::fs::create_dir_all(&staged)?;
+            let options = fs_extra::dir::CopyOptions::new().content_only(true);
+            fs_extra::dir::move_dir(dir, &staged, &options).map_err(io::Error::other)?;
+        }
+        let size = dir_size(&staged)?;
+
+        let mut state = self.lock();
+        let path = self.path(id);
+        if state.entries.contains_key(&id) || path.exists() {
+            // The build was published while this one built. Keep the build that is served.
+            drop(state);
+            std::fs::remove_dir_all(&staged)?;
+            return Ok(());
+        }
+
+        std::fs::rename(&staged, &path)?;
+        state.insert(id, size);
+        trace!(?id, size, "published build");
+        let evicted = self.evict(&mut state, Some(id));
+        drop(state);
+        remove_evicted(evicted);
+
+        Ok(())
+    }
+
+    /// The total size of the builds in the cache in bytes.
+    pub fn size(&self) -> u64 {
+        self.lock().size
+    }
+
+    /// Evict the least recently used builds until the cache fits in the budget.
+    ///
+    /// Evicted builds are only moved out of the served directory. They are returned so they can
+    /// be deleted with [`remove_evicted`] after the lock is released, since deleting a large
+    /// build would block every request that checks the cache.
+    fn evict(&self, state: &mut CacheState, keep: Option<Uuid>) -> Vec<PathBuf> {
+        let mut evicted = Vec::new();
+        while state.size > self.budget {
+            let oldest = state
+                .entries
+                .iter()
+                .filter(|(id, _)| Some(**id) != keep && !state.pinned.contains(id))
+                .min_by_key(|(_, entry)| entry.last_access)
+                .map(|(id, _)| *id);
+            let Some(id) = oldest else {
+                warn!(
+                    size = state.size,
+                    budget = self.budget,
+                    "the pinned builds don't fit in the cache budget"
+                );
+                break;
+            };
+
+            match self.stage_removal(id) {
+                Ok(path) => evicted.push(path),
+                Err(err) => warn!(?id, ?err, "failed to evict build"),
+            }
+            let entry = state.entries.remove(&id).expect("evicted build is cached");
+            state.size -= entry.size;
+            trace!(?id, "evicted build");
+        }
+        evicted
+    }
+
+    /// Move a build out of the served directory into the staging directory, so it isn't half
+    /// deleted while it's served. Returns where the build was moved.
+    fn stage_removal(&self, id: Uuid) -> io::Result<PathBuf> {
+        let evicted = self
+            .root
+            .join(STAGING_DIR)
+            .join(format!("evicted-{id}-{}", Uuid::new_v4()));
+        std::fs::create_dir_all(self.root.join(STAGING_DIR))?;
+        std::fs::rename(self.path(id), &evicted)?;
+        Ok(evicted)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, CacheState> {
+        self.state.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+impl CacheState {
+    fn insert(&mut self, id: Uuid, size: u64) {
+        self.clock += 1;
+        let entry = CacheEntry {
+            size,
+            last_access: self.clock,
+        };
+        if let Some(old) = self.entries.insert(id, entry) {
+            self.size -= old.size;
+        }
+        self.size += size;
+    }
+
+    fn touch(&mut self, id: Uuid) -> bool {
+        self.clock += 1;
+        match self.entries.get_mut(&id) {
+            Some(entry) => {
+                entry.last_access = self.clock;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Delete the builds [`BuildCache::evict`] moved out of the cache. Builds that can't be deleted
+/// are left in the staging directory, which is cleared when the cache is opened again.
+fn remove_evicted(evicted: Vec<PathBuf>) {
+    for path in evicted {
+        if let Err(err) = std::fs::remove_dir_all(&path) {
+            warn!(?path, ?err, "failed to delete evicted build");
+        }
+    }
+}
+
+/// The total size of the files in a directory.
+fn dir_size(path: &Path) -> io::Result<u64> {
+    let mut size = 0;
+    for item in std::fs::read_dir(path)? {
+        let item = item?;
+        let metadata = item.metadata()?;
+        size += match metadata.is_dir() {
+            true => dir_size(&item.path())?,
+            false => metadata.len(),
+        };
+    }
+    Ok(size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Write a build of `size` bytes to a directory outside the cache.
+    fn build(dir: &Path, size: usize) -> PathBuf {
+        let build = dir.join(Uuid::new_v4().to_string());
+        std::fs::create_dir_all(build.join("assets")).unwrap();
+        std::fs::write(build.join("index.html"), "x".repeat(size / 2)).unwrap();
+        std::fs::write(
+            build.join("assets/app_bg.wasm"),
+            "x".repeat(size - size / 2),
+        )
+        .unwrap();
+        build
+    }
+
+    #[test]
+    fn publishes_builds() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = BuildCache::open(dir.path().join("built"), 1000, []).unwrap();
+        let id = Uuid::new_v4();
+
+        let built = build(dir.path(), 100);
+        cache.publish(id, &built).unwrap();
+
+        assert!(!built.exists());
+        assert!(cache.get(id));
+        assert!(cache.path(id).join("assets/app_bg.wasm").exists());
+        assert_eq!(cache.size(), 100);
+        // Nothing is left behind in the staging directory.
+        let staging = dir.path().join("built").join(STAGING_DIR);
+        assert_eq!(std::fs::read_dir(staging).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn evicts_least_recently_used_builds() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = BuildCache::open(dir.path().join("built"), 250, []).unwrap();
+        let [a, b, c] = [Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4()];
+
+        cache.publish(a, &build(dir.path(), 100)).unwrap();
+        cache.publish(b, &build(dir.path(), 100)).unwrap();
+        cache.touch(a);
+        cache.publish(c, &build(dir.path(), 100)).unwrap();
+
+        assert!(cache.get(a));
+        assert!(!cache.get(b));
+        assert!(!cache.path(b).exists());
+        assert!(cache.get(c));
+        assert_eq!(cache.size(), 200);
+    }
+
+    #[test]
+    fn keeps_pinned_builds() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = BuildCache::open(dir.path().join("built"), 150, []).unwrap();
+        let [example, a, b] = [Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4()];
+
+        cache.pin(example);
+        cache.publish(example, &build(dir.path(), 100)).unwrap();
+        cache.publish(a, &build(dir.path(), 40)).unwrap();
+        cache.publish(b, &build(dir.path(), 40)).unwrap();
+
+        assert!(cache.get(example));
+        assert!(!cache.get(a));
+        assert!(cache.get(b));
+    }
+
+    #[test]
+    fn keeps_new_builds_over_the_budget() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = BuildCache::open(dir.path().join("built"), 50, []).unwrap();
+        let id = Uuid::new_v4();
+
+        cache.publish(id, &build(dir.path(), 100)).unwrap();
+        assert!(cache.get(id));
+    }
+
+    #[test]
+    fn recovers_builds_on_open() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("built");
+        let [a, b] = [Uuid::new_v4(), Uuid::new_v4()];
+        {
+            let cache = BuildCache::open(&root, 1000, []).unwrap();
+            cache.publish(a, &build(dir.path(), 100)).unwrap();
+            cache.publish(b, &build(dir.path(), 100)).unwrap();
+        }
+        // A build that was being staged when the server stopped, and a file that isn't a build.
+        std::fs::create_dir_all(root.join(STAGING_DIR).join("half-moved")).unwrap();
+        std::fs::write(root.join("notes.txt"), "not a build").unwrap();
+
+        let cache = BuildCache::open(&root, 1000, []).unwrap();
+        assert!(cache.get(a));
+        assert!(cache.get(b));
+        assert_eq!(cache.size(), 200);
+        assert!(!root.join(STAGING_DIR).exists());
+
+        // Opening with a smaller budget evicts builds right away.
+        drop(cache);
+        let cache = BuildCache::open(&root, 150, []).unwrap();
+        assert_eq!(cache.size(), 100);
+        assert_eq!(
+            std::fs::read_dir(root.join(STAGING_DIR)).unwrap().count(),
+            0
+        );
+    }
+
+    #[test]
+    fn keeps_pinned_builds_on_open() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("built");
+        let [example, a] = [Uuid::new_v4(), Uuid::new_v4()];
+        {
+            let cache = BuildCache::open(&root, 1000, []).unwrap();
+            cache.publish(example, &build(dir.path(), 100)).unwrap();
+            cache.publish(a, &build(dir.path(), 100)).unwrap();
+        }
+
+        // The example is the oldest build, but it is pinned before the cache evicts anything.
+        let cache = BuildCache::open(&root, 150, [example]).unwrap();
+        assert!(cache.get(example));
+        assert!(!cache.get(a));
+        assert!(!cache.path(a).exists());
+    }
+}
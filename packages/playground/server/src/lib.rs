@@ -0,0 +1,136 @@
+//! The Dioxus Playground server: builds projects submitted over a websocket, serves the built
+//! apps and stores shared projects.
+
+use axum::{
+    error_handling::HandleErrorLayer,
+    extract::{Request, State},
+    http::StatusCode,
+    middleware::{self, Next},
+    response::{Redirect, Response},
+    routing::{get, post},
+    BoxError, Router,
+};
+use axum_client_ip::ClientIpSource;
+use dioxus_logger::tracing::error;
+use share::{get_shared_project, share_project};
+use std::{sync::atomic::Ordering, time::Duration};
+use tokio::time::Instant;
+use tower::{buffer::BufferLayer, limit::RateLimitLayer, ServiceBuilder};
+use tower_http::{compression::CompressionLayer, cors::CorsLayer};
+
+pub mod app;
+pub mod build;
+pub mod cache;
+mod serve;
+pub mod share;
+mod ws;
+
+pub use app::{AppState, EnvVars, ShareStoreKind};
+
+/// Rate limiter configuration.
+/// How many requests each user should get within a time period.
+const REQUESTS_PER_INTERVAL: u64 = 30;
+/// The period of time after the request limit resets.
+const RATE_LIMIT_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Build the router for the server.
+///
+/// The router reads the client ip from the `Fly-Client-IP` header in production and from the
+/// connection otherwise, so serve it with `into_make_service_with_connect_info::<SocketAddr>()`.
+pub fn router(state: AppState) -> Router {
+    let client_ip_src = match state.env.production {
+        true => ClientIpSource::FlyClientIp,
+        false => ClientIpSource::ConnectInfo,
+    };
+
+    // Build the routers.
+    let built_router = Router::new()
+        .route("/", get(serve::serve_built_index))
+        .route("/{*file_path}", get(serve::serve_other_built));
+
+    let shared_router = Router::new()
+        .route("/", post(share_project))
+        .route("/{id}", get(get_shared_project));
+
+    Router::new()
+        .route("/ws", get(ws::ws_handler))
+        .nest("/built/{build_id}", built_router)
+        // Nested routers don't match a trailing slash, but the index is loaded with and without it.
+        .route("/built/{build_id}/", get(serve::serve_built_index))
+        .nest("/shared", shared_router)
+        .route(
+            "/",
+            get(|| async { Redirect::permanent("https://dioxuslabs.com/play") }),
+        )
+        .route("/health", get(|| async { StatusCode::OK }))
+        .layer(
+            ServiceBuilder::new()
+                .layer(HandleErrorLayer::new(|error: BoxError| async move {
+                    error!(?error, "unhandled server error");
+                    (StatusCode::INTERNAL_SERVER_ERROR, "Internal Server Error")
+                }))
+                .layer(CompressionLayer::new())
+                .layer(CorsLayer::very_permissive())
+                .layer(BufferLayer::new(1024))
+                .layer(RateLimitLayer::new(
+                    REQUESTS_PER_INTERVAL,
+                    RATE_LIMIT_INTERVAL,
+                ))
+                .layer(client_ip_src.into_extension())
+                .layer(middleware::from_fn_with_state(
+                    state.clone(),
+                    request_counter,
+                )),
+        )
+        .with_state(state)
+}
+
+/// Start the shutdown watcher, which shuts the server down once it has been inactive for the
+/// [`EnvVars::shutdown_delay`], if there is one.
+pub fn start_shutdown_watcher(state: AppState) {
+    let Some(shutdown_delay) = state.env.shutdown_delay else {
+        return;
+    };
+
+    tokio::task::spawn(async move {
+        loop {
+            tokio::time::sleep(shutdown_delay).await;
+
+            // Check if server should shut down.
+            let should_shutdown = check_shutdown(&state, &shutdown_delay).await;
+            if should_shutdown {
+                // TODO: We could be more graceful here.
+                std::process::exit(0);
+            }
+        }
+    });
+}
+
+/// Check if the server should shutdown.
+async fn check_shutdown(state: &AppState, shutdown_delay: &Duration) -> bool {
+    let now = Instant::now();
+    let mut last_req_time = state.last_request_time.lock().await;
+
+    // Reset timer when build is occuring.
+    if state.is_building.load(Ordering::SeqCst) {
+        *last_req_time = now;
+        return false;
+    }
+
+    // Exit program if not building and duration exceeds shutdown time.
+    let duration_since_req = now.duration_since(*last_req_time);
+    if duration_since_req.as_secs() >= shutdown_delay.as_secs() {
+        return true;
+    }
+
+    false
+}
+
+/// A middleware that counts the time since the last request for the shutdown watcher.
+async fn request_counter(State(state): State<AppState>, req: Request, next: Next) -> Response {
+    let now = Instant::now();
+    let mut lock = state.last_request_time.lock().await;
+    *lock = now;
+    drop(lock);
+    next.run(req).await
+}
@@ -11,13 +11,14 @@ use uuid::Uuid;
 
 use crate::app::AppState;
 
-/// Handle providing temporary built wasm assets.
-/// This should delete temporary projects after 30 seconds.
+/// Handle providing built wasm assets from the build cache.
+/// Every request counts as an access, so builds that are in use aren't evicted.
 pub async fn serve_built_index(
     State(state): State<AppState>,
     Path(build_id): Path<Uuid>,
 ) -> impl IntoResponse {
-    let path = state.env.built_path.join(build_id.to_string());
+    state.cache.touch(build_id);
+    let path = state.cache.path(build_id);
 
     let index_path = path.join("index.html");
     let file = match tokio::fs::File::open(index_path.clone()).await {
@@ -40,11 +41,8 @@ pub async fn serve_other_built(
     State(state): State<AppState>,
     Path((build_id, file_path)): Path<(Uuid, PathBuf)>,
 ) -> impl IntoResponse {
-    let path = state
-        .env
-        .built_path
-        .join(build_id.to_string())
-        .join(file_path);
+    state.cache.touch(build_id);
+    let path = state.cache.path(build_id).join(file_path);
 
     let file = match tokio::fs::File::open(path.clone()).await {
         Ok(f) => f,
@@ -62,6 +60,12 @@ pub async fn serve_other_built(
     let content_type = match file_ext.to_str() {
         Some("wasm") => "application/wasm",
         Some("js") => "application/javascript",
+        // Assets from the project's `assets` folder.
+        Some("css") => "text/css",
+        Some("svg") => "image/svg+xml",
+        Some("png") => "image/png",
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("ico") => "image/x-icon",
         Some(_) => {
             warn!(build_id = ?build_id, path = ?path, "project tried accessing denied file");
             return Err((StatusCode::NOT_FOUND, "not found"));
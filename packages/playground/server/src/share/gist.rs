@@ -0,0 +1,160 @@
+//! Sharing projects as GitHub Gists.
+
+use super::{ShareError, ShareStore};
+use futures::future::BoxFuture;
+use model::{ProjectFiles, MAIN_FILE};
+use reqwest::{header, RequestBuilder, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, time::Duration};
+
+const GISTS_URL_PREFIX: &str = "https://api.github.com/gists";
+const GITHUB_USER_AGENT: &str = "Dioxus Playground";
+const PRIMARY_GIST_FILE_NAME: &str = "dxp.rs";
+/// Gist file names can't have a `/`, so it is replaced with a character project paths can't have.
+const GIST_PATH_SEPARATOR: &str = "~";
+
+/// Stores shared projects as public GitHub Gists, with the gist id as the share id.
+///
+/// The main file is stored as `dxp.rs` and the other files by their path, like
+/// `src~components.rs`. Gists don't expire, so projects are kept until they are deleted.
+pub struct GistStore {
+    client: reqwest::Client,
+    auth_token: String,
+}
+
+impl GistStore {
+    pub fn new(client: reqwest::Client, auth_token: String) -> Self {
+        Self { client, auth_token }
+    }
+
+    /// Add the headers the GitHub API needs to a request.
+    fn github(&self, request: RequestBuilder) -> RequestBuilder {
+        request
+            .bearer_auth(&self.auth_token)
+            .header(header::ACCEPT, "application/vnd.github+json")
+            .header(header::USER_AGENT, GITHUB_USER_AGENT)
+    }
+}
+
+impl ShareStore for GistStore {
+    fn create(
+        &self,
+        files: ProjectFiles,
+        _expiry: Option<Duration>,
+    ) -> BoxFuture<'_, Result<String, ShareError>> {
+        Box::pin(async move {
+            let files = files
+                .into_iter()
+                .map(|(path, content)| (gist_file_name(&path), GistFile { content }))
+                .collect();
+
+            let new_gist = NewGist {
+                description: "A user-saved Dioxus Playground snippet.".to_string(),
+                public: true,
+                files,
+            };
+
+            let res = self
+                .github(self.client.post(GISTS_URL_PREFIX))
+                .json(&new_gist)
+                .send()
+                .await?;
+
+            Ok(res.json::<Gist>().await?.id)
+        })
+    }
+
+    fn get<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<ProjectFiles, ShareError>> {
+        Box::pin(async move {
+            let res = self
+                .github(self.client.get(format!("{GISTS_URL_PREFIX}/{id}")))
+                .send()
+                .await?;
+
+            // Was the gist found?
+            if res.status() == StatusCode::NOT_FOUND {
+                return Err(ShareError::NotFound);
+            }
+
+            let gist = res.json::<Gist>().await?;
+            let files = gist
+                .files
+                .into_iter()
+                .map(|(name, file)| (project_path(&name), file.content))
+                .collect::<ProjectFiles>();
+
+            // Gists that weren't shared from the playground are not projects.
+            if !files.contains_key(MAIN_FILE) {
+                return Err(ShareError::NotFound);
+            }
+            Ok(files)
+        })
+    }
+
+    fn delete<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<(), ShareError>> {
+        Box::pin(async move {
+            let res = self
+                .github(self.client.delete(format!("{GISTS_URL_PREFIX}/{id}")))
+                .send()
+                .await?;
+
+            if res.status() == StatusCode::NOT_FOUND {
+                return Err(ShareError::NotFound);
+            }
+            res.error_for_status()?;
+
+            Ok(())
+        })
+    }
+}
+
+/// The gist file name of a project file.
+fn gist_file_name(path: &str) -> String {
+    match path {
+        MAIN_FILE => PRIMARY_GIST_FILE_NAME.to_string(),
+        path => path.replace('/', GIST_PATH_SEPARATOR),
+    }
+}
+
+/// The project path of a gist file.
+fn project_path(name: &str) -> String {
+    match name {
+        PRIMARY_GIST_FILE_NAME => MAIN_FILE.to_string(),
+        name => name.replace(GIST_PATH_SEPARATOR, "/"),
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Gist {
+    id: String,
+    files: HashMap<String, GistFile>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct GistFile {
+    content: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct NewGist {
+    description: String,
+    public: bool,
+    files: HashMap<String, GistFile>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_project_paths_to_gist_file_names() {
+        for (path, name) in [
+            (MAIN_FILE, PRIMARY_GIST_FILE_NAME),
+            ("src/components.rs", "src~components.rs"),
+            ("assets/icons/logo.svg", "assets~icons~logo.svg"),
+        ] {
+            assert_eq!(gist_file_name(path), name);
+            assert_eq!(project_path(name), path);
+        }
+    }
+}
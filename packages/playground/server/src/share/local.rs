@@ -0,0 +1,245 @@
+//! Sharing projects as files on the server.
+
+use super::{ShareError, ShareStore};
+use dioxus_logger::tracing::{info, warn};
+use futures::future::BoxFuture;
+use model::ProjectFiles;
+use serde::{Deserialize, Serialize};
+use std::{
+    io,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use uuid::Uuid;
+
+/// The characters share ids are made of. They are all URL-safe.
+const ID_ALPHABET: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+/// The length of share ids, which is about 60 bits of randomness.
+const ID_LENGTH: usize = 10;
+
+/// Stores shared projects as JSON files in a directory, under `<id>.json`.
+///
+/// This needs no network or credentials, so self-hosted servers and tests can share projects.
+/// Expired projects are removed when they are requested and when the store is opened.
+pub struct LocalStore {
+    root: PathBuf,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SharedProject {
+    files: ProjectFiles,
+    /// The unix time in seconds the project expires at.
+    expires_at: Option<u64>,
+}
+
+impl SharedProject {
+    fn is_expired(&self) -> bool {
+        self.expires_at
+            .is_some_and(|expires_at| unix_now() >= expires_at)
+    }
+}
+
+impl LocalStore {
+    /// Open the store in `root`, removing any expired projects and unfinished writes.
+    pub fn open(root: impl Into<PathBuf>) -> io::Result<Self> {
+        let root = root.into();
+        std::fs::create_dir_all(&root)?;
+
+        let mut removed = 0;
+        for item in std::fs::read_dir(&root)? {
+            let path = item?.path();
+            let expired = match path.extension().and_then(|ext| ext.to_str()) {
+                Some("json") => std::fs::read(&path)
+                    .ok()
+                    .and_then(|file| serde_json::from_slice::<SharedProject>(&file).ok())
+                    .is_some_and(|project| project.is_expired()),
+                Some("tmp") => true,
+                _ => false,
+            };
+
+            if expired {
+                std::fs::remove_file(&path)?;
+                removed += 1;
+            }
+        }
+        info!(?root, removed, "opened the local share store");
+
+        Ok(Self { root })
+    }
+
+    /// The file of a shared project, or `None` if the id isn't one this store could have made.
+    fn path(&self, id: &str) -> Option<PathBuf> {
+        let valid = id.len() == ID_LENGTH && id.bytes().all(|b| ID_ALPHABET.contains(&b));
+        valid.then(|| self.root.join(format!("{id}.json")))
+    }
+
+    async fn read(&self, path: &Path) -> Result<SharedProject, ShareError> {
+        let file = match tokio::fs::read(path).await {
+            Ok(file) => file,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Err(ShareError::NotFound),
+            Err(err) => return Err(err.into()),
+        };
+
+        Ok(serde_json::from_slice(&file)?)
+    }
+}
+
+impl ShareStore for LocalStore {
+    fn create(
+        &self,
+        files: ProjectFiles,
+        expiry: Option<Duration>,
+    ) -> BoxFuture<'_, Result<String, ShareError>> {
+        Box::pin(async move {
+            let project = SharedProject {
+                files,
+                expires_at: expiry.map(|expiry| unix_now() + expiry.as_secs()),
+            };
+
+            // Write the project to a temporary file first, so a project is never read half
+            // written. It is then linked to its id, which fails if the id is already taken.
+            let tmp = self.root.join(format!("{}.tmp", Uuid::new_v4()));
+            tokio::fs::write(&tmp, serde_json::to_vec(&project)?).await?;
+
+            let result = loop {
+                let id = new_id();
+                let path = self.root.join(format!("{id}.json"));
+                match tokio::fs::hard_link(&tmp, &path).await {
+                    Ok(()) => break Ok(id),
+                    Err(err) if err.kind() == io::ErrorKind::AlreadyExists => continue,
+                    Err(err) => break Err(err.into()),
+                }
+            };
+
+            if let Err(err) = tokio::fs::remove_file(&tmp).await {
+                warn!(?err, ?tmp, "failed to remove temporary shared project");
+            }
+            result
+        })
+    }
+
+    fn get<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<ProjectFiles, ShareError>> {
+        Box::pin(async move {
+            let path = self.path(id).ok_or(ShareError::NotFound)?;
+            let project = self.read(&path).await?;
+
+            if project.is_expired() {
+                self.delete(id).await?;
+                return Err(ShareError::NotFound);
+            }
+
+            Ok(project.files)
+        })
+    }
+
+    fn delete<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<(), ShareError>> {
+        Box::pin(async move {
+            let path = self.path(id).ok_or(ShareError::NotFound)?;
+            match tokio::fs::remove_file(path).await {
+                Ok(()) => Ok(()),
+                Err(err) if err.kind() == io::ErrorKind::NotFound => Err(ShareError::NotFound),
+                Err(err) => Err(err.into()),
+            }
+        })
+    }
+}
+
+/// A new random share id.
+fn new_id() -> String {
+    let mut random = Uuid::new_v4().as_u128();
+    let mut id = String::with_capacity(ID_LENGTH);
+    for _ in 0..ID_LENGTH {
+        id.push(ID_ALPHABET[(random % ID_ALPHABET.len() as u128) as usize] as char);
+        random /= ID_ALPHABET.len() as u128;
+    }
+    id
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use model::Project;
+
+    fn files(code: &str) -> ProjectFiles {
+        Project::new(code, None, None).into_files()
+    }
+
+    #[tokio::test]
+    async fn stores_projects() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = LocalStore::open(dir.path()).unwrap();
+
+        let id = store.create(files("fn main() {}"), None).await.unwrap();
+        assert_eq!(id.len(), ID_LENGTH);
+        assert!(id.bytes().all(|b| b.is_ascii_alphanumeric()));
+        assert_eq!(store.get(&id).await.unwrap(), files("fn main() {}"));
+
+        // Sharing the same code again gets a new id.
+        let other = store.create(files("fn main() {}"), None).await.unwrap();
+        assert_ne!(id, other);
+
+        store.delete(&id).await.unwrap();
+        assert!(matches!(store.get(&id).await, Err(ShareError::NotFound)));
+        assert!(matches!(store.delete(&id).await, Err(ShareError::NotFound)));
+
+        // Only the shared projects are left in the store.
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[tokio::test]
+    async fn rejects_ids_it_could_not_have_made() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("secret.json"), "{}").unwrap();
+        let store = LocalStore::open(dir.path().join("shared")).unwrap();
+
+        for id in ["../secret", "..%2Fsecret", "", "abc"] {
+            assert!(matches!(store.get(id).await, Err(ShareError::NotFound)));
+        }
+    }
+
+    #[tokio::test]
+    async fn expires_projects() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = LocalStore::open(dir.path()).unwrap();
+
+        let expired = store
+            .create(files("expired"), Some(Duration::ZERO))
+            .await
+            .unwrap();
+        let kept = store
+            .create(files("kept"), Some(Duration::from_secs(60)))
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            store.get(&expired).await,
+            Err(ShareError::NotFound)
+        ));
+        assert!(!dir.path().join(format!("{expired}.json")).exists());
+        assert_eq!(store.get(&kept).await.unwrap(), files("kept"));
+    }
+
+    #[tokio::test]
+    async fn removes_expired_projects_on_open() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = LocalStore::open(dir.path()).unwrap();
+        let expired = store
+            .create(files("expired"), Some(Duration::ZERO))
+            .await
+            .unwrap();
+        let kept = store.create(files("kept"), None).await.unwrap();
+        std::fs::write(dir.path().join("unfinished.tmp"), "{").unwrap();
+
+        let store = LocalStore::open(dir.path()).unwrap();
+        assert!(!dir.path().join(format!("{expired}.json")).exists());
+        assert!(!dir.path().join("unfinished.tmp").exists());
+        assert_eq!(store.get(&kept).await.unwrap(), files("kept"));
+    }
+}
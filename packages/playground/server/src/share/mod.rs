@@ -0,0 +1,102 @@
+//! Sharing projects through a [`ShareStore`].
+
+use crate::app::AppState;
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use dioxus_logger::tracing::{error, trace};
+use futures::future::BoxFuture;
+use model::api::{GetSharedProjectRes, ShareProjectReq, ShareProjectRes};
+use model::{Project, ProjectError, ProjectFiles};
+use std::time::Duration;
+use thiserror::Error;
+
+mod gist;
+mod local;
+
+pub use gist::GistStore;
+pub use local::LocalStore;
+
+/// Stores shared projects by their share id.
+///
+/// The server stores projects with [`GistStore`] or [`LocalStore`], picked by
+/// [`EnvVars::share_store`](crate::EnvVars::share_store).
+pub trait ShareStore: Send + Sync {
+    /// Store the files of a project, returning its share id.
+    ///
+    /// The project can't be retrieved after `expiry` if there is one. Stores that can't expire
+    /// projects keep them forever.
+    fn create(
+        &self,
+        files: ProjectFiles,
+        expiry: Option<Duration>,
+    ) -> BoxFuture<'_, Result<String, ShareError>>;
+
+    /// Get the files of a shared project.
+    fn get<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<ProjectFiles, ShareError>>;
+
+    /// Delete a shared project.
+    fn delete<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<(), ShareError>>;
+}
+
+/// Any error from a [`ShareStore`].
+#[derive(Debug, Error)]
+pub enum ShareError {
+    #[error("shared project not found")]
+    NotFound,
+
+    #[error(transparent)]
+    InvalidProject(#[from] ProjectError),
+
+    #[error(transparent)]
+    Request(#[from] reqwest::Error),
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+}
+
+impl IntoResponse for ShareError {
+    fn into_response(self) -> axum::response::Response {
+        match self {
+            ShareError::NotFound => StatusCode::NOT_FOUND,
+            ShareError::InvalidProject(error) => {
+                return (StatusCode::BAD_REQUEST, error.to_string()).into_response()
+            }
+            error => {
+                error!(?error, "share store error");
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+        .into_response()
+    }
+}
+
+pub async fn get_shared_project(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<GetSharedProjectRes>, ShareError> {
+    trace!(id = ?id, "get shared project request");
+    let files = state.share_store.get(&id).await?;
+
+    Ok(Json(GetSharedProjectRes { id, files }))
+}
+
+pub async fn share_project(
+    State(state): State<AppState>,
+    Json(payload): Json<ShareProjectReq>,
+) -> Result<Json<ShareProjectRes>, ShareError> {
+    trace!(payload = ?payload, "share project request");
+    let project = Project::from_files(payload.files, None, None)?;
+    let id = state
+        .share_store
+        .create(project.into_files(), state.env.share_expiry)
+        .await?;
+
+    Ok(Json(ShareProjectRes { id }))
+}
@@ -1,12 +1,12 @@
 use crate::{
-    build::{BuildCommand, BuildMessage, BuildRequest},
+    build::{dependencies::Dependencies, BuildCommand, BuildMessage, BuildRequest},
     AppState,
 };
 use axum::{
     extract::{ws::WebSocket, State, WebSocketUpgrade},
     response::IntoResponse,
 };
-use axum_client_ip::SecureClientIp;
+use axum_client_ip::ClientIp;
 use dioxus_logger::tracing::error;
 use futures::{SinkExt, StreamExt as _};
 use model::{Project, SocketMessage};
@@ -14,11 +14,12 @@ use tokio::{
     select,
     sync::mpsc::{self, UnboundedSender},
 };
+use uuid::Uuid;
 
 /// Handle any pre-websocket processing.
 pub async fn ws_handler(
     State(state): State<AppState>,
-    SecureClientIp(ip): SecureClientIp,
+    ClientIp(ip): ClientIp,
     ws: WebSocketUpgrade,
 ) -> impl IntoResponse {
     let ip = ip.to_string();
@@ -31,7 +32,7 @@ pub async fn ws_handler(
 /// - Handle submitting build requests, allowing only one build per socket.
 /// - Send any build messages to the client.
 /// - Stop any ongoing builds if the connection closes.
-async fn handle_socket(state: AppState, _ip: String, socket: WebSocket) {
+async fn handle_socket(state: AppState, ip: String, socket: WebSocket) {
     let (mut socket_tx, mut socket_rx) = socket.split();
 
     // Ensure only one client per socket.
@@ -49,6 +50,7 @@ async fn handle_socket(state: AppState, _ip: String, socket: WebSocket) {
     // drop(connected_sockets);
 
     // Start our build loop.
+    let socket_id = Uuid::new_v4();
     let (build_tx, mut build_rx) = mpsc::unbounded_channel();
     let mut current_build: Option<BuildRequest> = None;
 
@@ -62,16 +64,36 @@ async fn handle_socket(state: AppState, _ip: String, socket: WebSocket) {
                 };
 
                 // Start a new build, stopping any existing ones.
-                if let SocketMessage::BuildRequest(code) = socket_msg {
+                if let SocketMessage::BuildRequest(files) = socket_msg {
                     if let Some(ref request) = current_build {
-                        let result = state.build_queue_tx.send(BuildCommand::Stop { id: request.id });
+                        let result = state.build_queue_tx.send(BuildCommand::Stop { id: request.id, socket_id });
                         if result.is_err() {
                             error!(build_id = ?request.id, "failed to send build stop signal for new build request");
                             continue;
                         }
                     }
 
-                    let request = start_build(&state, build_tx.clone(), code);
+                    // Projects with files that can't be built fail right away.
+                    let project = match Project::from_files(files, None, None) {
+                        Ok(project) => project,
+                        Err(err) => {
+                            current_build = None;
+                            let _ = build_tx.send(BuildMessage::Finished(Err(err.to_string())));
+                            continue;
+                        }
+                    };
+
+                    // So do projects with dependencies that aren't allowed, before they are queued.
+                    let dependencies = match state.dependencies.resolve(&project) {
+                        Ok(dependencies) => dependencies,
+                        Err(err) => {
+                            current_build = None;
+                            let _ = build_tx.send(BuildMessage::Finished(Err(err.to_string())));
+                            continue;
+                        }
+                    };
+
+                    let request = start_build(&state, &ip, socket_id, build_tx.clone(), project, dependencies);
                     current_build = Some(request);
                 }
             }
@@ -97,9 +119,10 @@ async fn handle_socket(state: AppState, _ip: String, socket: WebSocket) {
 
     // The socket has closed. Make sure we cancel any active builds.
     if let Some(request) = current_build {
-        let result = state
-            .build_queue_tx
-            .send(BuildCommand::Stop { id: request.id });
+        let result = state.build_queue_tx.send(BuildCommand::Stop {
+            id: request.id,
+            socket_id,
+        });
 
         if result.is_err() {
             error!(build_id = ?request.id, "failed to send build stop signal for closed websocket");
@@ -118,13 +141,18 @@ async fn handle_socket(state: AppState, _ip: String, socket: WebSocket) {
 /// Assembles the build request and sends it to the queue.
 fn start_build(
     state: &AppState,
+    ip: &str,
+    socket_id: Uuid,
     build_tx: UnboundedSender<BuildMessage>,
-    code: String,
+    project: Project,
+    dependencies: Dependencies,
 ) -> BuildRequest {
-    let project = Project::new(code, None, None);
     let request = BuildRequest {
         id: project.id(),
+        client: ip.to_string(),
+        socket_id,
         project,
+        dependencies,
         ws_msg_tx: build_tx,
     };
 
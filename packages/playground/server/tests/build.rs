@@ -0,0 +1,350 @@
+mod common;
+
+use common::{FakeDx, TestServer};
+use dioxus_dx_wire_format::{cargo_metadata::CompilerMessage, BuildStage, StructuredOutput};
+use model::{CargoLevel, Project, ProjectFiles, SocketMessage, MAIN_FILE, MANIFEST_FILE};
+use std::time::Duration;
+
+const CODE: &str = r#"fn main() { println!("hello"); }"#;
+
+/// A warning from cargo for the crate named `krate`.
+fn warning(krate: &str) -> StructuredOutput {
+    let message: CompilerMessage = serde_json::from_value(serde_json::json!({
+        "package_id": format!("{krate} 0.1.0 (path+file:///template)"),
+        "target": {
+            "name": krate,
+            "kind": ["bin"],
+            "crate_types": ["bin"],
+            "required-features": [],
+            "src_path": "/template/src/main.rs",
+            "edition": "2021",
+            "doctest": false,
+            "test": true,
+            "doc": true
+        },
+        "message": {
+            "message": "unused variable: `x`",
+            "code": null,
+            "level": "warning",
+            "spans": [],
+            "children": [],
+            "rendered": null
+        }
+    }))
+    .unwrap();
+    StructuredOutput::CargoOutput { message }
+}
+
+#[tokio::test]
+async fn builds_and_serves_project() {
+    let dx = FakeDx::new()
+        .with_line("a log line that isn't json")
+        .with_output(StructuredOutput::BuildUpdate {
+            stage: BuildStage::RunningBindgen,
+        });
+    let server = TestServer::start(dx).await;
+    let mut client = server.connect().await;
+
+    client.request_build(CODE).await;
+    let messages = client.recv_build().await;
+
+    let id = Project::new(CODE, None, None).id();
+    assert!(matches!(messages[0], SocketMessage::QueuePosition(0)));
+    assert!(matches!(
+        messages[1],
+        SocketMessage::BuildStage(model::BuildStage::RunningBindgen)
+    ));
+    assert!(matches!(messages[2], SocketMessage::BuildFinished(Ok(built)) if built == id));
+    assert_eq!(messages.len(), 3);
+
+    // The code was written into the template for dx.
+    let main = std::fs::read_to_string(server.template_path().join("src/main.rs")).unwrap();
+    assert_eq!(main, CODE);
+
+    for path in [format!("/built/{id}"), format!("/built/{id}/")] {
+        let index = reqwest::get(server.url(&path)).await.unwrap();
+        assert!(index.status().is_success(), "{path}");
+        assert_eq!(index.text().await.unwrap(), format!("<p>{id}</p>"));
+    }
+
+    let wasm = reqwest::get(server.url(&format!("/built/{id}/app_bg.wasm")))
+        .await
+        .unwrap();
+    assert!(wasm.status().is_success());
+    assert_eq!(wasm.headers()["content-type"], "application/wasm");
+}
+
+#[tokio::test]
+async fn forwards_diagnostics_for_the_project() {
+    let dx = FakeDx::new()
+        .with_output(warning("dioxus-core"))
+        .with_output(warning("play-{BUILD_ID}"));
+    let server = TestServer::start(dx).await;
+    let mut client = server.connect().await;
+
+    client.request_build(CODE).await;
+    let messages = client.recv_build().await;
+
+    // Diagnostics for dependencies are dropped.
+    let diagnostics: Vec<_> = messages
+        .iter()
+        .filter_map(|message| match message {
+            SocketMessage::BuildDiagnostic(diagnostic) => Some(diagnostic),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].level, CargoLevel::Warning);
+    assert_eq!(diagnostics[0].message, "unused variable: `x`");
+    assert!(matches!(
+        messages.last(),
+        Some(SocketMessage::BuildFinished(Ok(_)))
+    ));
+}
+
+#[tokio::test]
+async fn reports_failed_builds() {
+    let server = TestServer::start(FakeDx::new().failing(101)).await;
+    let mut client = server.connect().await;
+
+    client.request_build(CODE).await;
+    let messages = client.recv_build().await;
+
+    let Some(SocketMessage::BuildFinished(Err(error))) = messages.last() else {
+        panic!("expected a failed build, got {messages:?}");
+    };
+    assert!(error.contains("101"), "{error}");
+
+    // The socket is closed after the build finishes.
+    assert!(client.recv().await.is_none());
+
+    let id = Project::new(CODE, None, None).id();
+    let index = reqwest::get(server.url(&format!("/built/{id}/")))
+        .await
+        .unwrap();
+    assert_eq!(index.status(), reqwest::StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn queues_builds_behind_the_current_build() {
+    let server = TestServer::start(FakeDx::new().with_delay(Duration::from_millis(300))).await;
+    let mut first = server.connect().await;
+    let mut second = server.connect().await;
+
+    first.request_build(CODE).await;
+    assert!(matches!(
+        first.recv().await,
+        Some(SocketMessage::QueuePosition(0))
+    ));
+    second.request_build("fn main() {}").await;
+
+    let messages = second.recv_build().await;
+    assert!(matches!(messages[0], SocketMessage::QueuePosition(1)));
+    assert!(matches!(messages[1], SocketMessage::QueuePosition(0)));
+    assert!(matches!(messages[2], SocketMessage::BuildFinished(Ok(_))));
+
+    assert!(matches!(
+        first.recv_build().await.last(),
+        Some(SocketMessage::BuildFinished(Ok(_)))
+    ));
+}
+
+#[tokio::test]
+async fn skips_projects_that_are_already_built() {
+    let dx = FakeDx::new();
+    let server = TestServer::start(dx.clone()).await;
+
+    for _ in 0..2 {
+        let mut client = server.connect().await;
+        client.request_build(CODE).await;
+        assert!(matches!(
+            client.recv_build().await.last(),
+            Some(SocketMessage::BuildFinished(Ok(_)))
+        ));
+    }
+
+    assert_eq!(dx.builds(), 1);
+}
+
+#[tokio::test]
+async fn builds_in_parallel_on_several_workers() {
+    let dx = FakeDx::new().with_delay(Duration::from_millis(300));
+    let server = TestServer::with_workers(dx.clone(), 2).await;
+    let mut first = server.connect().await;
+    let mut second = server.connect().await;
+
+    first.request_build(CODE).await;
+    second.request_build("fn main() {}").await;
+
+    // Neither build waits in the queue.
+    for client in [&mut first, &mut second] {
+        let messages = client.recv_build().await;
+        assert!(matches!(messages[0], SocketMessage::QueuePosition(0)));
+        assert!(matches!(messages[1], SocketMessage::BuildFinished(Ok(_))));
+    }
+    assert_eq!(dx.builds(), 2);
+
+    // The second worker builds in its own copy of the template.
+    let worker_template = server.state.env.worker_template_path(1);
+    assert!(worker_template.join("snippets/Cargo.toml").exists());
+}
+
+#[tokio::test]
+async fn new_builds_only_cancel_builds_of_the_same_socket() {
+    let server = TestServer::start(FakeDx::new().with_delay(Duration::from_millis(300))).await;
+    let mut first = server.connect().await;
+    let mut second = server.connect().await;
+
+    first.request_build(CODE).await;
+    assert!(matches!(
+        first.recv().await,
+        Some(SocketMessage::QueuePosition(0))
+    ));
+
+    // The second socket builds the same project, then changes its mind. Only its own request
+    // for the project is cancelled.
+    second.request_build(CODE).await;
+    assert!(matches!(
+        second.recv().await,
+        Some(SocketMessage::QueuePosition(1))
+    ));
+    second.request_build("fn main() {}").await;
+
+    let id = Project::new(CODE, None, None).id();
+    let messages = first.recv_build().await;
+    assert!(
+        matches!(messages.last(), Some(SocketMessage::BuildFinished(Ok(built))) if *built == id)
+    );
+
+    let other_id = Project::new("fn main() {}", None, None).id();
+    let messages = second.recv_build().await;
+    assert!(
+        matches!(messages.last(), Some(SocketMessage::BuildFinished(Ok(built))) if *built == other_id)
+    );
+}
+
+#[tokio::test]
+async fn evicts_the_least_recently_served_builds() {
+    // Each fake build is a little under 50 bytes, so two fit in the cache.
+    let server = TestServer::with_env(FakeDx::new(), |env| env.built_cache_size = 100).await;
+    let [a, b, c] = ["fn main() {}", "fn main() { a() }", "fn main() { b() }"];
+
+    server.build(a).await;
+    server.build(b).await;
+
+    // Serving `a` makes `b` the least recently used build.
+    let id = |code| Project::new(code, None, None).id();
+    let index = reqwest::get(server.url(&format!("/built/{}", id(a))))
+        .await
+        .unwrap();
+    assert!(index.status().is_success());
+
+    server.build(c).await;
+
+    for (code, served) in [(a, true), (b, false), (c, true)] {
+        let index = reqwest::get(server.url(&format!("/built/{}", id(code))))
+            .await
+            .unwrap();
+        assert_eq!(index.status().is_success(), served, "{code}");
+    }
+}
+
+#[tokio::test]
+async fn writes_every_file_of_the_project() {
+    let server = TestServer::start(FakeDx::new()).await;
+    let files = |files: &[(&str, &str)]| -> ProjectFiles {
+        files
+            .iter()
+            .map(|(path, contents)| (path.to_string(), contents.to_string()))
+            .collect()
+    };
+
+    let first = files(&[
+        (MAIN_FILE, "mod components; fn main() {}"),
+        ("src/components.rs", "pub fn App() {}"),
+        ("assets/main.css", "body {}"),
+    ]);
+    let mut client = server.connect().await;
+    client.request_project(first.clone()).await;
+    let id = Project::from_files(first.clone(), None, None).unwrap().id();
+    assert!(
+        matches!(client.recv_build().await.last(), Some(SocketMessage::BuildFinished(Ok(built))) if *built == id)
+    );
+    for (path, contents) in &first {
+        let written = std::fs::read_to_string(server.template_path().join(path)).unwrap();
+        assert_eq!(&written, contents);
+    }
+
+    // The files of the last project don't leak into the next one.
+    let mut client = server.connect().await;
+    client.request_build(CODE).await;
+    client.recv_build().await;
+    assert!(!server.template_path().join("src/components.rs").exists());
+    assert!(!server.template_path().join("assets/main.css").exists());
+}
+
+#[tokio::test]
+async fn rejects_projects_with_invalid_files() {
+    let dx = FakeDx::new();
+    let server = TestServer::start(dx.clone()).await;
+    let mut client = server.connect().await;
+
+    let files = ProjectFiles::from([
+        (MAIN_FILE.to_string(), CODE.to_string()),
+        ("../../escape.rs".to_string(), String::new()),
+    ]);
+    client.request_project(files).await;
+
+    let messages = client.recv_build().await;
+    assert!(
+        matches!(&messages[..], [SocketMessage::BuildFinished(Err(err))] if err.contains("escape.rs"))
+    );
+    assert_eq!(dx.builds(), 0);
+}
+
+#[tokio::test]
+async fn builds_projects_with_allowed_dependencies() {
+    let server = TestServer::start(FakeDx::new()).await;
+    let mut client = server.connect().await;
+
+    let files = ProjectFiles::from([
+        (MAIN_FILE.to_string(), CODE.to_string()),
+        (
+            MANIFEST_FILE.to_string(),
+            "[dependencies]\nserde = { version = \"1\", features = [\"derive\"] }".to_string(),
+        ),
+    ]);
+    client.request_project(files.clone()).await;
+    let id = Project::from_files(files, None, None).unwrap().id();
+    assert!(
+        matches!(client.recv_build().await.last(), Some(SocketMessage::BuildFinished(Ok(built))) if *built == id)
+    );
+
+    // The dependency was added to the template's manifest, pinned to the allowed version.
+    let manifest = std::fs::read_to_string(server.template_path().join(MANIFEST_FILE)).unwrap();
+    assert!(manifest.contains("=1.0.219"), "{manifest}");
+    assert!(manifest.contains(&format!("play-{id}")), "{manifest}");
+}
+
+#[tokio::test]
+async fn rejects_dependencies_that_are_not_allowed() {
+    let dx = FakeDx::new();
+    let server = TestServer::start(dx.clone()).await;
+    let mut client = server.connect().await;
+
+    let files = ProjectFiles::from([
+        (MAIN_FILE.to_string(), CODE.to_string()),
+        (
+            MANIFEST_FILE.to_string(),
+            "[dependencies]\ntokio = \"1\"".to_string(),
+        ),
+    ]);
+    client.request_project(files).await;
+
+    let messages = client.recv_build().await;
+    assert!(
+        matches!(&messages[..], [SocketMessage::BuildFinished(Err(err))] if err.contains("`tokio`") && err.contains("serde 1.0.219")),
+        "{messages:?}"
+    );
+    assert_eq!(dx.builds(), 0);
+}
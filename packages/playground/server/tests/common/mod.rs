@@ -0,0 +1,252 @@
+//! A server on a random port that builds with a fake `dx`.
+
+#![allow(dead_code)]
+
+use dioxus_dx_wire_format::StructuredOutput;
+use futures::{future::BoxFuture, SinkExt, StreamExt};
+use model::{Project, ProjectFiles, SocketMessage};
+use server::{build::toolchain::Toolchain, build::BuildError, AppState, EnvVars, ShareStoreKind};
+use std::{
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use tempfile::TempDir;
+use tokio::{net::TcpStream, sync::mpsc::UnboundedSender};
+use tokio_tungstenite::{tungstenite::Message, MaybeTlsStream, WebSocketStream};
+use uuid::Uuid;
+
+/// How long a test waits for the next socket message before failing.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A toolchain that writes canned `dx --json-output` lines and an empty app instead of compiling.
+///
+/// `{BUILD_ID}` in the canned lines is replaced with the id of the build.
+#[derive(Clone, Default)]
+pub struct FakeDx {
+    lines: Vec<String>,
+    exit_code: Option<i32>,
+    delay: Duration,
+    builds: Arc<AtomicUsize>,
+}
+
+impl FakeDx {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Write the structured output the way dx does, in the `json` field of a tracing message.
+    pub fn with_output(mut self, output: StructuredOutput) -> Self {
+        let json = serde_json::to_string(&output).unwrap();
+        let line = serde_json::json!({ "message": "structured output", "json": json });
+        self.lines.push(line.to_string());
+        self
+    }
+
+    /// Write a line that isn't structured output.
+    pub fn with_line(mut self, line: &str) -> Self {
+        self.lines.push(line.to_string());
+        self
+    }
+
+    /// Fail every build with the exit code.
+    pub fn failing(mut self, exit_code: i32) -> Self {
+        self.exit_code = Some(exit_code);
+        self
+    }
+
+    /// Wait before finishing each build.
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// The number of builds that were started.
+    pub fn builds(&self) -> usize {
+        self.builds.load(Ordering::SeqCst)
+    }
+}
+
+impl Toolchain for FakeDx {
+    fn build(
+        &self,
+        template_path: &Path,
+        build_id: Uuid,
+        output: UnboundedSender<String>,
+    ) -> BoxFuture<'static, Result<(), BuildError>> {
+        self.builds.fetch_add(1, Ordering::SeqCst);
+        let fake = self.clone();
+        let public = template_path
+            .join("target/dx")
+            .join(format!("play-{build_id}"))
+            .join("debug/web/public");
+
+        Box::pin(async move {
+            for line in &fake.lines {
+                let _ = output.send(line.replace("{BUILD_ID}", &build_id.to_string()));
+            }
+            tokio::time::sleep(fake.delay).await;
+
+            if let Some(code) = fake.exit_code {
+                return Err(BuildError::DxFailed(Some(code)));
+            }
+
+            tokio::fs::create_dir_all(&public).await?;
+            tokio::fs::write(public.join("index.html"), format!("<p>{build_id}</p>")).await?;
+            tokio::fs::write(public.join("app_bg.wasm"), b"\0asm").await?;
+            Ok(())
+        })
+    }
+}
+
+/// A server listening on a random local port, with its template and built projects in a
+/// temporary directory.
+pub struct TestServer {
+    pub addr: SocketAddr,
+    pub state: AppState,
+    _dir: TempDir,
+}
+
+impl TestServer {
+    pub async fn start(toolchain: impl Toolchain + 'static) -> Self {
+        Self::with_workers(toolchain, 1).await
+    }
+
+    /// Start a server that runs up to `build_workers` builds at the same time.
+    pub async fn with_workers(toolchain: impl Toolchain + 'static, build_workers: usize) -> Self {
+        Self::with_env(toolchain, |env| env.build_workers = build_workers).await
+    }
+
+    /// Start a server with the test environment changed by `configure`.
+    pub async fn with_env(
+        toolchain: impl Toolchain + 'static,
+        configure: impl FnOnce(&mut EnvVars),
+    ) -> Self {
+        let dir = tempfile::tempdir().unwrap();
+        let mut env = test_env(dir.path());
+        configure(&mut env);
+        let state = AppState::with_toolchain(env, Arc::new(toolchain));
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let app = server::router(state.clone());
+        tokio::spawn(async move {
+            axum::serve(
+                listener,
+                app.into_make_service_with_connect_info::<SocketAddr>(),
+            )
+            .await
+            .unwrap();
+        });
+
+        Self {
+            addr,
+            state,
+            _dir: dir,
+        }
+    }
+
+    pub fn url(&self, path: &str) -> String {
+        format!("http://{}{path}", self.addr)
+    }
+
+    pub fn template_path(&self) -> &Path {
+        &self.state.env.build_template_path
+    }
+
+    /// Open a websocket to the server like the playground does.
+    pub async fn connect(&self) -> TestClient {
+        let (socket, _) = tokio_tungstenite::connect_async(format!("ws://{}/ws", self.addr))
+            .await
+            .unwrap();
+        TestClient { socket }
+    }
+
+    /// Build a project on a new websocket, returning the messages until the build finishes.
+    pub async fn build(&self, code: &str) -> Vec<SocketMessage> {
+        let mut client = self.connect().await;
+        client.request_build(code).await;
+        client.recv_build().await
+    }
+}
+
+/// Set up a copy of the build template and empty built and shared directories in `dir`.
+fn test_env(dir: &Path) -> EnvVars {
+    let template = dir.join("template");
+    let snippets = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("template/snippets");
+    std::fs::create_dir_all(template.join("snippets")).unwrap();
+    std::fs::create_dir_all(template.join("src")).unwrap();
+    for file in ["Cargo.toml", "Dioxus.toml", "dependencies.toml"] {
+        std::fs::copy(snippets.join(file), template.join("snippets").join(file)).unwrap();
+    }
+
+    EnvVars {
+        production: false,
+        port: 0,
+        dependency_allowlist_path: template.join("snippets/dependencies.toml"),
+        build_template_path: template,
+        built_path: dir.join("built"),
+        build_workers: 1,
+        built_cache_size: 1024 * 1024 * 1024,
+        pinned_projects_path: None,
+        shutdown_delay: None,
+        share_store: ShareStoreKind::Local,
+        share_path: dir.join("shared"),
+        share_expiry: None,
+        gist_auth_token: String::new(),
+    }
+}
+
+/// A websocket connected to a [`TestServer`].
+pub struct TestClient {
+    socket: WebSocketStream<MaybeTlsStream<TcpStream>>,
+}
+
+impl TestClient {
+    /// Request a build of a project with `code` as its main file.
+    pub async fn request_build(&mut self, code: &str) {
+        let files = Project::new(code, None, None).into_files();
+        self.request_project(files).await
+    }
+
+    pub async fn request_project(&mut self, files: ProjectFiles) {
+        let message = SocketMessage::BuildRequest(files);
+        let message = Message::text(message.as_json_string().unwrap());
+        self.socket.send(message).await.unwrap();
+    }
+
+    /// Wait for the next message from the server. Returns `None` once the socket is closed.
+    pub async fn recv(&mut self) -> Option<SocketMessage> {
+        loop {
+            let message = tokio::time::timeout(TIMEOUT, self.socket.next())
+                .await
+                .expect("the server should send a message")?
+                .ok()?;
+
+            match message {
+                Message::Text(text) => {
+                    return Some(SocketMessage::try_from(text.to_string()).unwrap())
+                }
+                Message::Close(_) => return None,
+                _ => continue,
+            }
+        }
+    }
+
+    /// Receive messages until the build finishes, returning all of them.
+    pub async fn recv_build(&mut self) -> Vec<SocketMessage> {
+        let mut messages = Vec::new();
+        while let Some(message) = self.recv().await {
+            let finished = matches!(message, SocketMessage::BuildFinished(_));
+            messages.push(message);
+            if finished {
+                break;
+            }
+        }
+        messages
+    }
+}
@@ -0,0 +1,85 @@
+mod common;
+
+use common::{FakeDx, TestServer};
+use model::api::{GetSharedProjectRes, ShareProjectReq, ShareProjectRes};
+use model::{ProjectFiles, MAIN_FILE};
+use reqwest::StatusCode;
+use std::time::Duration;
+
+const CODE: &str = r#"fn main() { println!("shared"); }"#;
+
+fn files(files: &[(&str, &str)]) -> ProjectFiles {
+    files
+        .iter()
+        .map(|(path, contents)| (path.to_string(), contents.to_string()))
+        .collect()
+}
+
+async fn share(server: &TestServer, files: ProjectFiles) -> reqwest::Response {
+    reqwest::Client::new()
+        .post(server.url("/shared"))
+        .json(&ShareProjectReq { files })
+        .send()
+        .await
+        .unwrap()
+}
+
+async fn share_id(server: &TestServer, files: ProjectFiles) -> String {
+    let res = share(server, files).await;
+    assert!(res.status().is_success());
+    res.json::<ShareProjectRes>().await.unwrap().id
+}
+
+#[tokio::test]
+async fn shares_projects_without_the_network() {
+    let server = TestServer::start(FakeDx::new()).await;
+
+    let project = files(&[(MAIN_FILE, CODE), ("assets/main.css", "body {}")]);
+    let id = share_id(&server, project.clone()).await;
+    assert!(id.chars().all(|c| c.is_ascii_alphanumeric()), "{id}");
+
+    let res = reqwest::get(server.url(&format!("/shared/{id}")))
+        .await
+        .unwrap();
+    assert!(res.status().is_success());
+    let shared = res.json::<GetSharedProjectRes>().await.unwrap();
+    assert_eq!(shared.id, id);
+    assert_eq!(shared.files, project);
+}
+
+#[tokio::test]
+async fn rejects_projects_that_cannot_be_built() {
+    let server = TestServer::start(FakeDx::new()).await;
+
+    for project in [
+        files(&[("src/app.rs", CODE)]),
+        files(&[(MAIN_FILE, CODE), ("../escape.rs", "")]),
+    ] {
+        let res = share(&server, project).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    }
+}
+
+#[tokio::test]
+async fn unknown_shares_are_not_found() {
+    let server = TestServer::start(FakeDx::new()).await;
+
+    for id in ["doesnotexist", "0000000000", "..%2F..%2Fsecret"] {
+        let res = reqwest::get(server.url(&format!("/shared/{id}")))
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::NOT_FOUND, "{id}");
+    }
+}
+
+#[tokio::test]
+async fn expired_shares_are_not_found() {
+    let server =
+        TestServer::with_env(FakeDx::new(), |env| env.share_expiry = Some(Duration::ZERO)).await;
+
+    let id = share_id(&server, files(&[(MAIN_FILE, CODE)])).await;
+    let res = reqwest::get(server.url(&format!("/shared/{id}")))
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+}
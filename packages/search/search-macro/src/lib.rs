@@ -2,50 +2,284 @@ use std::path::PathBuf;
 
 use proc_macro::TokenStream;
 use quote::quote;
-use syn::LitStr;
+use syn::{
+    parse::{Parse, ParseStream},
+    Ident, LitStr, Token,
+};
 
+/// Include a search index built by `SearchIndex::create`. Any error loading the index is logged
+/// and the index falls back to an empty index without results.
+///
+/// With the `book` feature, the index can instead be built from the markdown of a book while the
+/// crate compiles, so a fresh `cargo build` has a working search:
+///
+/// ```rust, ignore
+/// static INDEX: LazySearchIndex<Route> = load_search_index!(
+///     "searchable_0_7",
+///     book = "../../docs-src/0.7",
+///     prefix = "/learn/0.7",
+/// );
+/// ```
+///
+/// `book` is the directory of the book, or its `book.toml`, relative to the manifest dir.
+/// `prefix` is the url the book is nested under, and `ranking` optionally points to a ranking
+/// TOML file. The index is written to `OUT_DIR`, so the crate needs a build script, even an
+/// empty one
 #[proc_macro]
 pub fn load_search_index(input: TokenStream) -> TokenStream {
-    match syn::parse::<LitStr>(input) {
-        Ok(input) => generate_search_index(input),
+    match syn::parse::<IndexInput>(input) {
+        Ok(input) => generate_search_index(input, false),
         Err(err) => err.to_compile_error().into(),
     }
 }
 
-/// Transforms the book to use enum routes instead of paths
-fn generate_search_index(id: LitStr) -> TokenStream {
-    let target_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+/// Like `load_search_index!`, but the lazy index is a `Result` with the error loading the index
+#[proc_macro]
+pub fn try_load_search_index(input: TokenStream) -> TokenStream {
+    match syn::parse::<IndexInput>(input) {
+        Ok(input) => generate_search_index(input, true),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
 
-    let name = id.value();
-    let index_path = PathBuf::from(format!("{}/dioxus_search/index_{}.bin", target_dir, name));
-    let index_str = index_path.to_str().unwrap();
-    if index_path.exists() && !std::fs::read(&index_path).unwrap().is_empty() {
-        quote!{
-            {
-                const INDEX_BYTES: &[u8] = include_bytes!(#index_str);
-
-                dioxus_search::once_cell::sync::Lazy::new(|| {
-                    let (bytes, _) = dioxus_search::yazi::decompress(INDEX_BYTES, dioxus_search::yazi::Format::Zlib).unwrap();
-                    dioxus_search::SearchIndex::from_bytes(#name, bytes)
-                })
+/// The name of the index, and the book to build it from if it is built while compiling
+struct IndexInput {
+    id: LitStr,
+    book: Option<BookInput>,
+}
+
+// Without the `book` feature only the path is used, to point the error at it
+#[cfg_attr(not(feature = "book"), allow(dead_code))]
+struct BookInput {
+    path: LitStr,
+    prefix: Option<LitStr>,
+    ranking: Option<LitStr>,
+}
+
+impl Parse for IndexInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let id = input.parse()?;
+        let mut path = None;
+        let mut prefix = None;
+        let mut ranking = None;
+        while !input.is_empty() {
+            input.parse::<Token![,]>()?;
+            if input.is_empty() {
+                break;
             }
-        }.into()
-    } else {
-        // create a blank index file
-        std::fs::create_dir_all(index_path.parent().unwrap()).unwrap();
-        std::fs::write(&index_path, vec![]).unwrap();
-        println!("It looks like you haven't built the index yet, so we created an empty index file at {:?}", index_path);
-        quote! {
-            {
-                // let rust know that we care about this file
-                const INDEX_BYTES: &[u8] = include_bytes!(#index_str);
-
-                dioxus_search::once_cell::sync::Lazy::new(|| {
-                    eprintln!("Index not found: {:?}, have you built the index yet?", #index_str);
-                    dioxus_search::SearchIndex::default()
-                })
+            let key: Ident = input.parse()?;
+            input.parse::<Token![=]>()?;
+            let value: LitStr = input.parse()?;
+            let slot = match key.to_string().as_str() {
+                "book" => &mut path,
+                "prefix" => &mut prefix,
+                "ranking" => &mut ranking,
+                _ => {
+                    return Err(syn::Error::new(
+                        key.span(),
+                        "Expected `book`, `prefix` or `ranking`",
+                    ))
+                }
+            };
+            if slot.replace(value).is_some() {
+                return Err(syn::Error::new(key.span(), format!("`{key}` is set twice")));
+            }
+        }
+
+        let book = match path {
+            Some(path) => Some(BookInput {
+                path,
+                prefix,
+                ranking,
+            }),
+            None => {
+                if let Some(setting) = prefix.or(ranking) {
+                    return Err(syn::Error::new(
+                        setting.span(),
+                        "Only indexes built from a `book` have settings",
+                    ));
+                }
+                None
             }
+        };
+        Ok(Self { id, book })
+    }
+}
+
+/// Include the compressed index in the binary and load it lazily
+fn generate_search_index(input: IndexInput, fallible: bool) -> TokenStream {
+    let id = input.id;
+    // Building the index from a book includes every file it was built from, so cargo builds the
+    // index again when the book changes
+    let paths = match &input.book {
+        Some(book) => build_index_from_book(&id, book),
+        None => index_path(&id)
+            .map(|index_path| (index_path, Vec::new()))
+            .map_err(|err| syn::Error::new(id.span(), err)),
+    };
+    let (index_path, sources) = match paths {
+        Ok(paths) => paths,
+        Err(err) => return err.to_compile_error().into(),
+    };
+
+    let name = id.value();
+    let index_str = index_path.to_string_lossy().to_string();
+    let sources = sources
+        .iter()
+        .map(|source| source.to_string_lossy().to_string());
+    let load = if fallible {
+        quote! { dioxus_search::SearchIndex::from_compressed(#name, INDEX_BYTES) }
+    } else {
+        quote! { dioxus_search::SearchIndex::from_compressed_or_default(#name, INDEX_BYTES) }
+    };
+    quote! {
+        {
+            const INDEX_BYTES: &[u8] = include_bytes!(#index_str);
+            #(const _: &[u8] = include_bytes!(#sources);)*
+
+            dioxus_search::once_cell::sync::Lazy::new(|| #load)
+        }
+    }
+    .into()
+}
+
+fn manifest_dir() -> Result<PathBuf, String> {
+    std::env::var("CARGO_MANIFEST_DIR")
+        .map(PathBuf::from)
+        .map_err(|_| "CARGO_MANIFEST_DIR is not set. Build the crate with cargo".to_string())
+}
+
+/// Find the index file, creating an empty one if the index hasn't been built yet so
+/// `include_bytes!` still compiles. Loading the empty index fails with
+/// `IndexError::MissingIndex`, which tells the reader to build the index
+fn index_path(id: &LitStr) -> Result<PathBuf, String> {
+    let index_path = manifest_dir()?
+        .join("dioxus_search")
+        .join(format!("index_{}.bin", id.value()));
+
+    if !index_path.exists() {
+        let create = || {
+            std::fs::create_dir_all(index_path.parent().unwrap())?;
+            std::fs::write(&index_path, [])
+        };
+        create().map_err(|err| {
+            format!(
+                "Failed to create an empty search index at {}: {err}",
+                index_path.display()
+            )
+        })?;
+    }
+
+    Ok(index_path)
+}
+
+/// Build the index from the book and write it to `OUT_DIR`, so building never writes to the
+/// source tree. Returns the path of the index and the files it was built from.
+///
+/// New chapters are only part of the book once they are in `SUMMARY.md`, which is one of the
+/// files the index was built from, so adding a chapter builds the index again
+#[cfg(feature = "book")]
+fn build_index_from_book(id: &LitStr, input: &BookInput) -> syn::Result<(PathBuf, Vec<PathBuf>)> {
+    use dioxus_search_shared::{
+        book_includes, book_page_url, write_compressed, BookVars, NativeIndex, RankingConfig,
+        TokenizerConfig,
+    };
+
+    let error = |span: &LitStr, err: String| syn::Error::new(span.span(), err);
+    let manifest_dir = manifest_dir().map_err(|err| error(id, err))?;
+    let out_dir = std::env::var("OUT_DIR").map(PathBuf::from).map_err(|_| {
+        error(
+            id,
+            "Indexes built from a book are written to OUT_DIR, which cargo only sets for crates with a build script. Add a `build.rs` with an empty `fn main() {}`".to_string(),
+        )
+    })?;
+
+    // The path can point to the book directory or its manifest
+    let mut root = manifest_dir.join(input.path.value());
+    if root.is_file() {
+        root.pop();
+    }
+    let root = root.canonicalize().map_err(|err| {
+        error(
+            &input.path,
+            format!("Failed to find the book at {}: {err}", root.display()),
+        )
+    })?;
+    let book = mdbook_shared::MdBook::new(root.clone())
+        .map_err(|err| error(&input.path, format!("Failed to load the book: {err}")))?;
+    let vars = BookVars::load(&root)
+        .map_err(|err| error(&input.path, format!("Failed to load the book: {err:#}")))?;
+    let mut sources = Vec::new();
+    markdown_files(&root, &mut sources)
+        .map_err(|err| error(&input.path, format!("Failed to read the book: {err}")))?;
+    // Code blocks include examples relative to the crate, the same way mdbook-gen reads them
+    sources.extend(book_includes(&book, &manifest_dir));
+
+    let mut ranking = RankingConfig::default();
+    if let Some(path) = &input.ranking {
+        let ranking_path = manifest_dir.join(path.value());
+        let toml = std::fs::read_to_string(&ranking_path).map_err(|err| {
+            error(
+                path,
+                format!("Failed to read {}: {err}", ranking_path.display()),
+            )
+        })?;
+        ranking = RankingConfig::from_toml(&toml).map_err(|err| error(path, err.to_string()))?;
+        sources.push(ranking_path);
+    }
+    let book_toml = root.join("book.toml");
+    if let Ok(toml) = std::fs::read_to_string(&book_toml) {
+        ranking = ranking
+            .with_book_config(&toml)
+            .map_err(|err| error(&input.path, err.to_string()))?;
+        sources.push(book_toml);
+    }
+
+    let prefix = input.prefix.as_ref().map(LitStr::value).unwrap_or_default();
+    let index = NativeIndex::from_book(
+        &book,
+        &vars,
+        &manifest_dir,
+        |path| book_page_url(&prefix, path),
+        TokenizerConfig::default(),
+    )
+    .map_err(|err| {
+        error(
+            &input.path,
+            format!("Failed to build the search index: {err}"),
+        )
+    })?
+    .with_ranking(ranking);
+
+    // Only the index is written. The shards are for serving the index next to a site, which
+    // `SearchIndex::create_with_config` writes
+    let index_path = out_dir
+        .join("dioxus_search")
+        .join(format!("index_{}.bin", id.value()));
+    write_compressed(&index_path, &index.to_bytes())
+        .map_err(|err| error(id, format!("Failed to write the search index: {err}")))?;
+
+    Ok((index_path, sources))
+}
+
+#[cfg(not(feature = "book"))]
+fn build_index_from_book(_: &LitStr, input: &BookInput) -> syn::Result<(PathBuf, Vec<PathBuf>)> {
+    Err(syn::Error::new(
+        input.path.span(),
+        "Enable the `book` feature of dioxus-search to build indexes from a book while compiling",
+    ))
+}
+
+/// Every markdown file in the directory and its subdirectories
+#[cfg(feature = "book")]
+fn markdown_files(dir: &std::path::Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            markdown_files(&path, files)?;
+        } else if path.extension().is_some_and(|extension| extension == "md") {
+            files.push(path);
         }
-        .into()
     }
+    Ok(())
 }
@@ -0,0 +1,119 @@
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+use crate::Excerpt;
+
+/// A search engine that can answer queries for a [`crate::SearchIndex`].
+///
+/// The index is built with the [`crate::NativeIndex`] backend by default. Indexes built with
+/// stork can still be loaded with the `stork` feature.
+pub trait SearchBackend: Send + Sync {
+    /// Find the documents that match a query, best match first
+    fn search(&self, query: &str) -> Result<Vec<DocumentMatch>, SearchError>;
+
+    /// Find the code examples that match a query, best match first. Each match has the
+    /// [`CodeSource`] of the example, and its excerpt is the matching part of the code
+    fn search_code(&self, query: &str) -> Result<Vec<DocumentMatch>, SearchError> {
+        let _ = query;
+        Err(SearchError::new(
+            "This index does not support searching code examples",
+        ))
+    }
+
+    /// Complete the last word of a query with words from the index, most common first
+    fn complete(&self, query: &str, limit: usize) -> Vec<String> {
+        let _ = (query, limit);
+        Vec::new()
+    }
+
+    /// Suggest a similar query with results for a query without any
+    fn did_you_mean(&self, query: &str) -> Option<String> {
+        let _ = query;
+        None
+    }
+
+    /// Searches to suggest before anything is typed. Each match links to the page for the search
+    fn popular_searches(&self) -> Vec<DocumentMatch> {
+        Vec::new()
+    }
+
+    /// Serialize the index so it can be loaded again with [`crate::SearchIndex::from_bytes`]
+    fn to_bytes(&self) -> Vec<u8>;
+}
+
+/// A document that matched a query before its url is parsed into a route
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DocumentMatch {
+    pub url: String,
+    /// The id of the heading the matched section starts at
+    pub anchor: Option<String>,
+    /// The titles of the page and headings the matched section is nested under
+    pub breadcrumb: Vec<String>,
+    pub title: String,
+    pub excerpts: Vec<Excerpt>,
+    /// The terms in the document each word of the query matched
+    pub matches: Vec<TermMatch>,
+    pub score: usize,
+    /// Where the code example came from if this is a match for a code search
+    pub example: Option<CodeSource>,
+}
+
+/// Where a code example came from
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CodeSource {
+    /// The language of the code block, like `rust`
+    pub language: Option<String>,
+    /// The example file the code was included from
+    pub file: Option<String>,
+}
+
+/// A term in a document that a word in the query matched
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TermMatch {
+    /// The word from the query
+    pub query: String,
+    /// The term it matched in the document
+    pub term: String,
+    pub kind: MatchKind,
+}
+
+/// How a query word matched a term
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MatchKind {
+    /// The term is the query word
+    Exact,
+    /// The term starts with the query word
+    Prefix,
+    /// The term is within a few typos of the query word. The UI can show the corrected term
+    Fuzzy { typos: usize },
+}
+
+impl TermMatch {
+    /// Returns true if the query word was corrected to match the term
+    pub fn is_correction(&self) -> bool {
+        matches!(self.kind, MatchKind::Fuzzy { .. })
+    }
+}
+
+/// An error returned by a [`SearchBackend`] while running a query
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchError {
+    message: String,
+}
+
+impl SearchError {
+    pub fn new(message: impl Display) -> Self {
+        Self {
+            message: message.to_string(),
+        }
+    }
+}
+
+impl Display for SearchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for SearchError {}
@@ -0,0 +1,479 @@
+//! Build search documents straight from the markdown of a book, without rendering it first
+
+use std::{
+    collections::HashMap,
+    hash::Hash,
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+use mdbook_shared::{BookVars, FrontMatter, MdBook, Page, SummaryItem};
+use pulldown_cmark::{CodeBlockKind, Event, Parser, Tag};
+
+use crate::{
+    sections::SectionSplitter, CodeExample, CodeSource, Document, IndexError, Language,
+    NativeIndex, TokenizerConfig,
+};
+
+impl NativeIndex {
+    /// Build an index from the markdown of a book. See [`crate::SearchIndex::from_book`]
+    pub fn from_book<R: Hash + Eq + Clone>(
+        book: &MdBook<R>,
+        vars: &BookVars,
+        include_dir: &Path,
+        url: impl Fn(&R) -> String,
+        tokenizer: TokenizerConfig,
+    ) -> Result<Self, IndexError> {
+        let documents = documents_from_book(book, vars, include_dir, url)?;
+        Ok(Self::with_tokenizer(documents, tokenizer))
+    }
+}
+
+fn documents_from_book<R: Hash + Eq + Clone>(
+    book: &MdBook<R>,
+    vars: &BookVars,
+    include_dir: &Path,
+    url: impl Fn(&R) -> String,
+) -> Result<Vec<Document>, IndexError> {
+    let summary = &book.summary;
+    let mut depths = HashMap::new();
+    for items in [
+        &summary.prefix_chapters,
+        &summary.numbered_chapters,
+        &summary.suffix_chapters,
+    ] {
+        summary_depths(items, 0, &mut depths);
+    }
+
+    let mut documents = Vec::new();
+    for (_, page) in book.pages().iter() {
+        let depth = depths.get(&page.url).copied().unwrap_or_default();
+        let start = documents.len();
+        documents.extend(documents_from_page(
+            page,
+            vars,
+            include_dir,
+            url(&page.url),
+        )?);
+        for document in &mut documents[start..] {
+            document.depth = depth;
+        }
+    }
+    Ok(documents)
+}
+
+/// Record how deeply each page is nested in the `SUMMARY.md`
+fn summary_depths<R: Hash + Eq + Clone>(
+    items: &[SummaryItem<R>],
+    depth: u32,
+    depths: &mut HashMap<R, u32>,
+) {
+    for item in items {
+        if let SummaryItem::Link(link) = item {
+            if let Some(location) = &link.location {
+                depths.insert(location.clone(), depth);
+            }
+            summary_depths(&link.nested_items, depth + 1, depths);
+        }
+    }
+}
+
+fn documents_from_page<R>(
+    page: &Page<R>,
+    vars: &BookVars,
+    include_dir: &Path,
+    url: String,
+) -> Result<Vec<Document>, IndexError> {
+    let (front_matter, markdown) = FrontMatter::split(&page.raw);
+    // Substitute `{{ variables }}` the same way mdbook-gen does before the page is rendered
+    let markdown = vars
+        .substitute(markdown)
+        .map_err(|err| IndexError::Book(format!("{}: {err}", page.title)))?;
+    let mut splitter = SectionSplitter::new(url, page.title.clone());
+    splitter.keywords().extend(front_matter.keywords);
+    if let Some(language) = &front_matter.language {
+        splitter.set_language(Language::from_code(language));
+    }
+
+    // mdbook-shared records a section for every heading in the page in order, including the
+    // anchor the heading is rendered with
+    let mut sections = page.sections.iter();
+    let mut heading: Option<(usize, String)> = None;
+    let mut in_code_block = false;
+    // The fenced code block being read
+    let mut example: Option<CodeExample> = None;
+
+    for event in Parser::new(&markdown) {
+        match event {
+            Event::Start(Tag::Heading(level, ..)) => {
+                heading = Some((level as usize, String::new()));
+            }
+            Event::End(Tag::Heading(..)) => {
+                let section = sections.next();
+                let Some((level, title)) = heading.take() else {
+                    continue;
+                };
+                // The page title is already the title of the first section
+                if level == 1 {
+                    continue;
+                }
+                if SectionSplitter::splits_at(level) {
+                    let anchor = section.map(|section| section.id.clone());
+                    splitter.start_section(level, &title, anchor);
+                } else {
+                    splitter.new_line();
+                    splitter.body().push_str(&title);
+                    splitter.new_line();
+                }
+            }
+            Event::Text(text) if heading.is_none() && in_code_block => {
+                let mut file = None;
+                let code = resolve_includes(&text, include_dir, &mut file)
+                    .map_err(|err| IndexError::Book(format!("{}: {err}", page.title)))?;
+                if let Some(example) = &mut example {
+                    example.code.push_str(&code);
+                    if example.source.file.is_none() {
+                        example.source.file = file;
+                    }
+                }
+                splitter.code().push_str(&code);
+                splitter.body().push_str(&code);
+            }
+            Event::Text(text) if heading.is_none() => {
+                splitter.body().push_str(&strip_directives(&text));
+            }
+            Event::Code(code) if heading.is_none() => {
+                splitter.code().push_str(&code);
+                splitter.code().push('\n');
+                splitter.body().push_str(&code);
+            }
+            Event::Text(text) | Event::Code(text) => {
+                if let Some((_, title)) = &mut heading {
+                    title.push_str(&text);
+                }
+            }
+            Event::SoftBreak => splitter.body().push(' '),
+            Event::HardBreak => splitter.new_line(),
+            Event::Start(Tag::CodeBlock(kind)) => {
+                in_code_block = true;
+                if let CodeBlockKind::Fenced(info) = kind {
+                    example = Some(CodeExample {
+                        source: code_source(&info),
+                        code: String::new(),
+                    });
+                }
+                splitter.new_line();
+            }
+            Event::End(Tag::CodeBlock(_)) => {
+                in_code_block = false;
+                if let Some(mut example) = example.take() {
+                    // Blocks that only include a file are in the language of the file
+                    if example.source.language.is_none() {
+                        example.source.language = example.source.file.as_deref().and_then(|file| {
+                            Some(Path::new(file).extension()?.to_string_lossy().to_string())
+                        });
+                    }
+                    splitter.add_example(example);
+                }
+                splitter.code().push('\n');
+                splitter.new_line();
+            }
+            Event::End(
+                Tag::Paragraph | Tag::Item | Tag::BlockQuote | Tag::TableHead | Tag::TableRow,
+            ) => splitter.new_line(),
+            Event::End(Tag::TableCell) => splitter.body().push(' '),
+            _ => {}
+        }
+    }
+
+    Ok(splitter.finish())
+}
+
+/// Remove mdbook directives like `{{#include file.rs}}` that are replaced when the book is rendered
+fn strip_directives(text: &str) -> String {
+    let mut stripped = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("{{#") {
+        let Some(end) = rest[start..].find("}}") else {
+            break;
+        };
+        stripped.push_str(&rest[..start]);
+        rest = &rest[start + end + 2..];
+    }
+    stripped.push_str(rest);
+    stripped
+}
+
+/// Read the language and file from the info string of a fenced code block, like `rust` or
+/// `rust, no_run` or `rs@main.rs`. mdbook-gen adds the name of the file a block includes after `@`
+fn code_source(info: &str) -> CodeSource {
+    let (language, file) = match info.split_once('@') {
+        Some((language, file)) => (language, Some(file.trim())),
+        None => (info, None),
+    };
+    let language = language
+        .split(|ch: char| ch.is_whitespace() || ch == ',')
+        .next()
+        .unwrap_or_default();
+    CodeSource {
+        language: (!language.is_empty()).then(|| language.to_string()),
+        file: file.filter(|file| !file.is_empty()).map(str::to_string),
+    }
+}
+
+/// Replace `{{#include file.rs:anchor}}` directives in a code block with the code they include,
+/// the same way mdbook-gen does when the book is rendered. Paths are relative to `include_dir`,
+/// which is the crate the book is generated in. The path of the first included file is written to
+/// `file`
+fn resolve_includes(
+    code: &str,
+    include_dir: &Path,
+    file: &mut Option<String>,
+) -> Result<String, String> {
+    let mut resolved = String::new();
+    let mut rest = code;
+    while let Some((range, path, anchor)) = next_include(rest) {
+        resolved.push_str(&rest[..range.start]);
+        let full_path = include_dir.join(path);
+        let contents = std::fs::read_to_string(&full_path)
+            .map_err(|err| format!("Failed to include {}: {err}", full_path.display()))?;
+        resolved.push_str(&include_anchor(&contents, anchor));
+        file.get_or_insert_with(|| path.to_string());
+        rest = &rest[range.end..];
+    }
+    resolved.push_str(rest);
+    Ok(resolved)
+}
+
+/// Find the next `{{#include file.rs:anchor}}` directive in the text. Returns the range of the
+/// directive, the path and the anchor
+fn next_include(text: &str) -> Option<(Range<usize>, &str, Option<&str>)> {
+    let start = text.find("{{#include")?;
+    let end = start + text[start..].find("}}")? + 2;
+    let directive = text[start + "{{#include".len()..end - 2].trim();
+    let (path, anchor) = match directive.split_once(':') {
+        Some((path, anchor)) => (path, Some(anchor)),
+        None => (directive, None),
+    };
+    Some((start..end, path, anchor))
+}
+
+/// Every file the book includes with `{{#include}}`, relative to `include_dir`. Track these
+/// files along with the markdown to build the index again when an included example changes
+pub fn book_includes<R: Hash + Eq + Clone>(book: &MdBook<R>, include_dir: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    for (_, page) in book.pages().iter() {
+        let mut rest = page.raw.as_str();
+        while let Some((range, path, _)) = next_include(rest) {
+            let path = include_dir.join(path);
+            if path.is_file() && !files.contains(&path) {
+                files.push(path);
+            }
+            rest = &rest[range.end..];
+        }
+    }
+    files
+}
+
+/// The lines between `ANCHOR: name` and `ANCHOR_END: name`, or the whole file without any anchor
+/// comments if there is no anchor
+fn include_anchor(contents: &str, anchor: Option<&str>) -> String {
+    let mut lines = Vec::new();
+    let mut in_anchor = anchor.is_none();
+    let mut indent = 0;
+    for line in contents.lines() {
+        if let Some((_, name)) = line.split_once("ANCHOR:") {
+            if Some(name.trim()) == anchor {
+                in_anchor = true;
+                indent = line.len() - line.trim_start().len();
+            }
+        } else if let Some((_, name)) = line.split_once("ANCHOR_END:") {
+            if Some(name.trim()) == anchor {
+                in_anchor = false;
+            }
+        } else if in_anchor {
+            // Remove the indentation of the anchor comment from every line
+            let trimmed = line.len() - line.trim_start().len();
+            lines.push(&line[trimmed.min(indent)..]);
+        }
+    }
+    lines.join("\n")
+}
+
+/// The url the router of a generated book uses for a page. For example, `guide/index.md` is
+/// `/guide/` and `guide/state.md` is `/guide/state`.
+///
+/// Pass the prefix the book is nested under in your router, or an empty string if it isn't nested.
+pub fn book_page_url(prefix: &str, path: &Path) -> String {
+    let path = path.with_extension("");
+    let path = path.to_string_lossy().replace('\\', "/");
+    let path = path.strip_suffix("index").unwrap_or(&path);
+    let path = path.trim_start_matches('/');
+    format!("{}/{path}", prefix.trim_end_matches('/'))
+}
+
+#[test]
+fn book_page_urls() {
+    assert_eq!(book_page_url("", Path::new("index.md")), "/");
+    assert_eq!(
+        book_page_url("/learn/0.7", Path::new("guide/index.md")),
+        "/learn/0.7/guide/"
+    );
+    assert_eq!(
+        book_page_url("/learn/0.7/", Path::new("guide/state.md")),
+        "/learn/0.7/guide/state"
+    );
+}
+
+#[test]
+fn split_markdown_into_sections() {
+    use mdbook_shared::{PageId, Section};
+
+    let raw = "---\nkeywords: state\n---\n# Signals\n\nSignals hold state.\n\n## Reading `Signal`s\n\nCall read.\n\n```rust\n{{#include src/read.rs}}\nlet value = signal.read();\n```\n\n### Copy\n\nSignals are Copy.\n\n#### Details\n\nMore.\n";
+    let page = Page {
+        title: "Signals".to_string(),
+        url: "signals.md".to_string(),
+        segments: Vec::new(),
+        raw: raw.to_string(),
+        sections: [
+            (1, "Signals", "signals"),
+            (2, "Reading Signals", "reading-signals"),
+            (3, "Copy", "copy"),
+            (4, "Details", "details"),
+        ]
+        .into_iter()
+        .map(|(level, title, id)| Section {
+            level,
+            title: title.to_string(),
+            id: id.to_string(),
+        })
+        .collect(),
+        id: PageId(0),
+    };
+
+    let include_dir =
+        std::env::temp_dir().join(format!("dioxus_search_includes_{}", std::process::id()));
+    std::fs::create_dir_all(include_dir.join("src")).unwrap();
+    std::fs::write(
+        include_dir.join("src/read.rs"),
+        "let signal = use_signal(|| 0);",
+    )
+    .unwrap();
+
+    let documents = documents_from_page(
+        &page,
+        &BookVars::default(),
+        &include_dir,
+        "/signals".to_string(),
+    )
+    .unwrap();
+    let summary: Vec<_> = documents
+        .iter()
+        .map(|document| {
+            (
+                document.anchor.as_deref(),
+                document.breadcrumb.join(" > "),
+                document.title.as_str(),
+                document.body.as_str(),
+                document.keywords.clone(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            (
+                None,
+                String::new(),
+                "Signals",
+                "Signals hold state.",
+                vec!["state".to_string()]
+            ),
+            (
+                Some("reading-signals"),
+                "Signals".to_string(),
+                "Reading Signals",
+                "Call read.\nlet signal = use_signal(|| 0);\nlet value = signal.read();",
+                Vec::new()
+            ),
+            (
+                Some("copy"),
+                "Signals > Reading Signals".to_string(),
+                "Copy",
+                "Signals are Copy.\nDetails\nMore.",
+                Vec::new()
+            ),
+        ]
+    );
+
+    // The block records the file it includes
+    assert_eq!(
+        documents[1].examples,
+        [CodeExample {
+            source: CodeSource {
+                language: Some("rust".to_string()),
+                file: Some("src/read.rs".to_string()),
+            },
+            code: "let signal = use_signal(|| 0);\nlet value = signal.read();".to_string(),
+        }]
+    );
+    assert!(documents[0].examples.is_empty());
+
+    // Includes are read relative to the include dir, and a missing file fails the whole index
+    let missing = documents_from_page(
+        &page,
+        &BookVars::default(),
+        &include_dir.join("src"),
+        "/signals".to_string(),
+    );
+    assert!(matches!(missing, Err(IndexError::Book(message)) if message.contains("read.rs")));
+    std::fs::remove_dir_all(&include_dir).unwrap();
+}
+
+#[test]
+fn code_block_sources() {
+    let source = |language: &str, file: &str| CodeSource {
+        language: (!language.is_empty()).then(|| language.to_string()),
+        file: (!file.is_empty()).then(|| file.to_string()),
+    };
+    assert_eq!(code_source("rust"), source("rust", ""));
+    assert_eq!(code_source("rust, no_run"), source("rust", ""));
+    assert_eq!(code_source("rs@main.rs"), source("rs", "main.rs"));
+    assert_eq!(code_source(""), source("", ""));
+
+    let file =
+        "fn main() {\n    // ANCHOR: app\n    let app = App::new();\n    // ANCHOR_END: app\n}";
+    assert_eq!(include_anchor(file, Some("app")), "let app = App::new();");
+    assert_eq!(
+        include_anchor(file, None),
+        "fn main() {\n    let app = App::new();\n}"
+    );
+}
+
+#[test]
+fn substitute_book_vars() {
+    let raw = "# Installing\n\nAdd `dioxus = \"{{ dioxus_version }}\"` to your Cargo.toml.\n\n```toml\ndioxus = \"{{ dioxus_version }}\"\n```\n";
+    let page = Page {
+        title: "Installing".to_string(),
+        url: "installing.md".to_string(),
+        segments: Vec::new(),
+        raw: raw.to_string(),
+        sections: Vec::new(),
+        id: mdbook_shared::PageId(0),
+    };
+    let vars = BookVars::from_toml("[preprocessor.vars]\ndioxus_version = \"0.7.0\"").unwrap();
+
+    let documents =
+        documents_from_page(&page, &vars, Path::new("."), "/installing".to_string()).unwrap();
+    assert!(documents[0].body.contains("dioxus = \"0.7.0\""));
+    assert!(documents[0].code.contains("dioxus = \"0.7.0\""));
+    assert!(!documents[0].body.contains("{{"));
+
+    let err = documents_from_page(
+        &page,
+        &BookVars::default(),
+        Path::new("."),
+        "/installing".to_string(),
+    );
+    assert!(matches!(err, Err(IndexError::Book(_))));
+}
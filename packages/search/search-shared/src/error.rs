@@ -0,0 +1,80 @@
+use std::{fmt::Display, path::PathBuf};
+
+use crate::{DecodeError, SearchError};
+
+/// An error building, writing or loading a [`crate::SearchIndex`]
+#[derive(Debug)]
+pub enum IndexError {
+    /// Reading or writing an index file failed
+    Io {
+        path: PathBuf,
+        error: std::io::Error,
+    },
+    /// The index is truncated or isn't a search index
+    Corrupt(DecodeError),
+    /// The index was built by a backend this build can't load, like a stork index without the
+    /// `stork` feature
+    Incompatible(String),
+    /// The index was written by a different version of dioxus-search. Rebuild the index to fix
+    /// this
+    VersionMismatch { found: u32, expected: u32 },
+    /// The index hasn't been built yet
+    MissingIndex { name: String },
+    /// A page of the book the index is built from can't be indexed, like a page using an
+    /// undeclared variable
+    Book(String),
+}
+
+impl IndexError {
+    pub(crate) fn io(path: impl Into<PathBuf>, error: std::io::Error) -> Self {
+        Self::Io {
+            path: path.into(),
+            error,
+        }
+    }
+}
+
+impl Display for IndexError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IndexError::Io { path, error } => {
+                write!(f, "Failed to access search index {}: {error}", path.display())
+            }
+            IndexError::Corrupt(error) => error.fmt(f),
+            IndexError::Incompatible(message) => {
+                write!(f, "Incompatible search index: {message}")
+            }
+            IndexError::VersionMismatch { found, expected } => write!(
+                f,
+                "The search index has format version {found}, but this version of dioxus-search reads version {expected}. Rebuild the index"
+            ),
+            IndexError::MissingIndex { name } => write!(
+                f,
+                "The search index {name} hasn't been built yet. Build it with SearchIndex::create"
+            ),
+            IndexError::Book(message) => write!(f, "Failed to index the book: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for IndexError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            IndexError::Io { error, .. } => Some(error),
+            IndexError::Corrupt(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<DecodeError> for IndexError {
+    fn from(error: DecodeError) -> Self {
+        Self::Corrupt(error)
+    }
+}
+
+impl From<IndexError> for SearchError {
+    fn from(error: IndexError) -> Self {
+        SearchError::new(error)
+    }
+}
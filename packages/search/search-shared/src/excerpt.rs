@@ -0,0 +1,406 @@
+//! Excerpts of a document with the words that matched a query highlighted.
+//!
+//! Highlights are UTF-8 byte ranges into the text. Before an excerpt is cut they are widened to
+//! whole grapheme clusters and overlapping ranges are merged, and the context around the matches
+//! is counted in grapheme clusters too. An excerpt never splits an accented letter or an emoji,
+//! and the context is the same length for any script.
+
+use serde::{Deserialize, Serialize};
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::{Excerpt, Segment};
+
+/// How excerpts are cut from the body of a document
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExcerptConfig {
+    /// The number of graphemes shown before the first match in an excerpt. Twice as many are
+    /// shown after it, so the matches after the first one have room too
+    pub context: usize,
+    /// The most excerpts shown for each result. The excerpts with the most matches are chosen,
+    /// and they are returned in the order they appear in the document
+    pub max_excerpts: usize,
+}
+
+impl Default for ExcerptConfig {
+    fn default() -> Self {
+        Self {
+            context: 80,
+            max_excerpts: 1,
+        }
+    }
+}
+
+/// The byte offset every grapheme cluster starts at, followed by the length of the text
+struct Graphemes {
+    bounds: Vec<usize>,
+}
+
+impl Graphemes {
+    fn new(text: &str) -> Self {
+        let bounds = text
+            .grapheme_indices(true)
+            .map(|(index, _)| index)
+            .chain(std::iter::once(text.len()))
+            .collect();
+        Self { bounds }
+    }
+
+    /// The number of grapheme clusters in the text
+    fn len(&self) -> usize {
+        self.bounds.len() - 1
+    }
+
+    /// The grapheme cluster the byte offset is in
+    fn floor(&self, offset: usize) -> usize {
+        self.bounds.partition_point(|bound| *bound <= offset).max(1) - 1
+    }
+
+    /// The first grapheme cluster that starts at or after the byte offset
+    fn ceil(&self, offset: usize) -> usize {
+        self.bounds.partition_point(|bound| *bound < offset)
+    }
+
+    /// The byte offset of a grapheme cluster, or the length of the text past the last one
+    fn offset(&self, grapheme: usize) -> usize {
+        self.bounds[grapheme.min(self.len())]
+    }
+}
+
+/// Widen the byte ranges to whole grapheme clusters, drop empty ranges and merge ranges that
+/// overlap or touch. The result is sorted and in grapheme clusters
+fn merge_graphemes(graphemes: &Graphemes, highlights: &[(usize, usize)]) -> Vec<(usize, usize)> {
+    let mut ranges: Vec<(usize, usize)> = highlights
+        .iter()
+        .filter(|(start, end)| start < end)
+        .map(|&(start, end)| (graphemes.floor(start), graphemes.ceil(end)))
+        .filter(|(start, end)| start < end)
+        .collect();
+    ranges.sort_unstable();
+
+    let mut merged: Vec<(usize, usize)> = Vec::with_capacity(ranges.len());
+    for (start, end) in ranges {
+        match merged.last_mut() {
+            Some((_, last_end)) if start <= *last_end => *last_end = (*last_end).max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+/// Widen the byte ranges of the highlights to whole grapheme clusters and merge the ones that
+/// overlap or touch. The result is sorted byte ranges that start and end on grapheme boundaries
+pub fn merge_highlights(text: &str, highlights: &[(usize, usize)]) -> Vec<(usize, usize)> {
+    let graphemes = Graphemes::new(text);
+    merge_graphemes(&graphemes, highlights)
+        .into_iter()
+        .map(|(start, end)| (graphemes.offset(start), graphemes.offset(end)))
+        .collect()
+}
+
+/// Split the whole text into highlighted and plain segments
+pub fn highlight(text: &str, highlights: &[(usize, usize)]) -> Vec<Segment> {
+    segments(text, 0, text.len(), &merge_highlights(text, highlights))
+}
+
+/// Cut the excerpts with the most highlights out of the text. If nothing in the text is
+/// highlighted, the start of the text is the only excerpt
+pub fn excerpts(text: &str, highlights: &[(usize, usize)], config: &ExcerptConfig) -> Vec<Excerpt> {
+    let graphemes = Graphemes::new(text);
+    let highlights = merge_graphemes(&graphemes, highlights);
+
+    // Every excerpt starts a little before one of the highlights. Count the highlights each one
+    // would show in full
+    let mut windows: Vec<(usize, usize, usize)> = highlights
+        .iter()
+        .map(|(anchor, _)| {
+            let start = anchor.saturating_sub(config.context);
+            let end = (anchor + config.context * 2).min(graphemes.len());
+            let count = highlights
+                .iter()
+                .filter(|(s, e)| *s >= start && *e <= end)
+                .count();
+            (start, end, count)
+        })
+        .collect();
+    windows.sort_by_key(|(start, _, count)| (std::cmp::Reverse(*count), *start));
+    if windows.is_empty() {
+        windows.push((0, (config.context * 2).min(graphemes.len()), 0));
+    }
+
+    let mut chosen: Vec<(usize, usize, usize)> = Vec::new();
+    for window in windows {
+        if chosen.len() >= config.max_excerpts.max(1) {
+            break;
+        }
+        let overlaps = chosen
+            .iter()
+            .any(|(start, end, _)| window.0 < *end && *start < window.1);
+        if !overlaps {
+            chosen.push(window);
+        }
+    }
+    chosen.sort_unstable();
+
+    let highlights: Vec<_> = highlights
+        .into_iter()
+        .map(|(start, end)| (graphemes.offset(start), graphemes.offset(end)))
+        .collect();
+    chosen
+        .into_iter()
+        .map(|(start, end, count)| Excerpt {
+            text: segments(
+                text,
+                graphemes.offset(start),
+                graphemes.offset(end),
+                &highlights,
+            ),
+            score: count,
+        })
+        .collect()
+}
+
+/// Split the text between the byte offsets `start` and `end` into highlighted and plain
+/// segments. The highlights must be sorted, must not overlap and must start and end on char
+/// boundaries. Highlights that are only partly between the offsets are cut to fit
+pub(crate) fn segments(
+    text: &str,
+    start: usize,
+    end: usize,
+    highlights: &[(usize, usize)],
+) -> Vec<Segment> {
+    let mut segments = Vec::new();
+    let mut cursor = start;
+    for &(highlight_start, highlight_end) in highlights {
+        let highlight_start = highlight_start.max(start);
+        let highlight_end = highlight_end.min(end);
+        if highlight_start >= highlight_end {
+            continue;
+        }
+        if highlight_start > cursor {
+            segments.push(Segment {
+                text: text[cursor..highlight_start].to_string(),
+                highlighted: false,
+            });
+        }
+        segments.push(Segment {
+            text: text[highlight_start..highlight_end].to_string(),
+            highlighted: true,
+        });
+        cursor = highlight_end;
+    }
+    if cursor < end {
+        segments.push(Segment {
+            text: text[cursor..end].to_string(),
+            highlighted: false,
+        });
+    }
+    segments
+}
+
+#[cfg(test)]
+fn text_of(excerpt: &Excerpt) -> String {
+    excerpt
+        .text
+        .iter()
+        .map(|segment| segment.text.as_str())
+        .collect()
+}
+
+#[cfg(test)]
+fn highlighted(excerpt: &Excerpt) -> Vec<&str> {
+    excerpt
+        .text
+        .iter()
+        .filter(|segment| segment.highlighted)
+        .map(|segment| segment.text.as_str())
+        .collect()
+}
+
+#[test]
+fn highlights_count_graphemes() {
+    // "é" is two chars here, an "e" and a combining accent, and the flag is two chars too
+    let text = "cafe\u{301} 🇫🇷 signal";
+    let signal = text.find("signal").unwrap();
+    let segments = highlight(text, &[(signal, text.len()), (0, 4)]);
+    let highlighted: Vec<_> = segments
+        .iter()
+        .filter(|segment| segment.highlighted)
+        .map(|segment| segment.text.as_str())
+        .collect();
+    // The highlight that ended before the accent is widened to the whole grapheme
+    assert_eq!(highlighted, ["cafe\u{301}", "signal"]);
+
+    // A range inside the flag highlights the whole flag
+    let flag = text.find('🇫').unwrap();
+    let segments = highlight(text, &[(flag + 1, flag + 2)]);
+    assert!(segments
+        .iter()
+        .any(|segment| segment.highlighted && segment.text == "🇫🇷"));
+}
+
+#[test]
+fn overlapping_highlights_are_merged() {
+    let text = "use_signal reads a signal";
+    let merged = merge_highlights(text, &[(4, 10), (0, 10), (0, 3), (19, 25), (19, 22)]);
+    assert_eq!(merged, [(0, 10), (19, 25)]);
+
+    // Touching highlights are one highlighted segment
+    let segments = highlight(text, &[(0, 3), (3, 4), (4, 10)]);
+    assert_eq!(segments[0].text, "use_signal");
+    assert!(segments[0].highlighted);
+}
+
+#[test]
+fn several_excerpts_with_context() {
+    let text = "signal ".to_string() + &"filler ".repeat(20) + "another signal here";
+    let second = text.rfind("signal").unwrap();
+    let highlights = [(0, 6), (second, second + 6)];
+    let config = ExcerptConfig {
+        context: 10,
+        max_excerpts: 3,
+    };
+    let found = excerpts(&text, &highlights, &config);
+
+    // The matches are too far apart for one excerpt, so each gets its own in document order
+    assert_eq!(found.len(), 2);
+    assert_eq!(text_of(&found[0]), "signal filler filler");
+    assert_eq!(text_of(&found[1]), "r another signal here");
+    for excerpt in &found {
+        assert_eq!(highlighted(excerpt), ["signal"]);
+        assert_eq!(excerpt.score, 1);
+        assert!(text_of(excerpt).chars().count() <= 30);
+    }
+
+    // One excerpt only shows the first match
+    let config = ExcerptConfig {
+        max_excerpts: 1,
+        ..config
+    };
+    let found = excerpts(&text, &highlights, &config);
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].text[0].text, "signal");
+}
+
+#[test]
+fn excerpts_without_highlights_show_the_start() {
+    let config = ExcerptConfig {
+        context: 2,
+        max_excerpts: 2,
+    };
+    let found = excerpts("n\u{303}andú 😀 rápido", &[], &config);
+    assert_eq!(found.len(), 1);
+    assert_eq!(text_of(&found[0]), "n\u{303}and");
+    assert_eq!(found[0].score, 0);
+}
+
+#[cfg(test)]
+mod properties {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    /// Text with multi-byte chars, combining marks and emoji made of several chars
+    fn text() -> impl Strategy<Value = String> {
+        proptest::collection::vec(
+            prop_oneof![
+                Just("a"),
+                Just("signal"),
+                Just(" "),
+                Just("é"),
+                Just("e\u{301}"),
+                Just("日本"),
+                Just("😀"),
+                Just("👩‍💻"),
+                Just("🇫🇷"),
+                Just("\n"),
+            ],
+            0..60,
+        )
+        .prop_map(|parts| parts.concat())
+    }
+
+    /// Text and byte ranges in it. The ranges can start and end in the middle of a char
+    fn text_and_highlights() -> impl Strategy<Value = (String, Vec<(usize, usize)>)> {
+        text().prop_flat_map(|text| {
+            let len = text.len();
+            let ranges = proptest::collection::vec((0..=len, 0..=len), 0..8);
+            (Just(text), ranges)
+        })
+    }
+
+    fn is_grapheme_boundary(text: &str, offset: usize) -> bool {
+        offset == text.len()
+            || text
+                .grapheme_indices(true)
+                .any(|(index, _)| index == offset)
+    }
+
+    /// Whether the text starts or ends in the middle of one of the graphemes [`text`] generates
+    fn splits_a_grapheme(text: &str) -> bool {
+        let flags = text.chars().filter(|c| ('🇦'..='🇿').contains(c)).count();
+        text.starts_with('\u{301}')
+            || text.starts_with('\u{200d}')
+            || text.ends_with('\u{200d}')
+            || flags % 2 == 1
+    }
+
+    proptest! {
+        #[test]
+        fn highlighting_keeps_the_text((text, highlights) in text_and_highlights()) {
+            let segments = highlight(&text, &highlights);
+            let joined: String = segments.iter().map(|segment| segment.text.as_str()).collect();
+            prop_assert_eq!(joined, text);
+
+            // Merged highlights are never next to each other, and no segment is empty
+            for pair in segments.windows(2) {
+                prop_assert!(!(pair[0].highlighted && pair[1].highlighted));
+            }
+            prop_assert!(segments.iter().all(|segment| !segment.text.is_empty()));
+        }
+
+        #[test]
+        fn merged_highlights_are_whole_graphemes((text, highlights) in text_and_highlights()) {
+            let merged = merge_highlights(&text, &highlights);
+            for pair in merged.windows(2) {
+                prop_assert!(pair[0].1 < pair[1].0);
+            }
+            for &(start, end) in &merged {
+                prop_assert!(start < end);
+                prop_assert!(is_grapheme_boundary(&text, start));
+                prop_assert!(is_grapheme_boundary(&text, end));
+                // Every merged range covers at least one of the original ranges
+                prop_assert!(highlights.iter().any(|&(s, e)| s < e && s < end && e > start));
+            }
+        }
+
+        #[test]
+        fn excerpts_are_cut_from_the_text(
+            (text, highlights) in text_and_highlights(),
+            context in 0usize..20,
+            max_excerpts in 1usize..4,
+        ) {
+            let config = ExcerptConfig { context, max_excerpts };
+            let excerpts = excerpts(&text, &highlights, &config);
+            prop_assert!(!excerpts.is_empty());
+            prop_assert!(excerpts.len() <= max_excerpts);
+
+            let mut previous_end = 0;
+            for excerpt in &excerpts {
+                let excerpt_text = text_of(excerpt);
+                prop_assert!(excerpt_text.graphemes(true).count() <= context * 3);
+
+                // Excerpts are in document order and don't overlap
+                let start = text[previous_end..]
+                    .find(&excerpt_text)
+                    .map(|start| start + previous_end);
+                prop_assert!(start.is_some(), "{excerpt_text:?} is not in {text:?}");
+                previous_end = start.unwrap() + excerpt_text.len();
+                prop_assert!(!splits_a_grapheme(&excerpt_text), "{excerpt_text:?} splits a grapheme");
+
+                let full = highlighted(excerpt).len();
+                prop_assert!(excerpt.score <= full);
+            }
+        }
+    }
+}
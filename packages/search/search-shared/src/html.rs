@@ -0,0 +1,343 @@
+use std::{fmt::Display, str::FromStr};
+
+use dioxus_router::routable::Routable;
+use scraper::{ElementRef, Html, Node, Selector};
+
+use crate::{
+    sections::SectionSplitter, CodeExample, CodeSource, Document, Language, SearchIndexMapping,
+};
+
+/// The element that contains the searchable contents of each rendered page
+const CONTENT_SELECTOR: &str = "#main";
+
+/// Elements that start a new line of text when converting HTML to plain text
+const BLOCK_ELEMENTS: &[&str] = &[
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "br",
+    "dd",
+    "details",
+    "div",
+    "dl",
+    "dt",
+    "figcaption",
+    "footer",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "header",
+    "hr",
+    "li",
+    "main",
+    "nav",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "summary",
+    "table",
+    "td",
+    "th",
+    "tr",
+    "ul",
+];
+
+/// Read the rendered HTML for every static route in the mapping
+pub(crate) fn documents_from_route<R: Routable>(
+    mapping: impl SearchIndexMapping<R>,
+) -> Vec<Document>
+where
+    <R as FromStr>::Err: Display,
+{
+    let mut documents = Vec::new();
+    let base_directory = mapping.base_directory();
+
+    // Collect all the static routes
+    let static_routes = R::static_routes();
+    // Add the routes to the index
+    for route in static_routes {
+        let url = route.to_string();
+        if let Some(path) = mapping.map_route(route) {
+            let path = &path.strip_prefix("/").unwrap_or(&path);
+            let absolute_path = base_directory.join(path);
+            log::trace!("Adding {:?} to search index", absolute_path);
+            match std::fs::read_to_string(&absolute_path) {
+                Ok(contents) => {
+                    let document = Html::parse_document(&contents);
+                    let title = document
+                        .select(&Selector::parse("h1").unwrap())
+                        .next()
+                        .map(|title| title.text().collect::<String>())
+                        .unwrap_or_else(|| {
+                            document
+                                .select(&Selector::parse("title").unwrap())
+                                .next()
+                                .map(|title| title.text().collect::<String>())
+                                .unwrap_or_else(|| {
+                                    let mut title = String::new();
+                                    for segment in path.iter() {
+                                        title.push_str(&segment.to_string_lossy());
+                                        title.push(' ');
+                                    }
+                                    title
+                                })
+                        });
+                    let body = document
+                        .select(&Selector::parse(CONTENT_SELECTOR).unwrap())
+                        .next()
+                        .unwrap_or_else(|| document.root_element());
+                    let language = document.root_element().value().attr("lang");
+                    let language = language.map(Language::from_code);
+                    documents.extend(split_sections(&url, title, language, body));
+                }
+                Err(err) => {
+                    log::error!("Error reading file: {:?}: {}", absolute_path, err);
+                }
+            }
+        }
+    }
+
+    // Pages with fewer segments in their url are higher in the hierarchy of the site
+    let segments = |url: &str| url.split('/').filter(|segment| !segment.is_empty()).count();
+    let top = documents
+        .iter()
+        .map(|document| segments(&document.url))
+        .min()
+        .unwrap_or_default();
+    for document in &mut documents {
+        document.depth = (segments(&document.url) - top) as u32;
+    }
+
+    // Documents without a page set their own depth
+    documents.extend(mapping.documents());
+
+    documents
+}
+
+/// Split the contents of a page into a document for the top of the page and one for each
+/// section heading
+fn split_sections(
+    url: &str,
+    title: String,
+    language: Option<Language>,
+    content: ElementRef,
+) -> Vec<Document> {
+    let mut splitter = SectionSplitter::new(url, title);
+    if let Some(language) = language {
+        splitter.set_language(language);
+    }
+    push_text(&mut splitter, content, false);
+    splitter.finish()
+}
+
+fn push_text(splitter: &mut SectionSplitter, element: ElementRef, in_code: bool) {
+    let name = element.value().name();
+    // The page title is already the title of the first section
+    if matches!(name, "script" | "style" | "noscript" | "h1") {
+        return;
+    }
+
+    if let Some(level) = section_level(element) {
+        let title = element.text().collect::<String>();
+        let anchor = element.value().id().map(str::to_string);
+        splitter.start_section(level, &title, anchor);
+        return;
+    }
+
+    if name == "pre" && !in_code {
+        splitter.add_example(CodeExample {
+            source: code_source(element),
+            code: element.text().collect(),
+        });
+    }
+
+    let is_code = matches!(name, "code" | "pre");
+    let is_block = BLOCK_ELEMENTS.contains(&name);
+    if is_block {
+        splitter.new_line();
+    }
+    for child in element.children() {
+        match ElementRef::wrap(child) {
+            Some(child) => push_text(splitter, child, in_code || is_code),
+            None => {
+                if let Node::Text(contents) = child.value() {
+                    // Skip the whitespace between block elements
+                    let body = splitter.body();
+                    if contents.trim().is_empty() && (body.is_empty() || body.ends_with('\n')) {
+                        continue;
+                    }
+                    body.push_str(contents);
+                    if in_code || is_code {
+                        splitter.code().push_str(contents);
+                    }
+                }
+            }
+        }
+    }
+    if is_code && !in_code {
+        splitter.code().push('\n');
+    }
+    if is_block {
+        splitter.new_line();
+    }
+}
+
+/// Find the language and file of a `<pre>` block. The language comes from a `language-*` class or
+/// `data-lang` attribute on the block or its `<code>`, and the file from the header of the code
+/// blocks in our docs, which shows the file after `src/`
+fn code_source(pre: ElementRef) -> CodeSource {
+    let language = std::iter::once(pre)
+        .chain(pre.select(&Selector::parse("code").unwrap()))
+        .find_map(|element| {
+            let element = element.value();
+            element
+                .classes()
+                .find_map(|class| {
+                    class
+                        .strip_prefix("language-")
+                        .or_else(|| class.strip_prefix("lang-"))
+                })
+                .or_else(|| element.attr("data-lang"))
+                .map(str::to_string)
+        });
+
+    let file = pre
+        .ancestors()
+        .filter_map(ElementRef::wrap)
+        .find(|ancestor| ancestor.value().attr("data-codeblock").is_some())
+        .and_then(|block| block.select(&Selector::parse(".font-mono").unwrap()).next())
+        .map(|header| header.text().collect::<String>())
+        .map(|header| {
+            let header = header.trim();
+            header.strip_prefix("src/").unwrap_or(header).to_string()
+        })
+        .filter(|file| !file.is_empty());
+
+    CodeSource { language, file }
+}
+
+/// The level of the heading if the element starts a new section
+fn section_level(element: ElementRef) -> Option<usize> {
+    let level = match element.value().name() {
+        "h2" => 2,
+        "h3" => 3,
+        "h4" => 4,
+        "h5" => 5,
+        "h6" => 6,
+        _ => return None,
+    };
+    if !SectionSplitter::splits_at(level) {
+        return None;
+    }
+    // Only headings with an id can be linked to
+    element.value().id()?;
+    Some(level)
+}
+
+#[test]
+fn split_page_into_sections() {
+    let html = Html::parse_document(
+        r#"<div id="main">
+            <h1>Signals</h1>
+            <p>Signals hold state.</p>
+            <h2 id="reading"><a href="/signals#reading">Reading</a></h2>
+            <p>Call read.</p>
+            <h3 id="copy"><a href="/signals#copy">Copy</a></h3>
+            <p>Signals are Copy.</p>
+            <h2 id="writing"><a href="/signals#writing">Writing</a></h2>
+            <p>Call write.</p>
+        </div>"#,
+    );
+    let content = html
+        .select(&Selector::parse("#main").unwrap())
+        .next()
+        .unwrap();
+    let sections = split_sections(
+        "/signals",
+        "Signals".to_string(),
+        Some(Language::Portuguese),
+        content,
+    );
+    assert!(sections
+        .iter()
+        .all(|section| section.language == Some(Language::Portuguese)));
+
+    let summary: Vec<_> = sections
+        .iter()
+        .map(|section| {
+            (
+                section.anchor.as_deref(),
+                section.breadcrumb.join(" > "),
+                section.title.as_str(),
+                section.body.as_str(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            (None, String::new(), "Signals", "Signals hold state."),
+            (
+                Some("reading"),
+                "Signals".to_string(),
+                "Reading",
+                "Call read."
+            ),
+            (
+                Some("copy"),
+                "Signals > Reading".to_string(),
+                "Copy",
+                "Signals are Copy."
+            ),
+            (
+                Some("writing"),
+                "Signals".to_string(),
+                "Writing",
+                "Call write."
+            ),
+        ]
+    );
+}
+
+#[test]
+fn code_blocks_are_examples() {
+    let html = Html::parse_document(
+        r#"<div id="main">
+            <h1>Resources</h1>
+            <div data-codeblock="true">
+                <div><div class="font-mono">src/weather.rs</div><button>Copy</button></div>
+                <div class="codeblock"><pre>let weather = use_resource(get_weather);</pre></div>
+            </div>
+            <pre><code class="language-toml">[dependencies]</code></pre>
+        </div>"#,
+    );
+    let content = html
+        .select(&Selector::parse("#main").unwrap())
+        .next()
+        .unwrap();
+    let sections = split_sections("/resources", "Resources".to_string(), None, content);
+    assert_eq!(
+        sections[0].examples,
+        [
+            CodeExample {
+                source: CodeSource {
+                    language: None,
+                    file: Some("weather.rs".to_string()),
+                },
+                code: "let weather = use_resource(get_weather);".to_string(),
+            },
+            CodeExample {
+                source: CodeSource {
+                    language: Some("toml".to_string()),
+                    file: None,
+                },
+                code: "[dependencies]".to_string(),
+            },
+        ]
+    );
+}
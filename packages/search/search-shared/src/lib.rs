@@ -1,30 +1,52 @@
 use std::{
-    collections::HashMap,
     fmt::{Debug, Display},
-    path::PathBuf,
+    hash::Hash,
+    path::{Path, PathBuf},
     str::FromStr,
 };
 
 use bytes::Bytes;
 use dioxus_router::routable::Routable;
-use scraper::{Html, Selector};
+use mdbook_shared::MdBook;
 use serde::{Deserialize, Serialize};
-use stork_lib::{build_index, SearchError};
 
-#[derive(Deserialize, Serialize)]
+mod backend;
+mod book;
+mod error;
+mod excerpt;
+mod html;
+mod native;
+mod ranking;
+mod sections;
+#[cfg(feature = "stork")]
+mod stork;
+
+pub use backend::*;
+pub use book::{book_includes, book_page_url};
+pub use error::IndexError;
+pub use excerpt::{excerpts, highlight, merge_highlights, ExcerptConfig};
+pub use mdbook_shared::BookVars;
+pub use native::{
+    Clause, CodeExample, DecodeError, Document, Field, Language, NativeIndex, Query, SearchMode,
+    ShardedIndex, TokenizerConfig, DICTIONARY_SHARD, NATIVE_INDEX_MAGIC, NATIVE_INDEX_VERSION,
+};
+pub use ranking::{Boosts, PinnedResult, PopularSearch, RankingConfig};
+#[cfg(feature = "stork")]
+pub use stork::StorkIndex;
+
+/// A search index for the routes of type `R`
 pub struct SearchIndex<R> {
-    index: Bytes,
     name: String,
-    mock: bool,
+    /// The backend that answers queries. Indexes without a backend are placeholders that never return results
+    backend: Option<Box<dyn SearchBackend>>,
     _marker: std::marker::PhantomData<R>,
 }
 
 impl<R> Default for SearchIndex<R> {
     fn default() -> Self {
         Self {
-            index: Bytes::new(),
             name: String::new(),
-            mock: true,
+            backend: None,
             _marker: std::marker::PhantomData,
         }
     }
@@ -36,139 +58,332 @@ impl<R> Debug for SearchIndex<R> {
     }
 }
 
-impl<R: Routable> SearchIndex<R>
-where
-    <R as FromStr>::Err: Display,
-{
-    pub fn create(name: impl AsRef<str>, mapping: impl SearchIndexMapping<R>) -> Self {
-        let name = name.as_ref().to_string();
-        let asset_format = Config::from_route(mapping);
+impl<R> SearchIndex<R> {
+    /// Build a search index from the markdown of a book and write it to
+    /// `$CARGO_MANIFEST_DIR/dioxus_search/index_{name}.bin`. Unlike [`SearchIndex::create`],
+    /// this doesn't need the rendered site, so it can run in the `build.rs` of a docs crate:
+    ///
+    /// ```rust, ignore
+    /// let book = mdbook_shared::MdBook::new(PathBuf::from("../../docs-src/0.7")).unwrap();
+    /// let vars = BookVars::load(Path::new("../../docs-src/0.7")).unwrap();
+    /// let crate_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    /// SearchIndex::from_book("searchable_0_7", &book, &vars, &crate_dir, |path| {
+    ///     dioxus_search::book_page_url("/learn/0.7", path)
+    /// });
+    /// ```
+    ///
+    /// `url` maps the url of each page in the book to the url its route is parsed from. Every
+    /// heading section is indexed with the same anchor the generated page uses for it, along with
+    /// any `keywords` from the front matter of the page. `{{ variables }}` from the `[vars]` of
+    /// the `book.toml` are substituted the same way they are in the rendered page.
+    ///
+    /// `{{#include}}` directives in code blocks are read relative to `include_dir`, which should
+    /// be the crate the book is generated in. An include that can't be read fails the index. Use
+    /// [`book_includes`] to find the files to track for changes.
+    ///
+    /// The shards a [`ShardedIndex`] loads are written to
+    /// `$CARGO_MANIFEST_DIR/dioxus_search/index_{name}/` at the same time.
+    pub fn from_book(
+        name: impl AsRef<str>,
+        book: &MdBook<R>,
+        vars: &BookVars,
+        include_dir: &Path,
+        url: impl Fn(&R) -> String,
+    ) -> Result<Self, IndexError>
+    where
+        R: Hash + Eq + Clone,
+    {
+        Self::from_book_with_config(name, book, vars, include_dir, url, IndexConfig::default())
+    }
 
-        let toml = toml::to_string(&asset_format).unwrap();
-        let bytes = build_index(&stork_lib::Config::try_from(&*toml).unwrap())
-            .unwrap()
-            .bytes;
+    /// Like [`SearchIndex::from_book`], but with a custom tokenizer and ranking. Pages can
+    /// override the language of the tokenizer with `language` in their front matter, and pages
+    /// higher in the `SUMMARY.md` get more of the hierarchy boost
+    pub fn from_book_with_config(
+        name: impl AsRef<str>,
+        book: &MdBook<R>,
+        vars: &BookVars,
+        include_dir: &Path,
+        url: impl Fn(&R) -> String,
+        config: IndexConfig,
+    ) -> Result<Self, IndexError>
+    where
+        R: Hash + Eq + Clone,
+    {
+        let index = NativeIndex::from_book(book, vars, include_dir, url, config.tokenizer)?;
+        Self::from_native(
+            name,
+            index
+                .with_ranking(config.ranking)
+                .with_excerpts(config.excerpts),
+        )
+    }
 
-        stork_lib::register_index(&format!("index_{name}"), bytes.clone()).unwrap();
+    /// Use a native index and write it, along with its shards, to the manifest dir
+    fn from_native(name: impl AsRef<str>, index: NativeIndex) -> Result<Self, IndexError> {
+        let name = name.as_ref();
+        for (shard, bytes) in index.to_shards() {
+            write_compressed(
+                &output_dir().join(format!("index_{name}/{shard}.bin")),
+                &bytes,
+            )?;
+        }
+        let myself = Self::from_backend(name, index);
+        myself.write_to_manifest_dir()?;
+        Ok(myself)
+    }
 
-        let myself = Self {
-            index: bytes,
-            name,
-            mock: false,
+    /// Create a search index that answers queries with a custom backend
+    pub fn from_backend(name: impl AsRef<str>, backend: impl SearchBackend + 'static) -> Self {
+        Self {
+            name: name.as_ref().to_string(),
+            backend: Some(Box::new(backend)),
             _marker: std::marker::PhantomData,
-        };
+        }
+    }
+
+    /// Load an index from the bytes written by [`SearchIndex::create`] after decompression
+    pub fn from_bytes<T: Into<Bytes>>(name: impl AsRef<str>, bytes: T) -> Result<Self, IndexError> {
+        let name = name.as_ref().to_string();
+        let bytes = bytes.into();
 
-        let target_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap_or_else(|_| ".".into());
+        // `load_search_index!` includes an empty file until the index is built
+        if bytes.is_empty() {
+            return Err(IndexError::MissingIndex { name });
+        }
 
-        let path = format!("{}/dioxus_search/index_{}.bin", target_dir, myself.name);
-        std::fs::create_dir_all(std::path::Path::new(&path).parent().unwrap()).unwrap();
-        let compressed = yazi::compress(
-            &myself.index,
-            yazi::Format::Zlib,
-            yazi::CompressionLevel::Default,
-        )
-        .unwrap();
-        std::fs::write(path, compressed).unwrap();
+        if bytes.starts_with(NATIVE_INDEX_MAGIC) {
+            let index = NativeIndex::from_bytes(&bytes)?;
+            return Ok(Self::from_backend(name, index));
+        }
 
-        myself
+        #[cfg(feature = "stork")]
+        {
+            let index = stork::StorkIndex::load(&name, bytes)
+                .map_err(|err| IndexError::Incompatible(err.to_string()))?;
+            Ok(Self::from_backend(name, index))
+        }
+
+        #[cfg(not(feature = "stork"))]
+        Err(IndexError::Incompatible(format!(
+            "{name} was not built with the native backend. Enable the `stork` feature to load stork indexes"
+        )))
     }
 
-    pub fn from_bytes<T: Into<Bytes>>(name: impl AsRef<str>, bytes: T) -> Self {
-        let name = name.as_ref().to_string();
-        let bytes = bytes.into();
-        stork_lib::register_index(&format!("index_{name}"), bytes.clone()).unwrap();
-        Self {
-            index: bytes,
-            name,
-            mock: false,
-            _marker: std::marker::PhantomData,
+    /// Load an index from the compressed bytes written by [`SearchIndex::create`]
+    pub fn from_compressed(name: impl AsRef<str>, bytes: &[u8]) -> Result<Self, IndexError> {
+        let name = name.as_ref();
+        if bytes.is_empty() {
+            return Err(IndexError::MissingIndex {
+                name: name.to_string(),
+            });
         }
+        Self::from_bytes(name, decompress_index(bytes)?)
     }
 
-    pub fn search(&self, text: &str) -> Result<Vec<SearchResult<R>>, SearchError> {
-        if self.mock {
+    /// Like [`SearchIndex::from_compressed`], but logs any error and falls back to an empty index
+    /// so a broken index disables search instead of crashing the page
+    pub fn from_compressed_or_default(name: impl AsRef<str>, bytes: &[u8]) -> Self {
+        Self::from_compressed(name, bytes).unwrap_or_else(|err| {
+            log::error!("{err}");
+            Self::default()
+        })
+    }
+
+    /// Search the index without parsing the urls of the matches into routes. This is useful for
+    /// tools that only need the urls, like a search API on a server
+    pub fn search_documents(
+        &self,
+        text: &str,
+        mode: SearchMode,
+    ) -> Result<Vec<DocumentMatch>, SearchError> {
+        let Some(backend) = &self.backend else {
             return Ok(Vec::new());
-        }
+        };
 
-        let id = &self.name;
-        let output = stork_lib::search_from_cache(&format!("index_{id}"), text)?;
-        let mut results = Vec::new();
-        for result in output.results {
-            match result.entry.url.parse() {
-                Ok(route) => {
-                    let excerpts = result
-                        .excerpts
-                        .into_iter()
-                        .map(|excerpt| {
-                            let mut segments = Vec::new();
-                            let mut char_index = 0;
-                            let mut chars = excerpt.text.chars();
-                            let mut current_segment = String::new();
-                            for highlight_range in excerpt.highlight_ranges {
-                                let start = highlight_range.beginning;
-                                while char_index < start.saturating_sub(1) {
-                                    if let Some(c) = chars.next() {
-                                        current_segment.push(c);
-                                        char_index += 1;
-                                    } else {
-                                        break;
-                                    }
-                                }
-                                // add the current segment as a plain text segment
-                                if !current_segment.is_empty() {
-                                    segments.push(Segment {
-                                        text: std::mem::take(&mut current_segment),
-                                        highlighted: false,
-                                    });
-                                }
-                                let end = highlight_range.end;
-                                while char_index < end {
-                                    if let Some(c) = chars.next() {
-                                        current_segment.push(c);
-                                        char_index += 1;
-                                    } else {
-                                        break;
-                                    }
-                                }
-                                // add the current segment as a highlighted segment
-                                if !current_segment.is_empty() {
-                                    segments.push(Segment {
-                                        text: std::mem::take(&mut current_segment),
-                                        highlighted: true,
-                                    });
-                                }
-                            }
-                            Excerpt {
-                                text: segments,
-                                score: excerpt.score,
-                            }
-                        })
-                        .collect();
-                    results.push(SearchResult {
-                        route,
-                        excerpts,
-                        title: result.entry.title,
-                        score: result.score,
-                    })
-                }
-                Err(err) => {
-                    log::error!("Failed to parse url ({}): {err}", result.entry.url);
-                }
-            }
+        match mode {
+            SearchMode::Pages => backend.search(text),
+            SearchMode::Code => backend.search_code(text),
         }
+    }
+
+    /// Complete the last word of a query with words from the index, most common first. Returns
+    /// the whole query with the last word completed
+    pub fn complete(&self, query: &str, limit: usize) -> Vec<String> {
+        self.backend
+            .as_ref()
+            .map(|backend| backend.complete(query, limit))
+            .unwrap_or_default()
+    }
+
+    /// Suggest a similar query for a query without results, like `signal` for `sginal`
+    pub fn did_you_mean(&self, query: &str) -> Option<String> {
+        self.backend.as_ref()?.did_you_mean(query)
+    }
+
+    /// Serialize the index so it can be loaded again with [`SearchIndex::from_bytes`]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.backend
+            .as_ref()
+            .map(|backend| backend.to_bytes())
+            .unwrap_or_default()
+    }
+
+    /// Write the compressed index where `load_search_index!` looks for it
+    fn write_to_manifest_dir(&self) -> Result<(), IndexError> {
+        let path = output_dir().join(format!("index_{}.bin", self.name));
+        write_compressed(&path, &self.to_bytes())
+    }
+}
+
+/// How a [`SearchIndex`] is built
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct IndexConfig {
+    /// How text is split into terms
+    pub tokenizer: TokenizerConfig,
+    /// How results are ranked
+    pub ranking: RankingConfig,
+    /// How the excerpts of results are cut
+    pub excerpts: ExcerptConfig,
+}
+
+/// The directory indexes are written to
+fn output_dir() -> PathBuf {
+    let target_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap_or_else(|_| ".".into());
+    PathBuf::from(target_dir).join("dioxus_search")
+}
+
+/// Compress a serialized index or shard and write it to the path, creating its directory. The
+/// file can be loaded with [`SearchIndex::from_compressed`]
+pub fn write_compressed(path: &std::path::Path, bytes: &[u8]) -> Result<(), IndexError> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|err| IndexError::io(parent, err))?;
+    }
+    let compressed = yazi::compress(bytes, yazi::Format::Zlib, yazi::CompressionLevel::Default)
+        .map_err(|err| {
+            IndexError::io(
+                path,
+                std::io::Error::other(format!("failed to compress the index: {err:?}")),
+            )
+        })?;
+    std::fs::write(path, compressed).map_err(|err| IndexError::io(path, err))
+}
+
+/// Decompress an index or shard written by [`SearchIndex::create`]
+pub fn decompress_index(bytes: &[u8]) -> Result<Vec<u8>, IndexError> {
+    let (bytes, _) = yazi::decompress(bytes, yazi::Format::Zlib)
+        .map_err(|err| DecodeError::new(format!("failed to decompress the index: {err:?}")))?;
+    Ok(bytes)
+}
 
-        results.sort_by_key(|result| !result.score);
+impl<R: Routable> SearchIndex<R>
+where
+    <R as FromStr>::Err: Display,
+{
+    /// Build a search index from the rendered HTML of every static route and write it to
+    /// `$CARGO_MANIFEST_DIR/dioxus_search/index_{name}.bin`, along with the shards a
+    /// [`ShardedIndex`] loads in `$CARGO_MANIFEST_DIR/dioxus_search/index_{name}/`
+    pub fn create(
+        name: impl AsRef<str>,
+        mapping: impl SearchIndexMapping<R>,
+    ) -> Result<Self, IndexError> {
+        Self::create_with_config(name, mapping, IndexConfig::default())
+    }
+
+    /// Like [`SearchIndex::create`], but with a custom tokenizer and ranking. Pages can override
+    /// the language of the tokenizer with the `lang` attribute of their `<html>` element, and
+    /// pages with shorter urls get more of the hierarchy boost
+    pub fn create_with_config(
+        name: impl AsRef<str>,
+        mapping: impl SearchIndexMapping<R>,
+        config: IndexConfig,
+    ) -> Result<Self, IndexError> {
+        let documents = html::documents_from_route(mapping);
+        let index = NativeIndex::with_tokenizer(documents, config.tokenizer);
+        Self::from_native(
+            name,
+            index
+                .with_ranking(config.ranking)
+                .with_excerpts(config.excerpts),
+        )
+    }
+
+    pub fn search(&self, text: &str) -> Result<Vec<SearchResult<R>>, SearchError> {
+        let matches = self.search_documents(text, SearchMode::Pages)?;
+        Ok(SearchResult::from_matches(matches))
+    }
 
-        Ok(results)
+    /// The popular searches from the [`RankingConfig`] the index was built with, in order. Show
+    /// these before anything is typed
+    pub fn popular_searches(&self) -> Vec<SearchResult<R>> {
+        let matches = self
+            .backend
+            .as_ref()
+            .map(|backend| backend.popular_searches())
+            .unwrap_or_default();
+        SearchResult::from_matches(matches)
+    }
+
+    /// Search the code blocks of every page instead of the pages themselves. Each result has the
+    /// [`CodeSource`] of the code block and an excerpt of the code with the matches highlighted
+    pub fn search_code(&self, text: &str) -> Result<Vec<SearchResult<R>>, SearchError> {
+        let matches = self.search_documents(text, SearchMode::Code)?;
+        Ok(SearchResult::from_matches(matches))
     }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct SearchResult<R: Routable> {
+    /// The route of the matched page, including the section if the match was under a heading
     pub route: R,
+    /// The id of the heading the matched section starts at, or `None` for the top of the page
+    pub anchor: Option<String>,
+    /// The titles of the page and headings the matched section is nested under
+    pub breadcrumb: Vec<String>,
+    /// The title of the page or the text of the matched section heading
     pub title: String,
     pub excerpts: Vec<Excerpt>,
+    /// The terms each word of the query matched, including any typos that were corrected
+    pub matches: Vec<TermMatch>,
     pub score: usize,
+    /// Where the code example came from for results of [`SearchIndex::search_code`]
+    pub example: Option<CodeSource>,
+}
+
+impl<R: Routable> SearchResult<R>
+where
+    <R as FromStr>::Err: Display,
+{
+    /// Parse the route of each match from a backend, best match first. Matches with urls that
+    /// aren't valid routes are logged and skipped
+    pub fn from_matches(matches: Vec<DocumentMatch>) -> Vec<Self> {
+        let mut results = Vec::new();
+        for result in matches {
+            let url = match &result.anchor {
+                Some(anchor) => format!("{}#{anchor}", result.url),
+                None => result.url.clone(),
+            };
+            match url.parse() {
+                Ok(route) => results.push(SearchResult {
+                    route,
+                    anchor: result.anchor,
+                    breadcrumb: result.breadcrumb,
+                    matches: result.matches,
+                    excerpts: result.excerpts,
+                    title: result.title,
+                    score: result.score,
+                    example: result.example,
+                }),
+                Err(err) => {
+                    log::error!("Failed to parse url ({url}): {err}");
+                }
+            }
+        }
+
+        results.sort_by_key(|result| std::cmp::Reverse(result.score));
+
+        results
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -183,124 +398,22 @@ pub struct Segment {
     pub highlighted: bool,
 }
 
-#[derive(Serialize, Deserialize)]
-#[serde(deny_unknown_fields)]
-pub struct Config {
-    pub input: InputConfig,
-}
-
-impl Config {
-    fn from_route<R: Routable>(mapping: impl SearchIndexMapping<R>) -> Self
-    where
-        <R as FromStr>::Err: Display,
-    {
-        let mut files = Vec::new();
-        let base_directory = mapping.base_directory();
-
-        // Collect all the static routes
-        let static_routes = R::static_routes();
-        // Add the routes to the index
-        for route in static_routes {
-            let url = route.to_string();
-            if let Some(path) = mapping.map_route(route) {
-                let path = &path.strip_prefix("/").unwrap_or(&path);
-                let absolute_path = base_directory.join(path);
-                log::trace!("Adding {:?} to search index", absolute_path);
-                match std::fs::read_to_string(&absolute_path) {
-                    Ok(contents) => {
-                        let document = Html::parse_document(&contents);
-                        let title = document
-                            .select(&Selector::parse("h1").unwrap())
-                            .next()
-                            .map(|title| title.text().collect::<String>())
-                            .unwrap_or_else(|| {
-                                document
-                                    .select(&Selector::parse("title").unwrap())
-                                    .next()
-                                    .map(|title| title.text().collect::<String>())
-                                    .unwrap_or_else(|| {
-                                        let mut title = String::new();
-                                        for segment in path.iter() {
-                                            title.push_str(&segment.to_string_lossy());
-                                            title.push(' ');
-                                        }
-                                        title
-                                    })
-                            });
-                        files.push(File {
-                            path: path.to_string_lossy().into(),
-                            url,
-                            title,
-                            fields: HashMap::new(),
-                            explicit_source: None,
-                        })
-                    }
-                    Err(err) => {
-                        log::error!("Error reading file: {:?}: {}", absolute_path, err);
-                    }
-                }
-            }
-        }
-
-        Self {
-            input: InputConfig {
-                base_directory: base_directory.to_string_lossy().into(),
-                url_prefix: "".into(),
-                html_selector: Some("#main".into()),
-                files,
-                break_on_file_error: false,
-                minimum_indexed_substring_length: 3,
-                minimum_index_ideographic_substring_length: 1,
-            },
-        }
-    }
-}
-
-#[derive(Serialize, Deserialize)]
-#[serde(deny_unknown_fields)]
-pub struct InputConfig {
-    base_directory: String,
-    url_prefix: String,
-    html_selector: Option<String>,
-    files: Vec<File>,
-    break_on_file_error: bool,
-    minimum_indexed_substring_length: u8,
-    minimum_index_ideographic_substring_length: u8,
-}
-
-#[derive(Deserialize, Serialize)]
-#[serde(deny_unknown_fields)]
-struct File {
-    path: String,
-    url: String,
-    title: String,
-    #[serde(flatten, default)]
-    pub fields: HashMap<String, String>,
-    #[serde(flatten)]
-    pub explicit_source: Option<DataSource>,
-}
-
-#[derive(Serialize, Deserialize)]
-pub enum DataSource {
-    #[serde(rename = "contents")]
-    Contents(String),
-
-    #[serde(rename = "src_url")]
-    #[allow(clippy::upper_case_acronyms)]
-    URL(String),
-
-    #[serde(rename = "path")]
-    FilePath(String),
-}
-
 pub trait SearchIndexMapping<R: Routable> {
     fn base_directory(&self) -> PathBuf;
     fn map_route(&self, route: R) -> Option<PathBuf>;
+
+    /// Documents for content that doesn't have a rendered page of its own, like the items of a
+    /// list loaded from JSON. They are added to the index as they are, after the pages of the
+    /// static routes
+    fn documents(&self) -> Vec<Document> {
+        Vec::new()
+    }
 }
 
 pub struct Mapped<F: Fn(R) -> Option<PathBuf>, R: Routable> {
     base_directory: PathBuf,
     map: F,
+    documents: Vec<Document>,
     _marker: std::marker::PhantomData<R>,
 }
 
@@ -312,10 +425,23 @@ impl<F: Fn(R) -> Option<PathBuf>, R: Routable> SearchIndexMapping<R> for Mapped<
     fn map_route(&self, route: R) -> Option<PathBuf> {
         (self.map)(route)
     }
+
+    fn documents(&self) -> Vec<Document> {
+        self.documents.clone()
+    }
+}
+
+impl<F: Fn(R) -> Option<PathBuf>, R: Routable> Mapped<F, R> {
+    /// Add documents that don't have a rendered page. See [`SearchIndexMapping::documents`]
+    pub fn with_documents(mut self, documents: impl IntoIterator<Item = Document>) -> Self {
+        self.documents.extend(documents);
+        self
+    }
 }
 
 pub struct BaseDirectoryMapping {
     base_directory: PathBuf,
+    documents: Vec<Document>,
 }
 
 impl<R: Routable> SearchIndexMapping<R> for BaseDirectoryMapping {
@@ -323,10 +449,14 @@ impl<R: Routable> SearchIndexMapping<R> for BaseDirectoryMapping {
         self.base_directory.clone()
     }
 
+    fn documents(&self) -> Vec<Document> {
+        self.documents.clone()
+    }
+
     fn map_route(&self, route: R) -> Option<PathBuf> {
         let route = route.to_string();
         let (route, _) = route.split_once('#').unwrap_or((&route, ""));
-        let (route, _) = route.split_once('?').unwrap_or((&route, ""));
+        let (route, _) = route.split_once('?').unwrap_or((route, ""));
         let route = PathBuf::from(route).join("index.html");
         Some(route)
     }
@@ -336,6 +466,7 @@ impl BaseDirectoryMapping {
     pub fn new(base_directory: impl Into<PathBuf>) -> Self {
         Self {
             base_directory: base_directory.into(),
+            documents: Vec::new(),
         }
     }
 
@@ -343,9 +474,16 @@ impl BaseDirectoryMapping {
         Mapped {
             base_directory: self.base_directory,
             map,
+            documents: self.documents,
             _marker: std::marker::PhantomData,
         }
     }
+
+    /// Add documents that don't have a rendered page. See [`SearchIndexMapping::documents`]
+    pub fn with_documents(mut self, documents: impl IntoIterator<Item = Document>) -> Self {
+        self.documents.extend(documents);
+        self
+    }
 }
 
 impl From<PathBuf> for BaseDirectoryMapping {
@@ -353,3 +491,21 @@ impl From<PathBuf> for BaseDirectoryMapping {
         Self::new(base_directory)
     }
 }
+
+#[test]
+fn loading_broken_indexes() {
+    let missing = SearchIndex::<()>::from_compressed("missing", &[]);
+    assert!(matches!(missing, Err(IndexError::MissingIndex { name }) if name == "missing"));
+
+    let corrupt = SearchIndex::<()>::from_compressed("corrupt", b"not zlib");
+    assert!(matches!(corrupt, Err(IndexError::Corrupt(_))));
+
+    let mut truncated = NativeIndex::default().to_bytes();
+    truncated.pop();
+    let truncated = SearchIndex::<()>::from_bytes("truncated", truncated);
+    assert!(matches!(truncated, Err(IndexError::Corrupt(_))));
+
+    // A broken index falls back to an index without results instead of panicking
+    let fallback = SearchIndex::<()>::from_compressed_or_default("corrupt", b"not zlib");
+    assert!(fallback.to_bytes().is_empty());
+}
@@ -0,0 +1,154 @@
+//! A small binary encoding for native search indexes. Integers are LEB128 varints and
+//! strings are length prefixed UTF-8.
+
+use std::fmt::Display;
+
+/// An error decoding a native search index
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodeError {
+    message: String,
+}
+
+impl DecodeError {
+    pub(crate) fn new(message: impl Display) -> Self {
+        Self {
+            message: message.to_string(),
+        }
+    }
+}
+
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Invalid search index: {}", self.message)
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+#[derive(Default)]
+pub(crate) struct Encoder {
+    bytes: Vec<u8>,
+}
+
+impl Encoder {
+    pub fn finish(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    pub fn write_raw(&mut self, bytes: &[u8]) {
+        self.bytes.extend_from_slice(bytes);
+    }
+
+    pub fn write_u64(&mut self, mut value: u64) {
+        loop {
+            let byte = (value & 0x7f) as u8;
+            value >>= 7;
+            if value == 0 {
+                self.bytes.push(byte);
+                return;
+            }
+            self.bytes.push(byte | 0x80);
+        }
+    }
+
+    pub fn write_u32(&mut self, value: u32) {
+        self.write_u64(value as u64);
+    }
+
+    pub fn write_len(&mut self, len: usize) {
+        self.write_u64(len as u64);
+    }
+
+    pub fn write_f32(&mut self, value: f32) {
+        self.write_raw(&value.to_le_bytes());
+    }
+
+    pub fn write_str(&mut self, value: &str) {
+        self.write_len(value.len());
+        self.write_raw(value.as_bytes());
+    }
+}
+
+pub(crate) struct Decoder<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Decoder<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    pub fn read_raw(&mut self, len: usize) -> Result<&'a [u8], DecodeError> {
+        if self.bytes.len() < len {
+            return Err(DecodeError::new("unexpected end of data"));
+        }
+        let (raw, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(raw)
+    }
+
+    pub fn read_u64(&mut self) -> Result<u64, DecodeError> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.read_raw(1)?[0];
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(DecodeError::new("varint is too long"))
+    }
+
+    pub fn read_u32(&mut self) -> Result<u32, DecodeError> {
+        u32::try_from(self.read_u64()?).map_err(DecodeError::new)
+    }
+
+    /// Read a number that isn't the length of data that follows it, like an offset into data
+    /// that was already read
+    pub fn read_usize(&mut self) -> Result<usize, DecodeError> {
+        usize::try_from(self.read_u64()?).map_err(DecodeError::new)
+    }
+
+    pub fn read_len(&mut self) -> Result<usize, DecodeError> {
+        let len = usize::try_from(self.read_u64()?).map_err(DecodeError::new)?;
+        // Every item takes at least one byte, so a length longer than the remaining data is corrupt
+        if len > self.bytes.len() {
+            return Err(DecodeError::new("length is longer than the remaining data"));
+        }
+        Ok(len)
+    }
+
+    pub fn read_f32(&mut self) -> Result<f32, DecodeError> {
+        let raw = self.read_raw(4)?;
+        Ok(f32::from_le_bytes([raw[0], raw[1], raw[2], raw[3]]))
+    }
+
+    pub fn read_str(&mut self) -> Result<&'a str, DecodeError> {
+        let len = self.read_len()?;
+        std::str::from_utf8(self.read_raw(len)?).map_err(DecodeError::new)
+    }
+}
+
+#[test]
+fn round_trip() {
+    let mut encoder = Encoder::default();
+    encoder.write_u64(0);
+    encoder.write_u64(300);
+    encoder.write_u64(u64::MAX);
+    encoder.write_str("héllo");
+    encoder.write_f32(1.5);
+    let bytes = encoder.finish();
+
+    let mut decoder = Decoder::new(&bytes);
+    assert_eq!(decoder.read_u64(), Ok(0));
+    assert_eq!(decoder.read_u64(), Ok(300));
+    assert_eq!(decoder.read_u64(), Ok(u64::MAX));
+    assert_eq!(decoder.read_str(), Ok("héllo"));
+    assert_eq!(decoder.read_f32(), Ok(1.5));
+    assert!(decoder.is_empty());
+    assert!(decoder.read_u64().is_err());
+}
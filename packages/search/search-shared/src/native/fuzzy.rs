@@ -0,0 +1,57 @@
+//! Bounded edit distance for typo tolerant matching
+
+/// The number of typos a query word of this many characters can have and still match a term.
+/// Short words match too many unrelated terms with any typos
+pub(crate) fn max_typos(len: usize) -> usize {
+    match len {
+        0..=3 => 0,
+        4..=7 => 1,
+        _ => 2,
+    }
+}
+
+/// The optimal string alignment distance between two words: the number of insertions,
+/// deletions, substitutions and swaps of adjacent characters to turn one into the other.
+/// Returns `None` if the distance is more than `max`
+pub(crate) fn edit_distance(a: &str, b: &str, max: usize) -> Option<usize> {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    if a.len().abs_diff(b.len()) > max {
+        return None;
+    }
+
+    // Only the last two rows are needed for swaps
+    let mut previous_previous: Vec<usize> = Vec::new();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        let mut current = vec![i; b.len() + 1];
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            current[j] = (previous[j] + 1)
+                .min(current[j - 1] + 1)
+                .min(previous[j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                current[j] = current[j].min(previous_previous[j - 2] + 1);
+            }
+        }
+        // Every path through this row costs at least its minimum
+        if current.iter().min().is_some_and(|min| *min > max) {
+            return None;
+        }
+        previous_previous = std::mem::replace(&mut previous, current);
+    }
+
+    let distance = previous[b.len()];
+    (distance <= max).then_some(distance)
+}
+
+#[test]
+fn bounded_edit_distance() {
+    assert_eq!(edit_distance("signal", "signal", 1), Some(0));
+    assert_eq!(edit_distance("singal", "signal", 1), Some(1));
+    assert_eq!(edit_distance("use_resorce", "use_resource", 2), Some(1));
+    assert_eq!(edit_distance("sigal", "signal", 1), Some(1));
+    assert_eq!(edit_distance("router", "signal", 2), None);
+    assert_eq!(edit_distance("sig", "signals", 2), None);
+    assert_eq!(edit_distance("héllo", "hello", 1), Some(1));
+}
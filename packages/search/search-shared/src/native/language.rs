@@ -0,0 +1,156 @@
+use std::borrow::Cow;
+
+use rust_stemmers::{Algorithm, Stemmer};
+
+/// The language of an index or page. The language picks the stemmer and stop words used to index
+/// and search its text
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Language {
+    #[default]
+    English,
+    Portuguese,
+    /// Any other language. Words are indexed without stemming or stop words
+    Other,
+}
+
+impl Language {
+    /// Parse a language tag like `en`, `pt-BR` or `pt_PT`. Languages without a stemmer are
+    /// [`Language::Other`]
+    pub fn from_code(code: &str) -> Self {
+        let primary = code.trim().split(['-', '_']).next().unwrap_or_default();
+        match primary.to_ascii_lowercase().as_str() {
+            "en" | "english" => Language::English,
+            "pt" | "portuguese" => Language::Portuguese,
+            _ => Language::Other,
+        }
+    }
+
+    /// The primary language subtag, or an empty string for [`Language::Other`]
+    pub fn code(self) -> &'static str {
+        match self {
+            Language::English => "en",
+            Language::Portuguese => "pt",
+            Language::Other => "",
+        }
+    }
+
+    fn stemmer(self) -> Option<Stemmer> {
+        match self {
+            Language::English => Some(Stemmer::create(Algorithm::English)),
+            Language::Portuguese => Some(Stemmer::create(Algorithm::Portuguese)),
+            Language::Other => None,
+        }
+    }
+
+    fn stop_words(self) -> &'static [&'static str] {
+        match self {
+            Language::English => &[
+                "a", "an", "and", "are", "as", "at", "be", "but", "by", "for", "if", "in", "into",
+                "is", "it", "no", "not", "of", "on", "or", "such", "that", "the", "their", "then",
+                "there", "these", "they", "this", "to", "was", "will", "with",
+            ],
+            Language::Portuguese => &[
+                "a", "ao", "aos", "as", "com", "como", "da", "das", "de", "do", "dos", "e", "é",
+                "em", "na", "nas", "no", "nos", "o", "os", "ou", "para", "pela", "pelas", "pelo",
+                "pelos", "por", "que", "se", "um", "uma", "umas", "uns",
+            ],
+            Language::Other => &[],
+        }
+    }
+}
+
+/// How text is turned into the terms of a native index.
+///
+/// Text is split into lowercase words, and runs of Chinese, Japanese or Korean characters are
+/// split into overlapping pairs of characters since they aren't separated by spaces. Common words
+/// are then dropped and the rest are reduced to their stem in the language of the page they came
+/// from, so `signals` finds `signal`.
+///
+/// The configuration is stored in the index, so queries are tokenized the same way when the index
+/// is loaded in the browser.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TokenizerConfig {
+    /// The language of pages that don't set their own language
+    pub language: Language,
+    /// Index the stem of each word instead of the word itself
+    pub stemming: bool,
+    /// Skip common words like `the` outside of code
+    pub stop_words: bool,
+}
+
+impl Default for TokenizerConfig {
+    fn default() -> Self {
+        Self {
+            language: Language::English,
+            stemming: true,
+            stop_words: true,
+        }
+    }
+}
+
+impl TokenizerConfig {
+    /// A tokenizer for text in the language with stemming and stop words
+    pub fn new(language: Language) -> Self {
+        Self {
+            language,
+            ..Default::default()
+        }
+    }
+
+    /// Returns true if a lowercase word is too common to index in the language
+    pub(crate) fn is_stop_word(&self, language: Language, word: &str) -> bool {
+        self.stop_words && language.stop_words().contains(&word)
+    }
+
+    /// The stem of a lowercase word in the language. Identifiers and words with digits are kept
+    /// whole
+    pub(crate) fn stem<'a>(&self, language: Language, word: &'a str) -> Cow<'a, str> {
+        if !self.stemming || !word.chars().all(char::is_alphabetic) {
+            return Cow::Borrowed(word);
+        }
+        match language.stemmer() {
+            Some(stemmer) => stemmer.stem(word),
+            None => Cow::Borrowed(word),
+        }
+    }
+}
+
+#[test]
+fn stem_and_stop_words() {
+    let tokenizer = TokenizerConfig::default();
+    assert_eq!(tokenizer.stem(Language::English, "signals"), "signal");
+    assert_eq!(tokenizer.stem(Language::English, "restarting"), "restart");
+    assert_eq!(
+        tokenizer.stem(Language::Portuguese, "componentes"),
+        tokenizer.stem(Language::Portuguese, "componente")
+    );
+    assert_eq!(tokenizer.stem(Language::Other, "signals"), "signals");
+    // Identifiers are left alone
+    assert_eq!(
+        tokenizer.stem(Language::English, "use_signals"),
+        "use_signals"
+    );
+
+    assert!(tokenizer.is_stop_word(Language::English, "the"));
+    assert!(tokenizer.is_stop_word(Language::Portuguese, "para"));
+    assert!(!tokenizer.is_stop_word(Language::English, "para"));
+
+    let tokenizer = TokenizerConfig {
+        stemming: false,
+        stop_words: false,
+        ..Default::default()
+    };
+    assert_eq!(tokenizer.stem(Language::English, "signals"), "signals");
+    assert!(!tokenizer.is_stop_word(Language::English, "the"));
+}
+
+#[test]
+fn language_codes() {
+    assert_eq!(Language::from_code("en-US"), Language::English);
+    assert_eq!(Language::from_code("pt_BR"), Language::Portuguese);
+    assert_eq!(Language::from_code("PT"), Language::Portuguese);
+    assert_eq!(Language::from_code("zh-Hans"), Language::Other);
+    for language in [Language::English, Language::Portuguese, Language::Other] {
+        assert_eq!(Language::from_code(language.code()), language);
+    }
+}
@@ -0,0 +1,1389 @@
+//! A dependency-light search engine: an inverted index scored with BM25 that supports
+//! prefix matching and generates highlighted excerpts.
+
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, HashMap},
+};
+
+use crate::{
+    excerpt::{excerpts, merge_highlights, segments},
+    Boosts, CodeSource, DocumentMatch, Excerpt, ExcerptConfig, IndexError, MatchKind, PinnedResult,
+    PopularSearch, RankingConfig, SearchBackend, SearchError, TermMatch,
+};
+
+mod encode;
+mod fuzzy;
+mod language;
+mod query;
+mod shard;
+mod suggest;
+mod tokenize;
+
+pub use encode::DecodeError;
+use encode::{Decoder, Encoder};
+pub use language::{Language, TokenizerConfig};
+pub use query::{Clause, Field, Query, SearchMode};
+pub use shard::{ShardedIndex, DICTIONARY_SHARD};
+use tokenize::tokenize;
+
+/// The bytes every serialized native index starts with
+pub const NATIVE_INDEX_MAGIC: &[u8; 4] = b"DXSI";
+/// The version of the native index format, stored after the magic bytes. Bump this whenever the
+/// format changes so old indexes fail to load with [`IndexError::VersionMismatch`] instead of
+/// being misread
+pub const NATIVE_INDEX_VERSION: u32 = 4;
+
+/// BM25 term frequency saturation
+const K1: f32 = 1.2;
+/// BM25 document length normalization
+const B: f32 = 0.75;
+/// How much of the score a prefix match keeps compared to an exact match
+const PREFIX_WEIGHT: f32 = 0.5;
+/// How much of the score a fuzzy match keeps for each typo
+const FUZZY_WEIGHT: f32 = 0.4;
+/// The maximum number of index terms a single query term can expand to
+const MAX_EXPANSIONS: usize = 32;
+/// The number of lines of a code example shown in an excerpt
+const EXAMPLE_LINES: usize = 12;
+/// The number of lines to show before the first match in a code example
+const EXAMPLE_CONTEXT_LINES: usize = 2;
+
+/// A page, or a section of a page, to add to a [`NativeIndex`]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Document {
+    /// The url of the page. This is parsed into the route of a search result
+    pub url: String,
+    /// The id of the heading the section starts at, or `None` for the top of the page
+    pub anchor: Option<String>,
+    /// The titles of the page and headings the section is nested under
+    pub breadcrumb: Vec<String>,
+    /// The title of the page or the text of the section heading
+    pub title: String,
+    /// The plain text contents of the document
+    pub body: String,
+    /// The code in the document. This is also part of the body, but it can be searched on its own
+    /// with `code:`
+    pub code: String,
+    /// Extra words the document should be found by that are not part of its contents
+    pub keywords: Vec<String>,
+    /// The language of the document, or `None` for the language of the index
+    pub language: Option<Language>,
+    /// How deeply the page is nested in the table of contents. Top level pages are `0`
+    pub depth: u32,
+    /// The fenced code blocks in the document. Each one is indexed on its own so it can be found
+    /// with [`SearchMode::Code`]
+    pub examples: Vec<CodeExample>,
+}
+
+/// A fenced code block in a [`Document`]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CodeExample {
+    pub source: CodeSource,
+    pub code: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct StoredDocument {
+    url: String,
+    anchor: Option<String>,
+    breadcrumb: Vec<String>,
+    title: String,
+    body: String,
+    /// The number of tokens in the title and body
+    length: u32,
+    language: Language,
+    depth: u32,
+    /// The source of the code block if the document is a code example instead of a section
+    example: Option<CodeSource>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Term {
+    text: String,
+    /// The word the term is most often spelled as in the documents. Stems aren't always words, so
+    /// completions and suggestions show this instead
+    word: String,
+    /// The number of documents the term appears in. This is known even if the postings of a
+    /// sharded index haven't been loaded yet
+    document_frequency: u32,
+    /// How many of those documents are code examples
+    example_frequency: u32,
+    /// The documents the term appears in, sorted by document
+    postings: Vec<Posting>,
+}
+
+impl Term {
+    fn new(
+        text: String,
+        word: String,
+        postings: Vec<Posting>,
+        documents: &[StoredDocument],
+    ) -> Self {
+        let example_frequency = postings
+            .iter()
+            .filter(|posting| documents[posting.document as usize].example.is_some())
+            .count();
+        Self {
+            text,
+            word,
+            document_frequency: postings.len() as u32,
+            example_frequency: example_frequency as u32,
+            postings,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Posting {
+    document: u32,
+    frequency: u32,
+    /// The fields of the document the term appears in
+    fields: u32,
+}
+
+const TITLE_FIELD: u32 = 1 << 0;
+const BODY_FIELD: u32 = 1 << 1;
+const CODE_FIELD: u32 = 1 << 2;
+const KEYWORDS_FIELD: u32 = 1 << 3;
+
+impl Field {
+    fn mask(self) -> u32 {
+        match self {
+            Field::Any => TITLE_FIELD | BODY_FIELD | CODE_FIELD | KEYWORDS_FIELD,
+            Field::Title => TITLE_FIELD,
+            Field::Code => CODE_FIELD,
+        }
+    }
+}
+
+/// A document that matched part of a query
+#[derive(Default)]
+struct Candidate {
+    score: f32,
+    matches: Vec<TermMatch>,
+}
+
+/// An in-memory inverted index
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NativeIndex {
+    documents: Vec<StoredDocument>,
+    /// Each term and the documents it appears in, sorted by term
+    terms: Vec<Term>,
+    tokenizer: TokenizerConfig,
+    ranking: RankingConfig,
+    excerpts: ExcerptConfig,
+    /// Every language used by a document in the index
+    languages: Vec<Language>,
+    /// The number of documents that are code examples
+    example_count: usize,
+    /// The average length of pages and code examples. Code examples are scored separately from
+    /// pages so they don't change the ranking of pages
+    average_length: f32,
+    example_average_length: f32,
+    /// The title and document of each popular search in the ranking
+    popular: Vec<(String, u32)>,
+}
+
+impl NativeIndex {
+    /// Build an index from a list of documents with the default English tokenizer
+    pub fn new(documents: impl IntoIterator<Item = Document>) -> Self {
+        Self::with_tokenizer(documents, TokenizerConfig::default())
+    }
+
+    /// Build an index from a list of documents, splitting their text into terms with the
+    /// tokenizer
+    pub fn with_tokenizer(
+        documents: impl IntoIterator<Item = Document>,
+        tokenizer: TokenizerConfig,
+    ) -> Self {
+        let mut stored = Vec::new();
+        let mut postings: BTreeMap<String, Vec<Posting>> = BTreeMap::new();
+        // How often each term is spelled as each word
+        let mut spellings: HashMap<String, HashMap<String, u32>> = HashMap::new();
+        let mut spell = |term: &str, word: String| {
+            *spellings
+                .entry(term.to_string())
+                .or_default()
+                .entry(word)
+                .or_default() += 1;
+        };
+
+        let mut add = |document: StoredDocument, frequencies: HashMap<String, (u32, u32)>| {
+            let id = stored.len() as u32;
+            for (term, (frequency, fields)) in frequencies {
+                postings.entry(term).or_default().push(Posting {
+                    document: id,
+                    frequency,
+                    fields,
+                });
+            }
+            stored.push(document);
+        };
+
+        for document in documents {
+            let language = document.language.unwrap_or(tokenizer.language);
+            let mut frequencies: HashMap<String, (u32, u32)> = HashMap::new();
+            let mut length = 0;
+            let texts = [(&document.title, TITLE_FIELD), (&document.body, BODY_FIELD)]
+                .into_iter()
+                .chain(
+                    document
+                        .keywords
+                        .iter()
+                        .map(|keyword| (keyword, KEYWORDS_FIELD)),
+                );
+            for (text, field) in texts {
+                for token in tokenize(text, true) {
+                    if tokenizer.is_stop_word(language, &token.text) {
+                        continue;
+                    }
+                    let term = tokenizer.stem(language, &token.text).into_owned();
+                    spell(&term, token.text);
+                    let (frequency, fields) = frequencies.entry(term).or_default();
+                    *frequency += 1;
+                    *fields |= field;
+                    length += 1;
+                }
+            }
+            // The code is already counted as part of the body, but keywords like `for` that are
+            // stop words in prose are still indexed in code
+            for token in tokenize(&document.code, true) {
+                let term = tokenizer.stem(language, &token.text).into_owned();
+                spell(&term, token.text);
+                let (frequency, fields) = frequencies.entry(term).or_default();
+                *frequency = (*frequency).max(1);
+                *fields |= CODE_FIELD;
+            }
+
+            // Code examples link to the section they are in
+            let examples: Vec<_> = document
+                .examples
+                .into_iter()
+                .map(|example| {
+                    let mut frequencies: HashMap<String, (u32, u32)> = HashMap::new();
+                    let mut length = 0;
+                    for token in tokenize(&example.code, true) {
+                        let term = tokenizer.stem(language, &token.text).into_owned();
+                        spell(&term, token.text);
+                        let (frequency, fields) = frequencies.entry(term).or_default();
+                        *frequency += 1;
+                        *fields |= CODE_FIELD;
+                        length += 1;
+                    }
+                    let stored = StoredDocument {
+                        url: document.url.clone(),
+                        anchor: document.anchor.clone(),
+                        breadcrumb: document.breadcrumb.clone(),
+                        title: document.title.clone(),
+                        body: example.code,
+                        length,
+                        language,
+                        depth: document.depth,
+                        example: Some(example.source),
+                    };
+                    (stored, frequencies)
+                })
+                .collect();
+
+            add(
+                StoredDocument {
+                    url: document.url,
+                    anchor: document.anchor,
+                    breadcrumb: document.breadcrumb,
+                    title: document.title,
+                    body: document.body,
+                    length,
+                    language,
+                    depth: document.depth,
+                    example: None,
+                },
+                frequencies,
+            );
+            for (example, frequencies) in examples {
+                add(example, frequencies);
+            }
+        }
+
+        let terms = postings
+            .into_iter()
+            .map(|(text, mut postings)| {
+                postings.sort_by_key(|posting| posting.document);
+                // Use the most common spelling, or the shortest if several are as common
+                let word = spellings
+                    .remove(&text)
+                    .and_then(|words| {
+                        words
+                            .into_iter()
+                            .min_by(|(a, a_count), (b, b_count)| {
+                                b_count
+                                    .cmp(a_count)
+                                    .then(a.len().cmp(&b.len()))
+                                    .then(a.cmp(b))
+                            })
+                            .map(|(word, _)| word)
+                    })
+                    .unwrap_or_else(|| text.clone());
+                Term::new(text, word, postings, &stored)
+            })
+            .collect();
+
+        Self::from_parts(
+            stored,
+            terms,
+            tokenizer,
+            RankingConfig::default(),
+            ExcerptConfig::default(),
+        )
+    }
+
+    /// Rank the results of the index with custom boosts and pinned results. The pinned results and
+    /// popular searches in the ranking are looked up in the index right away, and pinned results
+    /// for pages the index doesn't have are dropped
+    pub fn with_ranking(mut self, mut ranking: RankingConfig) -> Self {
+        ranking.pinned.retain(|pin| {
+            let found = self.pinned_document(pin).is_some();
+            if !found {
+                log::warn!(
+                    "The result pinned for {:?} does not exist: {}",
+                    pin.query,
+                    pin.url
+                );
+            }
+            found
+        });
+        self.ranking = ranking;
+        self.popular = self.find_popular();
+        self
+    }
+
+    /// Cut the excerpts of the results with a custom context length and number of excerpts
+    pub fn with_excerpts(mut self, excerpts: ExcerptConfig) -> Self {
+        self.excerpts = excerpts;
+        self
+    }
+
+    fn from_parts(
+        documents: Vec<StoredDocument>,
+        terms: Vec<Term>,
+        tokenizer: TokenizerConfig,
+        ranking: RankingConfig,
+        excerpts: ExcerptConfig,
+    ) -> Self {
+        let mut languages = Vec::new();
+        for document in &documents {
+            if !languages.contains(&document.language) {
+                languages.push(document.language);
+            }
+        }
+
+        let average = |examples: bool| {
+            let lengths: Vec<_> = documents
+                .iter()
+                .filter(|document| document.example.is_some() == examples)
+                .map(|document| document.length as f32)
+                .collect();
+            lengths.iter().sum::<f32>() / lengths.len().max(1) as f32
+        };
+        let average_length = average(false);
+        let example_average_length = average(true);
+        let example_count = documents
+            .iter()
+            .filter(|document| document.example.is_some())
+            .count();
+
+        Self {
+            documents,
+            terms,
+            tokenizer,
+            ranking,
+            excerpts,
+            languages,
+            example_count,
+            average_length,
+            example_average_length,
+            popular: Vec::new(),
+        }
+    }
+
+    /// The number of documents in the index
+    pub fn len(&self) -> usize {
+        self.documents.len()
+    }
+
+    /// Returns true if the index doesn't contain any documents
+    pub fn is_empty(&self) -> bool {
+        self.documents.is_empty()
+    }
+
+    /// Parse a query and find every document that matches it, best match first
+    pub fn query(&self, query: &str) -> Vec<DocumentMatch> {
+        self.search_query(&Query::parse(query))
+    }
+
+    /// Parse a query and find every code example that matches it, best match first
+    pub fn query_code(&self, query: &str) -> Vec<DocumentMatch> {
+        self.search_query(&Query::parse(query).in_mode(SearchMode::Code))
+    }
+
+    /// Find every document that matches all of the clauses in the query and none of the excluded
+    /// clauses, best match first
+    pub fn search_query(&self, query: &Query) -> Vec<DocumentMatch> {
+        self.ranked_documents(query, true)
+            .into_iter()
+            .map(|(id, candidate)| {
+                let document = &self.documents[id as usize];
+                let terms: Vec<&str> = candidate
+                    .matches
+                    .iter()
+                    .map(|term_match| term_match.term.as_str())
+                    .collect();
+                let highlights =
+                    highlights(&document.body, &terms, &self.tokenizer, document.language);
+                let excerpts = match document.example {
+                    Some(_) => vec![code_excerpt(&document.body, &highlights)],
+                    None => excerpts(&document.body, &highlights, &self.excerpts),
+                };
+
+                // Stems aren't always words, so show corrections the way the document spells them
+                let mut matches = candidate.matches;
+                for term_match in matches.iter_mut().filter(|term| term.is_correction()) {
+                    if let Some(word) = self.spelling(document, &term_match.term) {
+                        term_match.term = word;
+                    }
+                }
+
+                DocumentMatch {
+                    url: document.url.clone(),
+                    anchor: document.anchor.clone(),
+                    breadcrumb: document.breadcrumb.clone(),
+                    title: document.title.clone(),
+                    excerpts,
+                    matches,
+                    score: (candidate.score * 100.0).round() as usize,
+                    example: document.example.clone(),
+                }
+            })
+            .collect()
+    }
+
+    /// Find the documents that match the query, best match first, with any documents pinned for
+    /// the query above them
+    fn ranked_documents(&self, query: &Query, check_phrases: bool) -> Vec<(u32, Candidate)> {
+        let mut candidates = self.matching_documents(query, check_phrases);
+
+        // Pinned documents are shown first even if they don't match the query
+        let mut pinned: Vec<_> = self
+            .pinned_documents(query)
+            .into_iter()
+            .map(|id| (id, candidates.remove(&id).unwrap_or_default()))
+            .collect();
+
+        let mut results: Vec<_> = candidates.into_iter().collect();
+        results.sort_by(|(a_id, a), (b_id, b)| b.score.total_cmp(&a.score).then(a_id.cmp(b_id)));
+
+        // Keep the scores of pinned documents above every other result, in the order they were
+        // pinned
+        let top_score = results.first().map_or(0.0, |(_, best)| best.score);
+        let pinned_count = pinned.len();
+        for (rank, (_, candidate)) in pinned.iter_mut().enumerate() {
+            candidate.score = top_score + (pinned_count - rank) as f32;
+        }
+        pinned.extend(results);
+        pinned
+    }
+
+    /// Find the documents that match the query. Phrases need the body of each document, so they
+    /// are only checked if `check_phrases` is true
+    fn matching_documents(&self, query: &Query, check_phrases: bool) -> HashMap<u32, Candidate> {
+        // Each language has its own stems and stop words, so the documents in each language are
+        // matched separately
+        let mut candidates = HashMap::new();
+        for language in &self.languages {
+            candidates.extend(self.matching_documents_in(query, *language, check_phrases));
+        }
+        candidates
+    }
+
+    fn matching_documents_in(
+        &self,
+        query: &Query,
+        language: Language,
+        check_phrases: bool,
+    ) -> HashMap<u32, Candidate> {
+        let mut candidates: Option<HashMap<u32, Candidate>> = None;
+        for clause in query.clauses.iter().filter(|clause| !clause.exclude) {
+            let Some(matches) =
+                self.match_clause(clause, query.mode, language, true, check_phrases)
+            else {
+                continue;
+            };
+            candidates = Some(match candidates {
+                None => matches,
+                Some(candidates) => intersect(candidates, matches),
+            });
+        }
+        let mut candidates = candidates.unwrap_or_default();
+
+        for clause in query.clauses.iter().filter(|clause| clause.exclude) {
+            let matches = self.match_clause(clause, query.mode, language, false, check_phrases);
+            for document in matches.unwrap_or_default().keys() {
+                candidates.remove(document);
+            }
+        }
+
+        candidates
+    }
+
+    /// Find the documents of the mode in the language that contain every word in the clause.
+    /// Returns `None` if the clause only has stop words, which match every document
+    fn match_clause(
+        &self,
+        clause: &Clause,
+        mode: SearchMode,
+        language: Language,
+        allow_typos: bool,
+        check_phrases: bool,
+    ) -> Option<HashMap<u32, Candidate>> {
+        let mask = clause.field.mask();
+        let mut candidates: Option<HashMap<u32, Candidate>> = None;
+
+        for word in self.searched_words(clause, mode, language) {
+            // Only the last word of a phrase can be a prefix, like the word being typed
+            let prefix = !clause.is_phrase() || Some(word) == clause.words.last();
+            let mut matches: HashMap<u32, Candidate> = HashMap::new();
+            for (term, kind) in self.expand(word, language, prefix, allow_typos) {
+                let weight = match kind {
+                    MatchKind::Exact => 1.0,
+                    MatchKind::Prefix => {
+                        let typed = shared_prefix_len(word, &term.text);
+                        PREFIX_WEIGHT * typed as f32 / term.text.len() as f32
+                    }
+                    MatchKind::Fuzzy { typos } => FUZZY_WEIGHT.powi(typos as i32),
+                };
+                let idf = self.idf(term, mode);
+                let postings = term.postings.iter().filter(|posting| {
+                    let document = &self.documents[posting.document as usize];
+                    posting.fields & mask != 0
+                        && document.language == language
+                        && document.example.is_some() == (mode == SearchMode::Code)
+                });
+                for posting in postings {
+                    let boost = self.boost(posting.fields & mask, posting.document);
+                    let candidate = matches.entry(posting.document).or_default();
+                    candidate.score += weight * idf * self.term_score(posting) * boost;
+                    candidate.matches.push(TermMatch {
+                        query: word.clone(),
+                        term: term.text.clone(),
+                        kind,
+                    });
+                }
+            }
+            candidates = Some(match candidates {
+                None => matches,
+                Some(candidates) => intersect(candidates, matches),
+            });
+        }
+
+        let mut candidates = candidates?;
+        if clause.is_phrase() && check_phrases {
+            candidates.retain(|document, _| self.contains_phrase(*document, clause));
+        }
+        Some(candidates)
+    }
+
+    /// The words of a clause that are looked up in the index for documents in the language. Stop
+    /// words are skipped outside of code because they were never indexed
+    fn searched_words<'a>(
+        &'a self,
+        clause: &'a Clause,
+        mode: SearchMode,
+        language: Language,
+    ) -> impl Iterator<Item = &'a String> {
+        clause.words.iter().filter(move |word| {
+            clause.field == Field::Code
+                || mode == SearchMode::Code
+                || !self.tokenizer.is_stop_word(language, word)
+        })
+    }
+
+    /// How much a match in the fields of a document is boosted: the largest boost of the fields,
+    /// scaled by how high the page is in the table of contents
+    fn boost(&self, fields: u32, document: u32) -> f32 {
+        let document = &self.documents[document as usize];
+        let boosts = &self.ranking.boost;
+        // The title of a section is its heading
+        let title = match document.anchor {
+            Some(_) => boosts.heading,
+            None => boosts.title,
+        };
+        let field = [
+            (TITLE_FIELD | KEYWORDS_FIELD, title),
+            (BODY_FIELD, boosts.body),
+            (CODE_FIELD, boosts.code),
+        ]
+        .into_iter()
+        .filter(|(mask, _)| fields & mask != 0)
+        .map(|(_, boost)| boost)
+        .fold(0.0, f32::max);
+        // Top level pages get the whole hierarchy boost, and each level below gets less of it
+        let hierarchy = 1.0 + (boosts.hierarchy - 1.0) / (1.0 + document.depth as f32);
+        field * hierarchy
+    }
+
+    /// The documents pinned to the top of the results for the query, in the order they were
+    /// pinned
+    fn pinned_documents(&self, query: &Query) -> Vec<u32> {
+        let mut pinned = Vec::new();
+        for pin in &self.ranking.pinned {
+            if Query::parse(&pin.query) != *query {
+                continue;
+            }
+            if let Some(document) = self.pinned_document(pin) {
+                if !pinned.contains(&(document as u32)) {
+                    pinned.push(document as u32);
+                }
+            }
+        }
+        pinned
+    }
+
+    /// The page or section a result is pinned to. A trailing slash in the url doesn't matter
+    fn pinned_document(&self, pin: &PinnedResult) -> Option<usize> {
+        let (url, anchor) = pin.location();
+        self.documents.iter().position(|document| {
+            document.example.is_none()
+                && document.url.trim_end_matches('/') == url.trim_end_matches('/')
+                && document.anchor.as_deref() == anchor
+        })
+    }
+
+    /// The first word in the title or body of the document that is indexed as the term
+    fn spelling(&self, document: &StoredDocument, term: &str) -> Option<String> {
+        [&document.title, &document.body]
+            .into_iter()
+            .find_map(|text| {
+                tokenize(text, true)
+                    .into_iter()
+                    .find(|token| self.tokenizer.stem(document.language, &token.text) == term)
+                    .map(|token| token.text)
+            })
+    }
+
+    /// Find the index terms a query word matches: the word or its stem in the language, any term
+    /// they are a prefix of and, if nothing else matched, any term within a few typos of them.
+    ///
+    /// The word itself is tried as well as its stem so the end of a word that is still being typed
+    /// can match by prefix
+    fn expand(
+        &self,
+        word: &str,
+        language: Language,
+        prefix: bool,
+        allow_typos: bool,
+    ) -> Vec<(&Term, MatchKind)> {
+        let stem = self.tokenizer.stem(language, word);
+        let mut forms = vec![stem];
+        if forms[0] != word {
+            forms.push(Cow::Borrowed(word));
+        }
+
+        let mut expanded: Vec<(&Term, MatchKind)> = Vec::new();
+        for form in &forms {
+            let start = self
+                .terms
+                .partition_point(|term| term.text.as_str() < form.as_ref());
+            let matches = self.terms[start..]
+                .iter()
+                .take_while(|term| term.text.starts_with(form.as_ref()))
+                .filter(|term| prefix || term.text == *form)
+                .take(MAX_EXPANSIONS);
+            for term in matches {
+                let kind = match term.text == *form {
+                    true => MatchKind::Exact,
+                    false => MatchKind::Prefix,
+                };
+                match expanded
+                    .iter_mut()
+                    .find(|(existing, _)| std::ptr::eq(*existing, term))
+                {
+                    Some((_, existing)) if kind == MatchKind::Exact => *existing = kind,
+                    Some(_) => {}
+                    None => expanded.push((term, kind)),
+                }
+            }
+        }
+
+        let max_typos = fuzzy::max_typos(word.chars().count());
+        if allow_typos && expanded.is_empty() && max_typos > 0 {
+            let mut typos: Vec<_> = self
+                .terms
+                .iter()
+                .filter_map(|term| {
+                    let typos = forms
+                        .iter()
+                        .filter_map(|form| fuzzy::edit_distance(form, &term.text, max_typos))
+                        .min()?;
+                    Some((term, typos))
+                })
+                .collect();
+            // Prefer the closest and most common terms
+            typos.sort_by_key(|(term, typos)| (*typos, std::cmp::Reverse(term.document_frequency)));
+            expanded.extend(
+                typos
+                    .into_iter()
+                    .take(MAX_EXPANSIONS)
+                    .map(|(term, typos)| (term, MatchKind::Fuzzy { typos })),
+            );
+        }
+
+        expanded
+    }
+
+    /// Check if the words of a phrase appear next to each other in the document
+    fn contains_phrase(&self, document: u32, clause: &Clause) -> bool {
+        let document = &self.documents[document as usize];
+        let texts: &[&str] = match clause.field {
+            Field::Any => &[&document.title, &document.body],
+            Field::Title => &[&document.title],
+            // The code isn't stored separately, so check the whole body
+            Field::Code => &[&document.body],
+        };
+        let stem = |word: &str| self.tokenizer.stem(document.language, word).into_owned();
+        let phrase: Vec<String> = clause.words.iter().map(|word| stem(word)).collect();
+        texts.iter().any(|text| {
+            let words: Vec<_> = tokenize(text, false)
+                .into_iter()
+                .map(|token| stem(&token.text))
+                .collect();
+            words.windows(phrase.len()).any(|window| {
+                window
+                    .iter()
+                    .zip(&phrase)
+                    .enumerate()
+                    .all(|(i, (word, query))| {
+                        // The last word of a phrase can be a prefix
+                        word == query || (i == phrase.len() - 1 && word.starts_with(query.as_str()))
+                    })
+            })
+        })
+    }
+
+    fn idf(&self, term: &Term, mode: SearchMode) -> f32 {
+        let (total, document_frequency) = match mode {
+            SearchMode::Pages => (
+                self.documents.len() - self.example_count,
+                term.document_frequency - term.example_frequency,
+            ),
+            SearchMode::Code => (self.example_count, term.example_frequency),
+        };
+        let total = total as f32;
+        let document_frequency = document_frequency as f32;
+        ((total - document_frequency + 0.5) / (document_frequency + 0.5) + 1.0).ln()
+    }
+
+    fn term_score(&self, posting: &Posting) -> f32 {
+        let frequency = posting.frequency as f32;
+        let document = &self.documents[posting.document as usize];
+        let average_length = match document.example {
+            Some(_) => self.example_average_length,
+            None => self.average_length,
+        };
+        let length = document.length as f32;
+        let normalization = 1.0 - B + B * length / average_length.max(1.0);
+        frequency * (K1 + 1.0) / (frequency + K1 * normalization)
+    }
+
+    /// Serialize the index into a compact binary format
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut encoder = Encoder::default();
+        write_header(&mut encoder, NATIVE_INDEX_MAGIC);
+        write_tokenizer(&mut encoder, &self.tokenizer);
+        write_ranking(&mut encoder, &self.ranking);
+        write_excerpts(&mut encoder, &self.excerpts);
+
+        encoder.write_len(self.documents.len());
+        for document in &self.documents {
+            write_document_info(&mut encoder, document);
+            encoder.write_str(&document.body);
+        }
+
+        // Terms are sorted, so store each term as the length of the prefix it shares with the previous term and the rest of the term
+        encoder.write_len(self.terms.len());
+        let mut previous = "";
+        for term in &self.terms {
+            let shared = shared_prefix_len(previous, &term.text);
+            encoder.write_len(shared);
+            encoder.write_str(&term.text[shared..]);
+            write_word(&mut encoder, term);
+            write_postings(&mut encoder, &term.postings);
+            previous = &term.text;
+        }
+
+        write_popular(&mut encoder, &self.popular);
+
+        encoder.finish()
+    }
+
+    /// Deserialize an index created with [`NativeIndex::to_bytes`]
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, IndexError> {
+        let mut decoder = Decoder::new(bytes);
+        read_header(&mut decoder, NATIVE_INDEX_MAGIC, "native index")?;
+        let tokenizer = read_tokenizer(&mut decoder)?;
+        let ranking = read_ranking(&mut decoder)?;
+        let excerpts = read_excerpts(&mut decoder)?;
+
+        let document_count = decoder.read_len()?;
+        let mut documents = Vec::with_capacity(document_count);
+        for _ in 0..document_count {
+            let mut document = read_document_info(&mut decoder)?;
+            document.body = decoder.read_str()?.to_string();
+            documents.push(document);
+        }
+
+        let term_count = decoder.read_len()?;
+        let mut terms: Vec<Term> = Vec::with_capacity(term_count);
+        for _ in 0..term_count {
+            let previous = terms.last().map(|term| term.text.as_str()).unwrap_or("");
+            let text = read_term(&mut decoder, previous)?;
+            let word = read_term(&mut decoder, &text)?;
+            let postings = read_postings(&mut decoder, documents.len())?;
+            terms.push(Term::new(text, word, postings, &documents));
+        }
+
+        let popular = read_popular(&mut decoder, documents.len())?;
+
+        if !decoder.is_empty() {
+            return Err(DecodeError::new("trailing data after the index").into());
+        }
+
+        let mut index = Self::from_parts(documents, terms, tokenizer, ranking, excerpts);
+        index.popular = popular;
+        Ok(index)
+    }
+}
+
+impl SearchBackend for NativeIndex {
+    fn search(&self, query: &str) -> Result<Vec<DocumentMatch>, SearchError> {
+        Ok(self.query(query))
+    }
+
+    fn search_code(&self, query: &str) -> Result<Vec<DocumentMatch>, SearchError> {
+        Ok(self.query_code(query))
+    }
+
+    fn complete(&self, query: &str, limit: usize) -> Vec<String> {
+        NativeIndex::complete(self, query, limit)
+    }
+
+    fn did_you_mean(&self, query: &str) -> Option<String> {
+        NativeIndex::did_you_mean(self, query)
+    }
+
+    fn popular_searches(&self) -> Vec<DocumentMatch> {
+        NativeIndex::popular_searches(self)
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        NativeIndex::to_bytes(self)
+    }
+}
+
+/// Write the magic bytes and format version a serialized index starts with
+fn write_header(encoder: &mut Encoder, magic: &[u8]) {
+    encoder.write_raw(magic);
+    // The version has a fixed size so it can always be read, even if the rest of the format changes
+    encoder.write_raw(&NATIVE_INDEX_VERSION.to_le_bytes());
+}
+
+/// Check the magic bytes and format version written by [`write_header`]
+fn read_header(decoder: &mut Decoder, magic: &[u8], name: &str) -> Result<(), IndexError> {
+    if decoder.read_raw(magic.len())? != magic {
+        return Err(DecodeError::new(format!("missing the {name} header")).into());
+    }
+    let version = decoder.read_raw(4)?;
+    let found = u32::from_le_bytes([version[0], version[1], version[2], version[3]]);
+    if found != NATIVE_INDEX_VERSION {
+        return Err(IndexError::VersionMismatch {
+            found,
+            expected: NATIVE_INDEX_VERSION,
+        });
+    }
+    Ok(())
+}
+
+/// Write everything about a document except its body
+fn write_document_info(encoder: &mut Encoder, document: &StoredDocument) {
+    encoder.write_str(&document.url);
+    // Anchors are never empty, so an empty string marks the top of the page
+    encoder.write_str(document.anchor.as_deref().unwrap_or_default());
+    encoder.write_len(document.breadcrumb.len());
+    for title in &document.breadcrumb {
+        encoder.write_str(title);
+    }
+    encoder.write_str(&document.title);
+    encoder.write_u32(document.length);
+    encoder.write_str(document.language.code());
+    encoder.write_u32(document.depth);
+    // The language and file of an example are never empty, so empty strings mark missing values
+    encoder.write_u32(document.example.is_some() as u32);
+    if let Some(example) = &document.example {
+        encoder.write_str(example.language.as_deref().unwrap_or_default());
+        encoder.write_str(example.file.as_deref().unwrap_or_default());
+    }
+}
+
+/// Read a document written with [`write_document_info`]. The body is left empty
+fn read_document_info(decoder: &mut Decoder) -> Result<StoredDocument, DecodeError> {
+    let url = decoder.read_str()?.to_string();
+    let anchor = Some(decoder.read_str()?)
+        .filter(|anchor| !anchor.is_empty())
+        .map(str::to_string);
+    let breadcrumb_len = decoder.read_len()?;
+    let mut breadcrumb = Vec::with_capacity(breadcrumb_len);
+    for _ in 0..breadcrumb_len {
+        breadcrumb.push(decoder.read_str()?.to_string());
+    }
+    Ok(StoredDocument {
+        url,
+        anchor,
+        breadcrumb,
+        title: decoder.read_str()?.to_string(),
+        body: String::new(),
+        length: decoder.read_u32()?,
+        language: Language::from_code(decoder.read_str()?),
+        depth: decoder.read_u32()?,
+        example: match decoder.read_u32()? {
+            0 => None,
+            _ => {
+                let mut read = || {
+                    Ok::<_, DecodeError>(
+                        Some(decoder.read_str()?)
+                            .filter(|text| !text.is_empty())
+                            .map(str::to_string),
+                    )
+                };
+                Some(CodeSource {
+                    language: read()?,
+                    file: read()?,
+                })
+            }
+        },
+    })
+}
+
+const STEMMING_FLAG: u32 = 1 << 0;
+const STOP_WORDS_FLAG: u32 = 1 << 1;
+
+/// Write the tokenizer so queries are tokenized the same way when the index is loaded
+fn write_tokenizer(encoder: &mut Encoder, tokenizer: &TokenizerConfig) {
+    encoder.write_str(tokenizer.language.code());
+    let mut flags = 0;
+    if tokenizer.stemming {
+        flags |= STEMMING_FLAG;
+    }
+    if tokenizer.stop_words {
+        flags |= STOP_WORDS_FLAG;
+    }
+    encoder.write_u32(flags);
+}
+
+fn read_tokenizer(decoder: &mut Decoder) -> Result<TokenizerConfig, DecodeError> {
+    let language = Language::from_code(decoder.read_str()?);
+    let flags = decoder.read_u32()?;
+    Ok(TokenizerConfig {
+        language,
+        stemming: flags & STEMMING_FLAG != 0,
+        stop_words: flags & STOP_WORDS_FLAG != 0,
+    })
+}
+
+/// Write the ranking so results are ranked the same way when the index is loaded
+fn write_ranking(encoder: &mut Encoder, ranking: &RankingConfig) {
+    let boost = &ranking.boost;
+    for boost in [
+        boost.title,
+        boost.heading,
+        boost.body,
+        boost.code,
+        boost.hierarchy,
+    ] {
+        encoder.write_f32(boost);
+    }
+    encoder.write_len(ranking.pinned.len());
+    for pin in &ranking.pinned {
+        encoder.write_str(&pin.query);
+        encoder.write_str(&pin.url);
+    }
+    encoder.write_len(ranking.popular.len());
+    for popular in &ranking.popular {
+        encoder.write_str(&popular.query);
+        encoder.write_str(popular.title.as_deref().unwrap_or_default());
+    }
+}
+
+fn read_ranking(decoder: &mut Decoder) -> Result<RankingConfig, DecodeError> {
+    let boost = Boosts {
+        title: decoder.read_f32()?,
+        heading: decoder.read_f32()?,
+        body: decoder.read_f32()?,
+        code: decoder.read_f32()?,
+        hierarchy: decoder.read_f32()?,
+    };
+    let pinned_count = decoder.read_len()?;
+    let mut pinned = Vec::with_capacity(pinned_count);
+    for _ in 0..pinned_count {
+        pinned.push(PinnedResult {
+            query: decoder.read_str()?.to_string(),
+            url: decoder.read_str()?.to_string(),
+        });
+    }
+    let popular_count = decoder.read_len()?;
+    let mut popular = Vec::with_capacity(popular_count);
+    for _ in 0..popular_count {
+        let query = decoder.read_str()?.to_string();
+        let title = decoder.read_str()?;
+        popular.push(PopularSearch {
+            query,
+            title: (!title.is_empty()).then(|| title.to_string()),
+        });
+    }
+    Ok(RankingConfig {
+        boost,
+        pinned,
+        popular,
+    })
+}
+
+/// Write how excerpts are cut so results look the same when the index is loaded
+fn write_excerpts(encoder: &mut Encoder, excerpts: &ExcerptConfig) {
+    encoder.write_u64(excerpts.context as u64);
+    encoder.write_u64(excerpts.max_excerpts as u64);
+}
+
+fn read_excerpts(decoder: &mut Decoder) -> Result<ExcerptConfig, DecodeError> {
+    // These are settings, not lengths of the data after them, so they can be larger than the rest
+    // of the index
+    Ok(ExcerptConfig {
+        context: decoder.read_usize()?,
+        max_excerpts: decoder.read_usize()?,
+    })
+}
+
+/// Write the popular searches that were found in the index
+fn write_popular(encoder: &mut Encoder, popular: &[(String, u32)]) {
+    encoder.write_len(popular.len());
+    for (title, document) in popular {
+        encoder.write_str(title);
+        encoder.write_u32(*document);
+    }
+}
+
+fn read_popular(
+    decoder: &mut Decoder,
+    document_count: usize,
+) -> Result<Vec<(String, u32)>, DecodeError> {
+    let popular_count = decoder.read_len()?;
+    let mut popular = Vec::with_capacity(popular_count);
+    for _ in 0..popular_count {
+        let title = decoder.read_str()?.to_string();
+        let document = decoder.read_u32()?;
+        if document as usize >= document_count {
+            return Err(DecodeError::new("popular search for a missing document"));
+        }
+        popular.push((title, document));
+    }
+    Ok(popular)
+}
+
+/// Write how a term is spelled as the length of the prefix it shares with the term and the rest
+/// of the word. Most words start with their stem, so this is usually a few bytes
+fn write_word(encoder: &mut Encoder, term: &Term) {
+    let shared = shared_prefix_len(&term.text, &term.word);
+    encoder.write_len(shared);
+    encoder.write_str(&term.word[shared..]);
+}
+
+/// Write a term's postings. Postings are sorted by document, so store the difference between
+/// document ids
+fn write_postings(encoder: &mut Encoder, postings: &[Posting]) {
+    encoder.write_len(postings.len());
+    let mut previous_document = 0;
+    for posting in postings {
+        encoder.write_u32(posting.document - previous_document);
+        encoder.write_u32(posting.frequency);
+        encoder.write_u32(posting.fields);
+        previous_document = posting.document;
+    }
+}
+
+fn read_postings(
+    decoder: &mut Decoder,
+    document_count: usize,
+) -> Result<Vec<Posting>, DecodeError> {
+    let posting_count = decoder.read_len()?;
+    let mut postings = Vec::with_capacity(posting_count);
+    let mut document = 0u32;
+    for _ in 0..posting_count {
+        document = document
+            .checked_add(decoder.read_u32()?)
+            .filter(|document| (*document as usize) < document_count)
+            .ok_or_else(|| DecodeError::new("posting for a missing document"))?;
+        postings.push(Posting {
+            document,
+            frequency: decoder.read_u32()?,
+            fields: decoder.read_u32()?,
+        });
+    }
+    Ok(postings)
+}
+
+/// Read a term stored as the length of the prefix it shares with the previous term and the rest
+/// of the term
+fn read_term(decoder: &mut Decoder, previous: &str) -> Result<String, DecodeError> {
+    // The shared prefix is already decoded, so it can be longer than the rest of the data
+    let shared = decoder.read_usize()?;
+    let prefix = previous
+        .get(..shared)
+        .ok_or_else(|| DecodeError::new("invalid term prefix"))?;
+    Ok(format!("{prefix}{}", decoder.read_str()?))
+}
+
+/// Keep the documents in both sets, combining their scores and matches
+fn intersect(
+    mut a: HashMap<u32, Candidate>,
+    b: HashMap<u32, Candidate>,
+) -> HashMap<u32, Candidate> {
+    a.retain(|document, _| b.contains_key(document));
+    for (document, candidate) in b {
+        if let Some(existing) = a.get_mut(&document) {
+            existing.score += candidate.score;
+            existing.matches.extend(candidate.matches);
+        }
+    }
+    a
+}
+
+fn shared_prefix_len(a: &str, b: &str) -> usize {
+    a.char_indices()
+        .zip(b.chars())
+        .take_while(|((_, a), b)| a == b)
+        .map(|((index, a), _)| index + a.len_utf8())
+        .last()
+        .unwrap_or(0)
+}
+
+/// Create an excerpt of whole lines of a code example, starting just before the first match
+fn code_excerpt(code: &str, highlights: &[(usize, usize)]) -> Excerpt {
+    let line_starts: Vec<usize> = std::iter::once(0)
+        .chain(code.match_indices('\n').map(|(index, _)| index + 1))
+        .collect();
+    let first_match = highlights.first().map_or(0, |(start, _)| *start);
+    let first_line = line_starts.partition_point(|start| *start <= first_match) - 1;
+    let first_line = first_line.saturating_sub(EXAMPLE_CONTEXT_LINES);
+    let start = line_starts[first_line];
+    let end = line_starts
+        .get(first_line + EXAMPLE_LINES)
+        .map_or(code.len(), |next| next - 1);
+
+    let count = highlights
+        .iter()
+        .filter(|(s, e)| *s >= start && *e <= end)
+        .count();
+    Excerpt {
+        text: segments(code, start, end, highlights),
+        score: count,
+    }
+}
+
+/// The byte ranges of the words in the text that are indexed as the matched terms
+fn highlights(
+    text: &str,
+    terms: &[&str],
+    tokenizer: &TokenizerConfig,
+    language: Language,
+) -> Vec<(usize, usize)> {
+    let matches: Vec<(usize, usize)> = tokenize(text, true)
+        .into_iter()
+        .filter(|token| terms.contains(&tokenizer.stem(language, &token.text).as_ref()))
+        .map(|token| (token.start, token.end))
+        .collect();
+
+    // Identifiers are tokenized both whole and by part, so a match can be inside a larger match
+    merge_highlights(text, &matches)
+}
+
+#[cfg(test)]
+fn test_index() -> NativeIndex {
+    NativeIndex::new([
+        Document {
+            url: "/signals".into(),
+            title: "Signals".into(),
+            body:
+                "Create state with use_signal. A Signal is Copy and can be read with Signal::read."
+                    .into(),
+            ..Default::default()
+        },
+        Document {
+            url: "/signals".into(),
+            anchor: Some("memos".into()),
+            breadcrumb: vec!["Signals".into()],
+            title: "Memos".into(),
+            body: "use_memo caches a value derived from other state.".into(),
+            keywords: vec!["computed".into()],
+            ..Default::default()
+        },
+        Document {
+            url: "/resources".into(),
+            title: "Resources".into(),
+            body: "use_resource runs an async task and reruns it when any signal it reads changes."
+                .into(),
+            ..Default::default()
+        },
+        Document {
+            url: "/router".into(),
+            title: "Router".into(),
+            body: "The router maps urls to components with the Routable derive.".into(),
+            ..Default::default()
+        },
+    ])
+}
+
+#[test]
+fn bm25_ranking_and_prefixes() {
+    let index = test_index();
+
+    let results = index.query("signal");
+    let urls: Vec<_> = results.iter().map(|result| result.url.as_str()).collect();
+    assert_eq!(urls, ["/signals", "/resources"]);
+
+    // Every query term has to match
+    let results = index.query("signal async");
+    let urls: Vec<_> = results.iter().map(|result| result.url.as_str()).collect();
+    assert_eq!(urls, ["/resources"]);
+
+    // Partial words match by prefix
+    let results = index.query("rout");
+    assert_eq!(results[0].url, "/router");
+    let highlighted: Vec<_> = results[0].excerpts[0]
+        .text
+        .iter()
+        .filter(|segment| segment.highlighted)
+        .map(|segment| segment.text.as_str())
+        .collect();
+    assert_eq!(highlighted, ["router", "Routable"]);
+
+    // Sections are returned with the heading they start at
+    let results = index.query("memo");
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].url, "/signals");
+    assert_eq!(results[0].anchor.as_deref(), Some("memos"));
+    assert_eq!(results[0].breadcrumb, ["Signals"]);
+
+    // Keywords are searchable
+    let results = index.query("computed");
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].anchor.as_deref(), Some("memos"));
+}
+
+#[test]
+fn serialization_round_trip() {
+    let index = test_index();
+    let bytes = index.to_bytes();
+    assert_eq!(NativeIndex::from_bytes(&bytes).unwrap(), index);
+    assert!(matches!(
+        NativeIndex::from_bytes(&bytes[..bytes.len() - 1]),
+        Err(IndexError::Corrupt(_))
+    ));
+
+    // Indexes written by another version of the format are rejected before they are read
+    let mut other_version = bytes.clone();
+    other_version[NATIVE_INDEX_MAGIC.len()] += 1;
+    assert!(matches!(
+        NativeIndex::from_bytes(&other_version),
+        Err(IndexError::VersionMismatch { found, expected })
+            if found == NATIVE_INDEX_VERSION + 1 && expected == NATIVE_INDEX_VERSION
+    ));
+}
+
+#[test]
+fn long_terms_at_the_end_round_trip() {
+    // The last term is spelled like its stem, so its shared prefix is longer than the bytes after it
+    let index = NativeIndex::new([Document {
+        url: "/hotreload".into(),
+        body: "hotreload".into(),
+        ..Default::default()
+    }]);
+    let bytes = index.to_bytes();
+    assert_eq!(NativeIndex::from_bytes(&bytes).unwrap(), index);
+}
+
+#[test]
+fn excerpt_settings_round_trip() {
+    // The excerpt context is stored near the end of the index and is larger than the data after it
+    let index = NativeIndex::new([Document {
+        url: "/signals".into(),
+        body: "Signals".into(),
+        ..Default::default()
+    }])
+    .with_excerpts(ExcerptConfig {
+        context: 200,
+        max_excerpts: 2,
+    });
+    let bytes = index.to_bytes();
+    assert_eq!(NativeIndex::from_bytes(&bytes).unwrap(), index);
+}
+
+#[test]
+fn cjk_text_is_searchable() {
+    let index = NativeIndex::new([Document {
+        url: "/zh/signals".into(),
+        title: "信号".into(),
+        body: "信号是响应式的状态。".into(),
+        language: Some(Language::Other),
+        ..Default::default()
+    }]);
+    assert_eq!(index.query("响应式").len(), 1);
+    assert_eq!(index.query("信").len(), 1);
+    assert!(index.query("响式").is_empty());
+}
+
+#[test]
+fn boosts_and_pinned_results() {
+    let ranking = RankingConfig {
+        boost: Boosts {
+            heading: 10.0,
+            ..Default::default()
+        },
+        pinned: vec![
+            PinnedResult {
+                query: "Signal".into(),
+                url: "/router/".into(),
+            },
+            PinnedResult {
+                query: "Signal".into(),
+                url: "/learn/0.6/signals".into(),
+            },
+        ],
+        ..Default::default()
+    };
+    let index = test_index().with_ranking(ranking);
+    // Pins to pages the index doesn't have are dropped when the index is built
+    assert_eq!(index.ranking.pinned.len(), 1);
+
+    // Pinned results come first even if they don't match the query
+    let results = index.query("signal");
+    let urls: Vec<_> = results.iter().map(|result| result.url.as_str()).collect();
+    assert_eq!(urls, ["/router", "/signals", "/resources"]);
+    assert!(results[0].score > results[1].score);
+
+    // Matches in headings are boosted over matches in the body
+    let results = index.query("state");
+    assert_eq!(results[0].anchor.as_deref(), Some("memos"));
+    let boosted = index.query("memos");
+    let plain = test_index().query("memos");
+    assert!(boosted[0].score > plain[0].score);
+
+    let bytes = index.to_bytes();
+    assert_eq!(NativeIndex::from_bytes(&bytes).unwrap(), index);
+}
@@ -0,0 +1,146 @@
+use serde::{Deserialize, Serialize};
+
+use super::tokenize::tokenize;
+
+/// A parsed search query. Every clause that isn't excluded has to match for a document to be returned.
+///
+/// - `signal memo` matches documents with both words
+/// - `"derive macro"` matches the words next to each other in that order
+/// - `-router` removes documents with the word
+/// - `title:signal` and `code:use_signal` only match the title or code of a document
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Query {
+    pub clauses: Vec<Clause>,
+    /// The kind of documents the query searches
+    pub mode: SearchMode,
+}
+
+/// The kind of documents a [`Query`] searches
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchMode {
+    /// Pages and their sections
+    #[default]
+    Pages,
+    /// The code blocks in pages. Code blocks are searched on their own, and stop words are
+    /// matched since they are often keywords
+    Code,
+}
+
+/// A word or phrase in a [`Query`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Clause {
+    /// The lowercase words to match. Phrases have more than one word that must appear in order
+    pub words: Vec<String>,
+    /// The part of the document the clause matches
+    pub field: Field,
+    /// If true, documents that match the clause are removed from the results
+    pub exclude: bool,
+}
+
+impl Clause {
+    /// Returns true if the clause has more than one word that must appear in order
+    pub fn is_phrase(&self) -> bool {
+        self.words.len() > 1
+    }
+}
+
+/// The part of a document a [`Clause`] matches
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Field {
+    #[default]
+    Any,
+    Title,
+    Code,
+}
+
+impl Query {
+    pub fn parse(query: &str) -> Self {
+        let mut clauses = Vec::new();
+        let mut rest = query.trim_start();
+
+        while !rest.is_empty() {
+            let mut exclude = false;
+            if let Some(stripped) = rest.strip_prefix('-') {
+                exclude = true;
+                rest = stripped;
+            }
+
+            let mut field = Field::Any;
+            for (prefix, prefix_field) in [("title:", Field::Title), ("code:", Field::Code)] {
+                if rest
+                    .get(..prefix.len())
+                    .is_some_and(|start| start.eq_ignore_ascii_case(prefix))
+                {
+                    field = prefix_field;
+                    rest = &rest[prefix.len()..];
+                    break;
+                }
+            }
+
+            let text;
+            if let Some(quoted) = rest.strip_prefix('"') {
+                // An unclosed quote runs to the end of the query
+                let end = quoted.find('"').unwrap_or(quoted.len());
+                text = &quoted[..end];
+                rest = quoted.get(end + 1..).unwrap_or_default();
+            } else {
+                let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+                text = &rest[..end];
+                rest = &rest[end..];
+            }
+            rest = rest.trim_start();
+
+            // Words with punctuation like `Signal::read` are matched as a phrase
+            let words: Vec<_> = tokenize(text, false)
+                .into_iter()
+                .map(|token| token.text)
+                .collect();
+            if !words.is_empty() {
+                clauses.push(Clause {
+                    words,
+                    field,
+                    exclude,
+                });
+            }
+        }
+
+        Self {
+            clauses,
+            mode: SearchMode::Pages,
+        }
+    }
+
+    /// Search a different kind of document
+    pub fn in_mode(mut self, mode: SearchMode) -> Self {
+        self.mode = mode;
+        self
+    }
+}
+
+#[test]
+fn parse_query_syntax() {
+    let query = Query::parse(
+        r#"Signal "derive  macro" -router TITLE:memo code:Signal::read -"old api"  "unclosed"#,
+    );
+    let clauses: Vec<_> = query
+        .clauses
+        .iter()
+        .map(|clause| (clause.words.join(" "), clause.field, clause.exclude))
+        .collect();
+    assert_eq!(
+        clauses,
+        [
+            ("signal".to_string(), Field::Any, false),
+            ("derive macro".to_string(), Field::Any, false),
+            ("router".to_string(), Field::Any, true),
+            ("memo".to_string(), Field::Title, false),
+            ("signal read".to_string(), Field::Code, false),
+            ("old api".to_string(), Field::Any, true),
+            ("unclosed".to_string(), Field::Any, false),
+        ]
+    );
+
+    // Lone operators and punctuation don't create clauses
+    assert_eq!(Query::parse(" - title: \"\" :: ").clauses, []);
+}
@@ -0,0 +1,458 @@
+//! Split a native index into shards so a client only downloads the parts of the index a query
+//! needs. The dictionary shard has the terms and the title of every document, the term shards
+//! have the postings of every term that starts with the same characters and the document shards
+//! have the bodies used for excerpts and phrases.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+use super::{
+    read_document_info, read_excerpts, read_header, read_popular, read_postings, read_ranking,
+    read_term, read_tokenizer, shared_prefix_len, write_document_info, write_excerpts,
+    write_header, write_popular, write_postings, write_ranking, write_tokenizer, write_word,
+    Clause, DecodeError, Decoder, Encoder, Field, NativeIndex, Query, SearchMode, Term,
+};
+use crate::{DocumentMatch, IndexError};
+
+/// The name of the shard that has to be loaded before any other shard
+pub const DICTIONARY_SHARD: &str = "dictionary";
+
+/// The bytes every serialized dictionary shard starts with
+const DICTIONARY_MAGIC: &[u8; 4] = b"DXSD";
+/// The number of characters at the start of a term that pick the shard of its postings
+const TERM_SHARD_PREFIX: usize = 2;
+/// The number of document bodies in each document shard
+const DOCUMENTS_PER_SHARD: usize = 64;
+/// Queries of a single word this short are answered from the dictionary alone
+const INSTANT_QUERY_LEN: usize = 2;
+/// The number of results stored for each instant query
+const INSTANT_RESULTS: usize = 8;
+
+/// The shard with the postings of a term. Shard names are used in file names and urls, so the
+/// characters are hex encoded
+fn term_shard(term: &str) -> String {
+    let prefix: String = term.chars().take(TERM_SHARD_PREFIX).collect();
+    let hex: String = prefix.bytes().map(|byte| format!("{byte:02x}")).collect();
+    format!("terms-{hex}")
+}
+
+/// The shard with the body of a document
+fn document_shard(document: u32) -> String {
+    format!("documents-{}", document as usize / DOCUMENTS_PER_SHARD)
+}
+
+impl NativeIndex {
+    /// Split the index into the [`DICTIONARY_SHARD`] and the shards a [`ShardedIndex`] loads as
+    /// queries need them. Returns the name and bytes of each shard
+    pub fn to_shards(&self) -> Vec<(String, Vec<u8>)> {
+        let mut shards = vec![(DICTIONARY_SHARD.to_string(), self.dictionary_bytes())];
+
+        let mut term_shards: BTreeMap<String, Vec<&Term>> = BTreeMap::new();
+        for term in &self.terms {
+            term_shards
+                .entry(term_shard(&term.text))
+                .or_default()
+                .push(term);
+        }
+        for (name, terms) in term_shards {
+            let mut encoder = Encoder::default();
+            encoder.write_len(terms.len());
+            for term in terms {
+                encoder.write_str(&term.text);
+                write_postings(&mut encoder, &term.postings);
+            }
+            shards.push((name, encoder.finish()));
+        }
+
+        for (shard, documents) in self.documents.chunks(DOCUMENTS_PER_SHARD).enumerate() {
+            let mut encoder = Encoder::default();
+            encoder.write_len(documents.len());
+            for document in documents {
+                encoder.write_str(&document.body);
+            }
+            shards.push((format!("documents-{shard}"), encoder.finish()));
+        }
+
+        shards
+    }
+
+    fn dictionary_bytes(&self) -> Vec<u8> {
+        let mut encoder = Encoder::default();
+        write_header(&mut encoder, DICTIONARY_MAGIC);
+        write_tokenizer(&mut encoder, &self.tokenizer);
+        write_ranking(&mut encoder, &self.ranking);
+        write_excerpts(&mut encoder, &self.excerpts);
+
+        encoder.write_len(self.documents.len());
+        for document in &self.documents {
+            write_document_info(&mut encoder, document);
+        }
+
+        encoder.write_len(self.terms.len());
+        let mut previous = "";
+        for term in &self.terms {
+            let shared = shared_prefix_len(previous, &term.text);
+            encoder.write_len(shared);
+            encoder.write_str(&term.text[shared..]);
+            write_word(&mut encoder, term);
+            encoder.write_u32(term.document_frequency);
+            encoder.write_u32(term.example_frequency);
+            previous = &term.text;
+        }
+
+        let instant = self.instant_results();
+        encoder.write_len(instant.len());
+        for (prefix, results) in instant {
+            encoder.write_str(&prefix);
+            encoder.write_len(results.len());
+            for (document, score) in results {
+                encoder.write_u32(document);
+                encoder.write_u64(score as u64);
+            }
+        }
+
+        write_popular(&mut encoder, &self.popular);
+
+        encoder.finish()
+    }
+
+    /// The best documents for every query short enough to answer from the dictionary
+    fn instant_results(&self) -> BTreeMap<String, Vec<(u32, usize)>> {
+        let prefixes: BTreeSet<String> = self
+            .terms
+            .iter()
+            .flat_map(|term| {
+                (1..=INSTANT_QUERY_LEN)
+                    .filter(|len| term.text.chars().count() >= *len)
+                    .map(|len| term.text.chars().take(len).collect())
+            })
+            .collect();
+
+        prefixes
+            .into_iter()
+            .map(|prefix| {
+                let query = Query {
+                    clauses: vec![Clause {
+                        words: vec![prefix.clone()],
+                        field: Field::Any,
+                        exclude: false,
+                    }],
+                    mode: SearchMode::Pages,
+                };
+                let results: Vec<_> = self
+                    .ranked_documents(&query, false)
+                    .into_iter()
+                    .take(INSTANT_RESULTS)
+                    .map(|(document, candidate)| {
+                        (document, (candidate.score * 100.0).round() as usize)
+                    })
+                    .collect();
+                (prefix, results)
+            })
+            .collect()
+    }
+}
+
+/// A [`NativeIndex`] that starts out with just the [`DICTIONARY_SHARD`] and loads the rest of the
+/// index one shard at a time.
+///
+/// Call [`ShardedIndex::missing_shards`] until it returns nothing, loading every shard it returns
+/// with [`ShardedIndex::insert_shard`], before searching with [`ShardedIndex::search`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ShardedIndex {
+    /// The index with the postings and bodies that have been loaded so far
+    index: NativeIndex,
+    loaded: HashSet<String>,
+    instant: HashMap<String, Vec<(u32, usize)>>,
+}
+
+impl ShardedIndex {
+    /// Start an index from the bytes of the [`DICTIONARY_SHARD`]
+    pub fn from_dictionary(bytes: &[u8]) -> Result<Self, IndexError> {
+        let mut decoder = Decoder::new(bytes);
+        read_header(&mut decoder, DICTIONARY_MAGIC, "dictionary")?;
+        let tokenizer = read_tokenizer(&mut decoder)?;
+        let ranking = read_ranking(&mut decoder)?;
+        let excerpts = read_excerpts(&mut decoder)?;
+
+        let document_count = decoder.read_len()?;
+        let mut documents = Vec::with_capacity(document_count);
+        for _ in 0..document_count {
+            documents.push(read_document_info(&mut decoder)?);
+        }
+
+        let term_count = decoder.read_len()?;
+        let mut terms: Vec<Term> = Vec::with_capacity(term_count);
+        for _ in 0..term_count {
+            let previous = terms.last().map(|term| term.text.as_str()).unwrap_or("");
+            let text = read_term(&mut decoder, previous)?;
+            terms.push(Term {
+                word: read_term(&mut decoder, &text)?,
+                text,
+                document_frequency: decoder.read_u32()?,
+                example_frequency: decoder.read_u32()?,
+                postings: Vec::new(),
+            });
+        }
+
+        let instant_count = decoder.read_len()?;
+        let mut instant = HashMap::with_capacity(instant_count);
+        for _ in 0..instant_count {
+            let prefix = decoder.read_str()?.to_string();
+            let result_count = decoder.read_len()?;
+            let mut results = Vec::with_capacity(result_count);
+            for _ in 0..result_count {
+                let document = decoder.read_u32()?;
+                if document as usize >= documents.len() {
+                    return Err(DecodeError::new("instant result for a missing document").into());
+                }
+                results.push((document, decoder.read_u64()? as usize));
+            }
+            instant.insert(prefix, results);
+        }
+
+        let popular = read_popular(&mut decoder, documents.len())?;
+
+        if !decoder.is_empty() {
+            return Err(DecodeError::new("trailing data after the dictionary").into());
+        }
+
+        let mut index = NativeIndex::from_parts(documents, terms, tokenizer, ranking, excerpts);
+        index.popular = popular;
+        Ok(Self {
+            index,
+            loaded: HashSet::from([DICTIONARY_SHARD.to_string()]),
+            instant,
+        })
+    }
+
+    /// Answer a query of one or two characters from the dictionary without loading any shards.
+    /// Returns `None` if the query needs the rest of the index. Instant results don't have
+    /// excerpts
+    pub fn instant_results(&self, query: &str) -> Option<Vec<DocumentMatch>> {
+        let query = Query::parse(query);
+        let [clause] = query.clauses.as_slice() else {
+            return None;
+        };
+        let [word] = clause.words.as_slice() else {
+            return None;
+        };
+        if clause.exclude || clause.field != Field::Any || word.chars().count() > INSTANT_QUERY_LEN
+        {
+            return None;
+        }
+
+        let results = self
+            .instant
+            .get(word)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        Some(
+            results
+                .iter()
+                .map(|(document, score)| {
+                    let document = &self.index.documents[*document as usize];
+                    DocumentMatch {
+                        url: document.url.clone(),
+                        anchor: document.anchor.clone(),
+                        breadcrumb: document.breadcrumb.clone(),
+                        title: document.title.clone(),
+                        excerpts: Vec::new(),
+                        matches: Vec::new(),
+                        score: *score,
+                        example: None,
+                    }
+                })
+                .collect(),
+        )
+    }
+
+    /// The shards that have to be loaded before the query can be answered. The documents a query
+    /// matches are only known once the postings of its terms are loaded, so keep loading the
+    /// missing shards until this returns nothing
+    pub fn missing_shards(&self, query: &str) -> Vec<String> {
+        self.missing_shards_for(&Query::parse(query))
+    }
+
+    /// Like [`ShardedIndex::missing_shards`], but for a query that has already been parsed
+    pub fn missing_shards_for(&self, query: &Query) -> Vec<String> {
+        let mut missing = BTreeSet::new();
+        for language in &self.index.languages {
+            for clause in &query.clauses {
+                for word in self.index.searched_words(clause, query.mode, *language) {
+                    let terms = self.index.expand(word, *language, true, !clause.exclude);
+                    for (term, _) in terms {
+                        missing.insert(term_shard(&term.text));
+                    }
+                }
+            }
+        }
+        missing.retain(|shard| !self.loaded.contains(shard));
+
+        if missing.is_empty() {
+            let documents = self.index.matching_documents(query, false);
+            let pinned = self.index.pinned_documents(query);
+            for document in documents.into_keys().chain(pinned) {
+                missing.insert(document_shard(document));
+            }
+            missing.retain(|shard| !self.loaded.contains(shard));
+        }
+
+        missing.into_iter().collect()
+    }
+
+    /// Returns true if the shard has already been loaded
+    pub fn is_loaded(&self, shard: &str) -> bool {
+        self.loaded.contains(shard)
+    }
+
+    /// Load a shard created with [`NativeIndex::to_shards`]
+    pub fn insert_shard(&mut self, name: &str, bytes: &[u8]) -> Result<(), DecodeError> {
+        if self.loaded.contains(name) {
+            return Ok(());
+        }
+
+        let mut decoder = Decoder::new(bytes);
+        if name.starts_with("terms-") {
+            let term_count = decoder.read_len()?;
+            for _ in 0..term_count {
+                let text = decoder.read_str()?;
+                let postings = read_postings(&mut decoder, self.index.documents.len())?;
+                let term = self
+                    .index
+                    .terms
+                    .binary_search_by(|term| term.text.as_str().cmp(text))
+                    .map_err(|_| DecodeError::new(format!("{text} is not in the dictionary")))?;
+                self.index.terms[term].postings = postings;
+            }
+        } else if let Some(shard) = name
+            .strip_prefix("documents-")
+            .and_then(|shard| shard.parse::<usize>().ok())
+        {
+            let document_count = decoder.read_len()?;
+            let start = shard * DOCUMENTS_PER_SHARD;
+            for document in start..start + document_count {
+                let body = decoder.read_str()?.to_string();
+                self.index
+                    .documents
+                    .get_mut(document)
+                    .ok_or_else(|| DecodeError::new("body for a missing document"))?
+                    .body = body;
+            }
+        } else {
+            return Err(DecodeError::new(format!("unknown shard {name}")));
+        }
+
+        if !decoder.is_empty() {
+            return Err(DecodeError::new(format!(
+                "trailing data after shard {name}"
+            )));
+        }
+        self.loaded.insert(name.to_string());
+        Ok(())
+    }
+
+    /// Search the shards that have been loaded so far, best match first
+    pub fn search(&self, query: &str) -> Vec<DocumentMatch> {
+        self.index.query(query)
+    }
+
+    /// Search the shards that have been loaded so far with a query that has already been parsed
+    pub fn search_query(&self, query: &Query) -> Vec<DocumentMatch> {
+        self.index.search_query(query)
+    }
+
+    /// Complete the last word of a query from the dictionary. See [`NativeIndex::complete`]
+    pub fn complete(&self, query: &str, limit: usize) -> Vec<String> {
+        self.index.complete(query, limit)
+    }
+
+    /// Suggest a similar query from the dictionary. See [`NativeIndex::did_you_mean`]
+    pub fn did_you_mean(&self, query: &str) -> Option<String> {
+        self.index.did_you_mean(query)
+    }
+
+    /// The popular searches stored in the dictionary. See [`NativeIndex::popular_searches`]
+    pub fn popular_searches(&self) -> Vec<DocumentMatch> {
+        self.index.popular_searches()
+    }
+}
+
+#[cfg(test)]
+fn load_all(index: &mut ShardedIndex, query: &str, shards: &HashMap<String, Vec<u8>>) -> usize {
+    let mut rounds = 0;
+    loop {
+        let missing = index.missing_shards(query);
+        if missing.is_empty() {
+            return rounds;
+        }
+        for shard in missing {
+            index.insert_shard(&shard, &shards[&shard]).unwrap();
+        }
+        rounds += 1;
+    }
+}
+
+#[test]
+fn sharded_search_matches_full_index() {
+    let full = super::test_index();
+    let shards: HashMap<_, _> = full.to_shards().into_iter().collect();
+    let mut index = ShardedIndex::from_dictionary(&shards[DICTIONARY_SHARD]).unwrap();
+
+    for query in [
+        "signal",
+        "rout",
+        "singal",
+        "\"derived from\"",
+        "use_signal -async",
+    ] {
+        load_all(&mut index, query, &shards);
+        assert_eq!(index.search(query), full.query(query), "{query}");
+    }
+    // Only the shards the queries needed were loaded
+    assert!(!index.is_loaded(&term_shard("caches")));
+}
+
+#[test]
+fn short_queries_are_answered_instantly() {
+    let full = super::test_index();
+    let shards: HashMap<_, _> = full.to_shards().into_iter().collect();
+    let mut index = ShardedIndex::from_dictionary(&shards[DICTIONARY_SHARD]).unwrap();
+
+    let instant = index.instant_results("ro").unwrap();
+    let urls: Vec<_> = instant.iter().map(|result| result.url.as_str()).collect();
+    assert_eq!(urls, ["/router"]);
+    assert!(index.instant_results("rou").is_none());
+    assert!(index.instant_results("title:ro").is_none());
+    assert_eq!(index.instant_results("zz"), Some(Vec::new()));
+
+    // The instant results agree with a full search
+    load_all(&mut index, "ro", &shards);
+    let full: Vec<_> = index
+        .search("ro")
+        .into_iter()
+        .map(|result| (result.url, result.anchor, result.score))
+        .collect();
+    let instant: Vec<_> = instant
+        .into_iter()
+        .map(|result| (result.url, result.anchor, result.score))
+        .collect();
+    assert_eq!(instant, full);
+}
+
+#[test]
+fn suggestions_only_need_the_dictionary() {
+    let full = super::test_index().with_ranking(crate::RankingConfig {
+        popular: vec![crate::PopularSearch {
+            query: "router".into(),
+            title: None,
+        }],
+        ..Default::default()
+    });
+    let shards: HashMap<_, _> = full.to_shards().into_iter().collect();
+    let index = ShardedIndex::from_dictionary(&shards[DICTIONARY_SHARD]).unwrap();
+
+    assert_eq!(index.complete("sig", 5), full.complete("sig", 5));
+    assert_eq!(index.did_you_mean("routter"), full.did_you_mean("routter"));
+    assert_eq!(index.popular_searches(), full.popular_searches());
+    assert_eq!(index.loaded.len(), 1);
+}
@@ -0,0 +1,204 @@
+//! Help readers find something to search for: completions for the word being typed, a similar
+//! query when a query has no results and the popular searches from the ranking.
+
+use super::{fuzzy, tokenize, NativeIndex, Query};
+use crate::DocumentMatch;
+
+impl NativeIndex {
+    /// Complete the last word of a query with the words in the index that start with it, most
+    /// common first. Returns the whole query with the last word completed. Nothing is completed
+    /// after a space, since the last word is finished
+    pub fn complete(&self, query: &str, limit: usize) -> Vec<String> {
+        let Some(last) = tokenize(query, false).pop() else {
+            return Vec::new();
+        };
+        if last.end != query.len() {
+            return Vec::new();
+        }
+
+        let mut words: Vec<_> = self
+            .terms
+            .iter()
+            .filter(|term| term.word.starts_with(&last.text) && term.word != last.text)
+            .filter(|term| {
+                !self
+                    .tokenizer
+                    .is_stop_word(self.tokenizer.language, &term.word)
+            })
+            .collect();
+        words.sort_by(|a, b| {
+            b.document_frequency
+                .cmp(&a.document_frequency)
+                .then(a.word.cmp(&b.word))
+        });
+        words.dedup_by(|a, b| a.word == b.word);
+
+        words
+            .into_iter()
+            .take(limit)
+            .map(|term| format!("{}{}", &query[..last.start], term.word))
+            .collect()
+    }
+
+    /// Suggest a similar query for a query without results. Every word that isn't in the index is
+    /// replaced with the closest word that is, allowing one more typo than a search does. Returns
+    /// `None` if every word is in the index or no close words were found
+    pub fn did_you_mean(&self, query: &str) -> Option<String> {
+        let mut suggestion = String::new();
+        let mut end = 0;
+        for token in tokenize(query, false) {
+            let known = self.languages.iter().any(|language| {
+                self.tokenizer.is_stop_word(*language, &token.text)
+                    || !self.expand(&token.text, *language, true, false).is_empty()
+            });
+            if known {
+                continue;
+            }
+
+            let max_typos = fuzzy::max_typos(token.text.chars().count()) + 1;
+            let closest = self
+                .terms
+                .iter()
+                .filter_map(|term| {
+                    let typos = fuzzy::edit_distance(&token.text, &term.word, max_typos)?;
+                    Some((typos, term))
+                })
+                .min_by(|(a_typos, a), (b_typos, b)| {
+                    a_typos
+                        .cmp(b_typos)
+                        .then(b.document_frequency.cmp(&a.document_frequency))
+                        .then(a.word.cmp(&b.word))
+                });
+            if let Some((_, term)) = closest {
+                suggestion.push_str(&query[end..token.start]);
+                suggestion.push_str(&term.word);
+                end = token.end;
+            }
+        }
+
+        if end == 0 {
+            return None;
+        }
+        suggestion.push_str(&query[end..]);
+        Some(suggestion)
+    }
+
+    /// The popular searches from the ranking, in order. Each one links to the document that was
+    /// the best result for its query when the index was built
+    pub fn popular_searches(&self) -> Vec<DocumentMatch> {
+        self.popular
+            .iter()
+            .map(|(title, document)| {
+                let document = &self.documents[*document as usize];
+                DocumentMatch {
+                    url: document.url.clone(),
+                    anchor: document.anchor.clone(),
+                    breadcrumb: document.breadcrumb.clone(),
+                    title: title.clone(),
+                    excerpts: Vec::new(),
+                    matches: Vec::new(),
+                    score: 0,
+                    example: None,
+                }
+            })
+            .collect()
+    }
+
+    /// Find the best result for each popular search in the ranking
+    pub(super) fn find_popular(&self) -> Vec<(String, u32)> {
+        let mut popular = Vec::new();
+        for search in &self.ranking.popular {
+            let best = self
+                .ranked_documents(&Query::parse(&search.query), true)
+                .into_iter()
+                .next();
+            match best {
+                Some((document, _)) => {
+                    let title = search
+                        .title
+                        .clone()
+                        .unwrap_or_else(|| self.documents[document as usize].title.clone());
+                    popular.push((title, document));
+                }
+                None => log::warn!("The popular search {:?} has no results", search.query),
+            }
+        }
+        popular
+    }
+}
+
+#[cfg(test)]
+fn suggestion_index() -> NativeIndex {
+    use super::Document;
+    use crate::{PopularSearch, RankingConfig};
+
+    NativeIndex::new([
+        Document {
+            url: "/signals".into(),
+            title: "Signals".into(),
+            body: "Signals are reactive. Read a signal with signal.read()".into(),
+            ..Default::default()
+        },
+        Document {
+            url: "/router".into(),
+            title: "Router".into(),
+            body: "The router renders the component for each route.".into(),
+            ..Default::default()
+        },
+    ])
+    .with_ranking(RankingConfig {
+        popular: vec![
+            PopularSearch {
+                query: "router".into(),
+                title: Some("Routing".into()),
+            },
+            PopularSearch {
+                query: "signal".into(),
+                title: None,
+            },
+            PopularSearch {
+                query: "missing".into(),
+                title: None,
+            },
+        ],
+        ..Default::default()
+    })
+}
+
+#[test]
+fn complete_last_word() {
+    let index = suggestion_index();
+    // Each term is completed with its most common spelling
+    assert_eq!(index.complete("sig", 5), ["signal"]);
+    assert_eq!(index.complete("read a Sig", 1), ["read a signal"]);
+    assert_eq!(index.complete("rout", 5), ["route", "router"]);
+    // A finished word isn't completed
+    assert!(index.complete("sig ", 5).is_empty());
+    // Stop words aren't suggested
+    assert!(index.complete("th", 5).is_empty());
+}
+
+#[test]
+fn did_you_mean() {
+    let index = suggestion_index();
+    // Searches allow one typo in a word this long, but suggestions allow two
+    assert_eq!(
+        index.did_you_mean("-sgnial renders").as_deref(),
+        Some("-signal renders")
+    );
+    assert_eq!(index.did_you_mean("router"), None);
+    assert_eq!(index.did_you_mean("xyzzyq"), None);
+}
+
+#[test]
+fn popular_searches() {
+    let index = suggestion_index();
+    let popular = NativeIndex::from_bytes(&index.to_bytes())
+        .unwrap()
+        .popular_searches();
+    let popular: Vec<_> = popular
+        .iter()
+        .map(|result| (result.title.as_str(), result.url.as_str()))
+        .collect();
+    assert_eq!(popular, [("Routing", "/router"), ("Signals", "/signals")]);
+}
@@ -0,0 +1,150 @@
+/// A normalized word and the byte range it came from in the original text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Token {
+    pub text: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Split text into lowercase word tokens.
+///
+/// Words are runs of alphanumeric characters and underscores, so identifiers like
+/// `use_signal` stay together. When `split_identifiers` is set, each part of an
+/// identifier is also emitted on its own so searching for `signal` finds `use_signal`.
+///
+/// Chinese, Japanese and Korean text isn't separated by spaces, so runs of those characters are
+/// split into overlapping pairs of characters instead.
+pub(crate) fn tokenize(text: &str, split_identifiers: bool) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut word_start = None;
+    let mut cjk_run = Vec::new();
+
+    for (index, c) in text
+        .char_indices()
+        .chain(std::iter::once((text.len(), ' ')))
+    {
+        let is_cjk = is_cjk(c);
+        let is_word_char = !is_cjk && (c.is_alphanumeric() || c == '_');
+        if let (Some(start), false) = (word_start, is_word_char) {
+            push_word(&mut tokens, text, start, index, split_identifiers);
+            word_start = None;
+        }
+        if !is_cjk && !cjk_run.is_empty() {
+            push_cjk_run(&mut tokens, text, &cjk_run, index);
+            cjk_run.clear();
+        }
+
+        if is_cjk {
+            cjk_run.push(index);
+        } else if is_word_char && word_start.is_none() {
+            word_start = Some(index);
+        }
+    }
+
+    tokens
+}
+
+/// Returns true for the ideographs, kana and hangul of Chinese, Japanese and Korean text
+fn is_cjk(c: char) -> bool {
+    matches!(c as u32,
+        0x3040..=0x30FF // Hiragana and Katakana
+        | 0x3400..=0x4DBF // CJK Unified Ideographs Extension A
+        | 0x4E00..=0x9FFF // CJK Unified Ideographs
+        | 0xAC00..=0xD7AF // Hangul Syllables
+        | 0xF900..=0xFAFF // CJK Compatibility Ideographs
+        | 0x20000..=0x2FA1F // Supplementary Ideographic Plane
+    )
+}
+
+/// Push every pair of neighboring characters in a run of CJK characters that start at `starts`
+/// and end at `end`. A single character is pushed on its own
+fn push_cjk_run(tokens: &mut Vec<Token>, text: &str, starts: &[usize], end: usize) {
+    let ends = starts.iter().skip(1).copied().chain(std::iter::once(end));
+    let chars: Vec<(usize, usize)> = starts.iter().copied().zip(ends).collect();
+    if let [(start, end)] = chars.as_slice() {
+        tokens.push(Token {
+            text: text[*start..*end].to_string(),
+            start: *start,
+            end: *end,
+        });
+        return;
+    }
+    for pair in chars.windows(2) {
+        let (start, end) = (pair[0].0, pair[1].1);
+        tokens.push(Token {
+            text: text[start..end].to_string(),
+            start,
+            end,
+        });
+    }
+}
+
+fn push_word(tokens: &mut Vec<Token>, text: &str, start: usize, end: usize, split: bool) {
+    let word = &text[start..end];
+    let trimmed = word.trim_matches('_');
+    if trimmed.is_empty() {
+        return;
+    }
+    let start = start + (word.len() - word.trim_start_matches('_').len());
+    let end = start + trimmed.len();
+
+    tokens.push(Token {
+        text: trimmed.to_lowercase(),
+        start,
+        end,
+    });
+
+    if split && trimmed.contains('_') {
+        let mut offset = start;
+        for part in trimmed.split('_') {
+            if !part.is_empty() {
+                tokens.push(Token {
+                    text: part.to_lowercase(),
+                    start: offset,
+                    end: offset + part.len(),
+                });
+            }
+            offset += part.len() + 1;
+        }
+    }
+}
+
+#[test]
+fn tokenize_identifiers() {
+    let tokens = tokenize("Call use_signal() to create a Signal.", true);
+    let words: Vec<_> = tokens.iter().map(|token| token.text.as_str()).collect();
+    assert_eq!(
+        words,
+        [
+            "call",
+            "use_signal",
+            "use",
+            "signal",
+            "to",
+            "create",
+            "a",
+            "signal"
+        ]
+    );
+    let signal = &tokens[3];
+    assert_eq!(
+        &"Call use_signal() to create a Signal."[signal.start..signal.end],
+        "signal"
+    );
+}
+
+#[test]
+fn tokenize_cjk() {
+    let text = "Dioxus的信号 是响应式";
+    let tokens = tokenize(text, true);
+    let words: Vec<_> = tokens.iter().map(|token| token.text.as_str()).collect();
+    assert_eq!(words, ["dioxus", "的信", "信号", "是响", "响应", "应式"]);
+    let last = tokens.last().unwrap();
+    assert_eq!(&text[last.start..last.end], "应式");
+
+    let words: Vec<_> = tokenize("信 signal", true)
+        .into_iter()
+        .map(|token| token.text)
+        .collect();
+    assert_eq!(words, ["信", "signal"]);
+}
@@ -0,0 +1,193 @@
+use serde::{Deserialize, Serialize};
+
+use crate::SearchError;
+
+/// How the results of a native index are ranked. The ranking is stored in the index, so results
+/// are ranked the same way when the index is loaded in the browser.
+///
+/// The ranking can be loaded from a TOML file:
+///
+/// ```toml
+/// [boost]
+/// title = 2.0
+/// hierarchy = 2.0
+///
+/// [[pinned]]
+/// query = "router"
+/// url = "/learn/{{ version }}/essentials/router/"
+///
+/// [[popular]]
+/// title = "Routing"
+/// query = "router"
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RankingConfig {
+    pub boost: Boosts,
+    /// Results that are always shown first for a query, in order
+    pub pinned: Vec<PinnedResult>,
+    /// Searches to suggest before anything is typed, in order
+    pub popular: Vec<PopularSearch>,
+}
+
+/// Factors the score of a match is multiplied by. A boost of `1.0` has no effect
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Boosts {
+    /// Matches in the title of a page or its front matter keywords
+    pub title: f32,
+    /// Matches in the heading a section starts at
+    pub heading: f32,
+    /// Matches in the text of a page
+    pub body: f32,
+    /// Matches in code blocks and inline code
+    pub code: f32,
+    /// Pages at the top of the table of contents are boosted by this factor. Pages nested
+    /// further down get less of the boost
+    pub hierarchy: f32,
+}
+
+impl Default for Boosts {
+    fn default() -> Self {
+        Self {
+            title: 2.0,
+            heading: 1.5,
+            body: 1.0,
+            code: 1.0,
+            hierarchy: 1.0,
+        }
+    }
+}
+
+/// A result that is shown first for a query
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PinnedResult {
+    /// The query the result is pinned for. Queries are compared after they are parsed, so case and
+    /// extra spaces don't matter
+    pub query: String,
+    /// The url of the page, with `#anchor` to pin a section of the page. `{{ version }}` is
+    /// replaced by [`RankingConfig::with_version`]
+    pub url: String,
+}
+
+/// A search to suggest before anything is typed. It links to the best result for its query, which
+/// is found when the index is built so the same list works for every version of a book
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PopularSearch {
+    /// The query to find the page with. Pin a result for the query to pick the page
+    pub query: String,
+    /// The text to show for the search, or `None` to show the title of the page
+    #[serde(default)]
+    pub title: Option<String>,
+}
+
+impl PinnedResult {
+    /// The url and anchor of the pinned page
+    pub(crate) fn location(&self) -> (&str, Option<&str>) {
+        match self.url.split_once('#') {
+            Some((url, anchor)) => (url, Some(anchor)),
+            None => (&self.url, None),
+        }
+    }
+}
+
+impl RankingConfig {
+    /// Parse a ranking from TOML
+    pub fn from_toml(toml: &str) -> Result<Self, SearchError> {
+        toml::from_str(toml).map_err(|err| SearchError::new(format!("Invalid ranking: {err}")))
+    }
+
+    /// Pin results in one version of a book by replacing `{{ version }}` in the urls of the
+    /// pinned results. Pins to pages that version doesn't have are dropped when the index is built
+    pub fn with_version(mut self, version: &str) -> Self {
+        for pin in &mut self.pinned {
+            pin.url = pin.url.replace("{{ version }}", version);
+        }
+        self
+    }
+
+    /// Use the `boost-title`, `boost-hierarchy` and `boost-paragraph` settings from the
+    /// `[output.html.search]` table of a `book.toml`. Settings the book doesn't have are left
+    /// unchanged
+    pub fn with_book_config(mut self, book_toml: &str) -> Result<Self, SearchError> {
+        #[derive(Deserialize, Default)]
+        struct Book {
+            #[serde(default)]
+            output: Output,
+        }
+        #[derive(Deserialize, Default)]
+        struct Output {
+            #[serde(default)]
+            html: Html,
+        }
+        #[derive(Deserialize, Default)]
+        struct Html {
+            #[serde(default)]
+            search: Search,
+        }
+        #[derive(Deserialize, Default)]
+        #[serde(rename_all = "kebab-case")]
+        struct Search {
+            boost_title: Option<f32>,
+            boost_hierarchy: Option<f32>,
+            boost_paragraph: Option<f32>,
+        }
+
+        let book: Book = toml::from_str(book_toml)
+            .map_err(|err| SearchError::new(format!("Invalid book.toml: {err}")))?;
+        let search = book.output.html.search;
+        if let Some(title) = search.boost_title {
+            // mdbook boosts every heading like the title
+            self.boost.title = title;
+            self.boost.heading = title;
+        }
+        if let Some(hierarchy) = search.boost_hierarchy {
+            self.boost.hierarchy = hierarchy;
+        }
+        if let Some(paragraph) = search.boost_paragraph {
+            self.boost.body = paragraph;
+        }
+        Ok(self)
+    }
+}
+
+#[test]
+fn parse_ranking() {
+    let ranking = RankingConfig::from_toml(
+        r#"
+        [boost]
+        heading = 3
+
+        [[pinned]]
+        query = "Router"
+        url = "/router#links"
+
+        [[pinned]]
+        query = "signal"
+        url = "/learn/{{ version }}/signals"
+
+        [[popular]]
+        query = "router"
+        title = "Routing"
+        "#,
+    )
+    .unwrap();
+    assert_eq!(ranking.boost.heading, 3.0);
+    assert_eq!(ranking.boost.title, Boosts::default().title);
+    assert_eq!(ranking.pinned[0].location(), ("/router", Some("links")));
+    assert_eq!(ranking.popular[0].title.as_deref(), Some("Routing"));
+
+    let ranking = ranking.with_version("0.6");
+    assert_eq!(ranking.pinned[0].url, "/router#links");
+    assert_eq!(ranking.pinned[1].url, "/learn/0.6/signals");
+
+    let ranking = ranking
+        .with_book_config("[output.html.search]\nboost-title = 4\nboost-hierarchy = 2\n")
+        .unwrap();
+    assert_eq!(ranking.boost.title, 4.0);
+    assert_eq!(ranking.boost.heading, 4.0);
+    assert_eq!(ranking.boost.hierarchy, 2.0);
+    assert_eq!(ranking.boost.body, 1.0);
+
+    assert!(RankingConfig::from_toml("[boost]\ntitle = \"high\"").is_err());
+}
@@ -0,0 +1,118 @@
+use crate::{CodeExample, Document, Language};
+
+/// The deepest heading level that starts a new section. This matches the `heading-split-level`
+/// our books used with stork
+pub(crate) const SECTION_SPLIT_LEVEL: usize = 3;
+
+/// Splits a page into a document for the top of the page and one for each section heading, so
+/// results can link straight to the heading that matched
+pub(crate) struct SectionSplitter {
+    sections: Vec<Document>,
+    current: Document,
+    /// The level and title of the page and each heading above the current section
+    parents: Vec<(usize, String)>,
+}
+
+impl SectionSplitter {
+    pub fn new(url: impl Into<String>, title: impl Into<String>) -> Self {
+        let title = title.into();
+        Self {
+            sections: Vec::new(),
+            parents: vec![(1, title.clone())],
+            current: Document {
+                url: url.into(),
+                title,
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Returns true if a heading at this level starts a new section
+    pub fn splits_at(level: usize) -> bool {
+        (2..=SECTION_SPLIT_LEVEL).contains(&level)
+    }
+
+    /// The text of the current section
+    pub fn body(&mut self) -> &mut String {
+        &mut self.current.body
+    }
+
+    /// The code in the current section. Code should also be pushed to the body
+    pub fn code(&mut self) -> &mut String {
+        &mut self.current.code
+    }
+
+    /// Keywords for the current section that are searchable but not shown in excerpts
+    pub fn keywords(&mut self) -> &mut Vec<String> {
+        &mut self.current.keywords
+    }
+
+    /// Add a code block to the current section. The code should also be pushed to the body and
+    /// code of the section
+    pub fn add_example(&mut self, mut example: CodeExample) {
+        // Keep the indentation of the first line
+        example.code = example
+            .code
+            .trim_start_matches(['\r', '\n'])
+            .trim_end()
+            .to_string();
+        if !example.code.trim().is_empty() {
+            self.current.examples.push(example);
+        }
+    }
+
+    /// Set the language of the page. This should be called before the first section starts
+    pub fn set_language(&mut self, language: Language) {
+        self.current.language = Some(language);
+    }
+
+    /// Start a new line in the current section if it doesn't already end with one
+    pub fn new_line(&mut self) {
+        let body = self.body();
+        if !body.is_empty() && !body.ends_with('\n') {
+            body.push('\n');
+        }
+    }
+
+    pub fn start_section(&mut self, level: usize, title: &str, anchor: Option<String>) {
+        let title = title.trim().to_string();
+
+        // The page title at level 1 is never popped
+        while self
+            .parents
+            .last()
+            .is_some_and(|(parent, _)| *parent >= level)
+        {
+            self.parents.pop();
+        }
+
+        let section = Document {
+            url: self.current.url.clone(),
+            anchor,
+            breadcrumb: self
+                .parents
+                .iter()
+                .map(|(_, title)| title.clone())
+                .collect(),
+            title: title.clone(),
+            language: self.current.language,
+            ..Default::default()
+        };
+        self.parents.push((level, title));
+
+        let finished = std::mem::replace(&mut self.current, section);
+        self.push_section(finished);
+    }
+
+    pub fn finish(mut self) -> Vec<Document> {
+        let last = std::mem::take(&mut self.current);
+        self.push_section(last);
+        self.sections
+    }
+
+    fn push_section(&mut self, mut section: Document) {
+        section.body = section.body.trim().to_string();
+        section.code = section.code.trim().to_string();
+        self.sections.push(section);
+    }
+}
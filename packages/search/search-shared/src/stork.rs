@@ -0,0 +1,78 @@
+//! Support for loading indexes that were built with stork before the native backend existed
+
+use bytes::Bytes;
+
+use crate::{DocumentMatch, Excerpt, SearchBackend, SearchError};
+
+/// A search index built by stork
+pub struct StorkIndex {
+    name: String,
+    bytes: Bytes,
+}
+
+impl StorkIndex {
+    /// Register a stork index with the name so it can be searched
+    pub fn load(name: &str, bytes: Bytes) -> Result<Self, SearchError> {
+        let name = format!("index_{name}");
+        stork_lib::register_index(&name, bytes.clone()).map_err(SearchError::new)?;
+        Ok(Self { name, bytes })
+    }
+}
+
+impl SearchBackend for StorkIndex {
+    fn search(&self, query: &str) -> Result<Vec<DocumentMatch>, SearchError> {
+        let output = stork_lib::search_from_cache(&self.name, query).map_err(SearchError::new)?;
+
+        Ok(output
+            .results
+            .into_iter()
+            .map(|result| {
+                let excerpts = result
+                    .excerpts
+                    .into_iter()
+                    .map(|excerpt| {
+                        // Stork counts highlight ranges in chars, starting one char early
+                        let offsets: Vec<usize> = excerpt
+                            .text
+                            .char_indices()
+                            .map(|(index, _)| index)
+                            .chain(std::iter::once(excerpt.text.len()))
+                            .collect();
+                        let byte_offset =
+                            |char_index: usize| offsets[char_index.min(offsets.len() - 1)];
+                        let highlights: Vec<(usize, usize)> = excerpt
+                            .highlight_ranges
+                            .iter()
+                            .map(|range| {
+                                (
+                                    byte_offset(range.beginning.saturating_sub(1)),
+                                    byte_offset(range.end),
+                                )
+                            })
+                            .collect();
+                        let segments = crate::excerpt::highlight(&excerpt.text, &highlights);
+                        Excerpt {
+                            text: segments,
+                            score: excerpt.score,
+                        }
+                    })
+                    .collect();
+
+                DocumentMatch {
+                    url: result.entry.url,
+                    anchor: None,
+                    breadcrumb: Vec::new(),
+                    title: result.entry.title,
+                    excerpts,
+                    matches: Vec::new(),
+                    score: result.score,
+                    example: None,
+                }
+            })
+            .collect())
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        self.bytes.to_vec()
+    }
+}
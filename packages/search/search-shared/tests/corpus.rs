@@ -0,0 +1,228 @@
+//! Search the fixture book in `tests/fixtures/book` end to end
+
+use std::path::{Path, PathBuf};
+
+use dioxus_search_shared::{
+    book_page_url, BookVars, DocumentMatch, MatchKind, NativeIndex, TermMatch, TokenizerConfig,
+};
+use mdbook_shared::MdBook;
+
+fn fixture_index() -> NativeIndex {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/book");
+    let vars = BookVars::load(&path).unwrap();
+    let book = MdBook::new(path).unwrap();
+    NativeIndex::from_book(
+        &book,
+        &vars,
+        Path::new(env!("CARGO_MANIFEST_DIR")),
+        |path| book_page_url("", path),
+        TokenizerConfig::default(),
+    )
+    .unwrap()
+}
+
+/// The url and anchor of each result
+fn locations(results: &[DocumentMatch]) -> Vec<String> {
+    results
+        .iter()
+        .map(|result| match &result.anchor {
+            Some(anchor) => format!("{}#{anchor}", result.url),
+            None => result.url.clone(),
+        })
+        .collect()
+}
+
+fn corrections(result: &DocumentMatch) -> Vec<(&str, &str)> {
+    result
+        .matches
+        .iter()
+        .filter(|term_match| term_match.is_correction())
+        .map(|TermMatch { query, term, .. }| (query.as_str(), term.as_str()))
+        .collect()
+}
+
+#[test]
+fn exact_and_prefix_matches() {
+    let index = fixture_index();
+
+    let results = index.query("memo");
+    assert_eq!(locations(&results), ["/signals#memos"]);
+    assert_eq!(results[0].breadcrumb, ["Signals"]);
+    assert_eq!(results[0].matches[0].kind, MatchKind::Exact);
+
+    let results = index.query("restar");
+    assert_eq!(locations(&results), ["/resources#restarting-resources"]);
+    assert_eq!(results[0].matches[0].kind, MatchKind::Prefix);
+
+    // Front matter keywords are searchable
+    let results = index.query("properties");
+    assert_eq!(locations(&results), ["/components"]);
+}
+
+#[test]
+fn typos_are_corrected() {
+    let index = fixture_index();
+
+    let results = index.query("singal");
+    assert!(!results.is_empty());
+    assert_eq!(results[0].url, "/signals");
+    // Corrections are spelled the way the page spells them, not as the stem they were indexed as
+    assert_eq!(corrections(&results[0]), [("singal", "signals")]);
+    assert_eq!(results[0].matches[0].kind, MatchKind::Fuzzy { typos: 1 });
+
+    let results = index.query("use_resorce");
+    assert_eq!(locations(&results), ["/resources"]);
+    assert_eq!(corrections(&results[0]), [("use_resorce", "use_resource")]);
+
+    // Corrected terms are highlighted in the excerpt
+    let highlighted: Vec<_> = results[0].excerpts[0]
+        .text
+        .iter()
+        .filter(|segment| segment.highlighted)
+        .map(|segment| segment.text.as_str())
+        .collect();
+    assert_eq!(highlighted, ["use_resource", "use_resource"]);
+
+    // Short words are not corrected
+    assert!(index.query("rsz").is_empty());
+}
+
+#[test]
+fn quoted_phrases() {
+    let index = fixture_index();
+
+    // Both words appear in all of these sections, but only next to each other in some
+    let mut words = locations(&index.query("derive macro"));
+    words.sort();
+    assert_eq!(
+        words,
+        [
+            "/components#props",
+            "/components#the-component-macro",
+            "/router"
+        ]
+    );
+
+    let mut phrase = locations(&index.query("\"the derive macro\""));
+    phrase.sort();
+    assert_eq!(phrase, ["/components#props", "/router"]);
+
+    assert!(index.query("\"macro derive\"").is_empty());
+}
+
+#[test]
+fn excluded_terms() {
+    let index = fixture_index();
+
+    let all = locations(&index.query("use_signal"));
+    assert!(all.contains(&"/migration#replacing-use-state".to_string()));
+
+    let excluded = locations(&index.query("use_signal -use_state"));
+    assert!(!excluded.contains(&"/migration#replacing-use-state".to_string()));
+    assert_eq!(excluded.len(), all.len() - 1);
+
+    // Excluding a phrase only removes documents with the whole phrase
+    let excluded = locations(&index.query("router -\"old api\""));
+    assert!(excluded.contains(&"/migration#router-changes".to_string()));
+}
+
+#[test]
+fn field_scopes() {
+    let index = fixture_index();
+
+    let results = index.query("title:router");
+    assert_eq!(
+        locations(&results),
+        ["/router", "/migration#router-changes"]
+    );
+
+    // Inline code counts as code, but prose doesn't
+    let code = locations(&index.query("code:set"));
+    assert_eq!(
+        code,
+        ["/signals#writing-signals", "/migration#replacing-use-state"]
+    );
+    assert!(index.query("code:closures").is_empty());
+    assert!(!index.query("closures").is_empty());
+
+    let code = locations(&index.query("code:Routable"));
+    assert_eq!(code, ["/router"]);
+}
+
+#[test]
+fn stemming_and_stop_words() {
+    let index = fixture_index();
+
+    // Different forms of a word share a stem
+    assert_eq!(
+        locations(&index.query("restarts")),
+        ["/resources#restarting-resources"]
+    );
+    assert_eq!(
+        locations(&index.query("rerun")),
+        locations(&index.query("reruns"))
+    );
+
+    // Stop words are ignored outside of phrases
+    assert_eq!(
+        locations(&index.query("the router")),
+        locations(&index.query("router"))
+    );
+    assert!(index.query("the").is_empty());
+
+    // Pages with a language in their front matter are stemmed in that language
+    assert_eq!(locations(&index.query("atualização")), ["/sinais"]);
+    assert_eq!(locations(&index.query("componente")), ["/sinais"]);
+    assert!(index.query("para").is_empty());
+}
+
+#[test]
+fn code_examples() {
+    let index = fixture_index();
+
+    // Code blocks are searched on their own, with the file they were included from
+    let results = index.query_code("use_server_future");
+    assert_eq!(locations(&results), ["/resources#server-futures"]);
+    let example = results[0].example.as_ref().unwrap();
+    assert_eq!(example.language.as_deref(), Some("rs"));
+    assert_eq!(
+        example.file.as_deref(),
+        Some("../search-shared/tests/fixtures/examples/server_future.rs")
+    );
+
+    // The excerpt is the code with the matches highlighted
+    let excerpt = &results[0].excerpts[0];
+    let code: String = excerpt
+        .text
+        .iter()
+        .map(|segment| segment.text.as_str())
+        .collect();
+    assert!(code.starts_with("#[component]\nfn Weather() -> Element {"));
+    let highlighted: Vec<_> = excerpt
+        .text
+        .iter()
+        .filter(|segment| segment.highlighted)
+        .map(|segment| segment.text.as_str())
+        .collect();
+    assert_eq!(highlighted, ["use_server_future"]);
+
+    let results = index.query_code("onclick: move |_|");
+    assert_eq!(locations(&results), ["/resources#server-futures"]);
+
+    // Prose doesn't match in code mode, and code examples aren't returned as pages
+    assert!(index.query_code("rendered").is_empty());
+    assert!(index
+        .query("use_server_future")
+        .iter()
+        .all(|result| result.example.is_none()));
+
+    let results = index.query_code("use_signal");
+    assert_eq!(
+        locations(&results),
+        ["/signals#reading-signals", "/signals#writing-signals"]
+    );
+    assert_eq!(
+        results[0].example.as_ref().unwrap().language.as_deref(),
+        Some("rust")
+    );
+}
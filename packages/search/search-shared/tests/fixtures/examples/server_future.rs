@@ -0,0 +1,16 @@
+use dioxus::prelude::*;
+
+// ANCHOR: server_future
+#[component]
+fn Weather() -> Element {
+    let mut weather = use_server_future(get_weather)?;
+    rsx! {
+        button { onclick: move |_| weather.clear(), "Clear" }
+    }
+}
+// ANCHOR_END: server_future
+
+#[server]
+async fn get_weather() -> Result<String, ServerFnError> {
+    Ok("Sunny".to_string())
+}
@@ -0,0 +1,73 @@
+//! Check the ranking of the fixture book in `tests/fixtures/book` against the snapshot in
+//! `tests/golden/ranking.txt`.
+//!
+//! Run with `UPDATE_GOLDEN=1` to update the snapshot after an intended ranking change, and review
+//! the diff before committing it.
+
+use std::{fmt::Write, path::PathBuf};
+
+use dioxus_search_shared::{book_page_url, BookVars, NativeIndex, RankingConfig, TokenizerConfig};
+use mdbook_shared::MdBook;
+
+/// The number of results kept in the snapshot for each query
+const TOP_RESULTS: usize = 5;
+
+const QUERIES: &[&str] = &[
+    "router",
+    "routes",
+    "signal",
+    "state",
+    "derive",
+    "props",
+    "component",
+    "use_signal",
+    "task",
+    "reads",
+    "value",
+    "title:signals",
+    "code:derive",
+    "\"the derive macro\"",
+];
+
+#[test]
+fn ranking_matches_snapshot() {
+    let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let book_dir = manifest_dir.join("tests/fixtures/book");
+    let vars = BookVars::load(&book_dir).unwrap();
+    let book = MdBook::new(book_dir).unwrap();
+    let ranking =
+        std::fs::read_to_string(manifest_dir.join("tests/fixtures/ranking.toml")).unwrap();
+    let index = NativeIndex::from_book(
+        &book,
+        &vars,
+        &manifest_dir,
+        |path| book_page_url("", path),
+        TokenizerConfig::default(),
+    )
+    .unwrap()
+    .with_ranking(RankingConfig::from_toml(&ranking).unwrap());
+
+    let mut snapshot = String::new();
+    for query in QUERIES {
+        writeln!(snapshot, "{query}").unwrap();
+        for result in index.query(query).iter().take(TOP_RESULTS) {
+            let location = match &result.anchor {
+                Some(anchor) => format!("{}#{anchor}", result.url),
+                None => result.url.clone(),
+            };
+            writeln!(snapshot, "    {location}").unwrap();
+        }
+    }
+
+    let path = manifest_dir.join("tests/golden/ranking.txt");
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, snapshot).unwrap();
+        return;
+    }
+    let expected = std::fs::read_to_string(&path).unwrap_or_default();
+    assert!(
+        snapshot == expected,
+        "The ranking changed. Run with UPDATE_GOLDEN=1 to update the snapshot if this is intended\n\nExpected:\n{expected}\nFound:\n{snapshot}"
+    );
+}
@@ -0,0 +1,3 @@
+// The tests build the fixture book into an index with `load_search_index!`, which writes the index
+// to `OUT_DIR`. Cargo only sets `OUT_DIR` for crates with a build script
+fn main() {}
@@ -0,0 +1,363 @@
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, HashMap, HashSet},
+    ffi::OsString,
+    fs::File,
+    io::{BufRead, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use serde::{Deserialize, Serialize};
+
+/// A search a reader made, and the result they opened if they opened one. Events don't have
+/// anything that identifies the reader, and queries that might have personal data are dropped
+/// before they are reported
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SearchEvent {
+    /// The facet that was searched, like the version of the docs
+    #[serde(default)]
+    pub facet: Option<String>,
+    pub query: String,
+    /// The number of results the query had
+    pub results: usize,
+    /// The rank of the result the reader opened, starting at 0 for the best match
+    #[serde(default)]
+    pub clicked_rank: Option<usize>,
+}
+
+impl SearchEvent {
+    /// The number of characters of a query that are kept
+    pub const MAX_QUERY_LEN: usize = 64;
+    /// The number of characters of a facet that are kept
+    pub const MAX_FACET_LEN: usize = 32;
+
+    /// Lowercase the query, collapse its whitespace and cut it to [`SearchEvent::MAX_QUERY_LEN`]
+    /// characters. Returns `None` if the query is empty or looks like it has personal data, like
+    /// an email address or a phone number
+    pub fn anonymized(self) -> Option<Self> {
+        let query = normalize_query(&self.query)
+            .chars()
+            .take(Self::MAX_QUERY_LEN)
+            .collect::<String>();
+        if query.is_empty() || may_be_personal(&query) {
+            return None;
+        }
+        Some(Self {
+            facet: self
+                .facet
+                .map(|facet| facet.chars().take(Self::MAX_FACET_LEN).collect()),
+            query,
+            ..self
+        })
+    }
+}
+
+fn normalize_query(query: &str) -> String {
+    query
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// Email addresses and long numbers like phone numbers or ids are never part of a docs query
+fn may_be_personal(query: &str) -> bool {
+    const MAX_DIGITS: usize = 5;
+    let mut digits = 0;
+    for c in query.chars() {
+        if c == '@' {
+            return true;
+        }
+        digits = if c.is_ascii_digit() { digits + 1 } else { 0 };
+        if digits > MAX_DIGITS {
+            return true;
+        }
+    }
+    false
+}
+
+/// Reports what readers search for to a callback. Searches run on every key press, so a query
+/// is only reported once the reader stops refining it: when they search for something else,
+/// open a result or [`SearchAnalytics::flush`] is called, like when the search closes.
+///
+/// ```rust, ignore
+/// let analytics = SearchAnalytics::new(|event| send_to_server(event));
+/// analytics.searched(Some("0.7"), "use_sig", 3);
+/// analytics.searched(Some("0.7"), "use_signal", 5);
+/// // Reports `use_signal` with 5 results and the third result opened
+/// analytics.clicked(2);
+/// ```
+pub struct SearchAnalytics {
+    report: Box<dyn Fn(SearchEvent)>,
+    pending: RefCell<Option<SearchEvent>>,
+}
+
+impl SearchAnalytics {
+    pub fn new(report: impl Fn(SearchEvent) + 'static) -> Self {
+        Self {
+            report: Box::new(report),
+            pending: RefCell::new(None),
+        }
+    }
+
+    /// Record the number of results of a search. A query that extends the previous query
+    /// replaces it, and a query the previous query extends, like after pressing backspace, is
+    /// ignored. Any other query reports the previous query
+    pub fn searched(&self, facet: Option<&str>, query: &str, results: usize) {
+        let query = normalize_query(query);
+        if query.is_empty() {
+            return;
+        }
+
+        let previous = self.pending.borrow_mut().take();
+        if let Some(previous) = previous {
+            if previous.facet.as_deref() == facet {
+                if previous.query.starts_with(&query) {
+                    *self.pending.borrow_mut() = Some(previous);
+                    return;
+                }
+                if !query.starts_with(&previous.query) {
+                    self.report(previous);
+                }
+            } else {
+                self.report(previous);
+            }
+        }
+
+        *self.pending.borrow_mut() = Some(SearchEvent {
+            facet: facet.map(str::to_string),
+            query,
+            results,
+            clicked_rank: None,
+        });
+    }
+
+    /// Report the last search with the rank of the result the reader opened
+    pub fn clicked(&self, rank: usize) {
+        let pending = self.pending.borrow_mut().take();
+        if let Some(mut event) = pending {
+            event.clicked_rank = Some(rank);
+            self.report(event);
+        }
+    }
+
+    /// Report the last search if it hasn't been reported yet
+    pub fn flush(&self) {
+        let pending = self.pending.borrow_mut().take();
+        if let Some(event) = pending {
+            self.report(event);
+        }
+    }
+
+    fn report(&self, event: SearchEvent) {
+        if let Some(event) = event.anonymized() {
+            (self.report)(event);
+        }
+    }
+}
+
+impl Drop for SearchAnalytics {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+/// An append-only log of search events on a server, stored as one JSON event per line. Events
+/// are anonymized again before they are written, so the log never has more than the query, the
+/// facet, the number of results and the rank of the opened result.
+///
+/// Events come from readers, so the log only accepts the facets it is told about and is rotated
+/// once it reaches [`AnalyticsLog::with_max_size`]. The previous log is kept at
+/// [`AnalyticsLog::rotated_path`], so the log never takes more than twice the maximum size
+pub struct AnalyticsLog {
+    path: PathBuf,
+    /// The facets events may have, or `None` to accept any facet
+    facets: Option<HashSet<String>>,
+    max_size: u64,
+    file: Mutex<LogFile>,
+}
+
+/// The file being appended to and its size
+struct LogFile {
+    file: File,
+    len: u64,
+}
+
+impl AnalyticsLog {
+    /// The size a log is rotated at by default
+    pub const DEFAULT_MAX_SIZE: u64 = 16 * 1024 * 1024;
+
+    /// Open the log at the path, creating it if it doesn't exist
+    pub fn open(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let file = open_append(path)?;
+        let len = file.metadata()?.len();
+        Ok(Self {
+            path: path.to_path_buf(),
+            facets: None,
+            max_size: Self::DEFAULT_MAX_SIZE,
+            file: Mutex::new(LogFile { file, len }),
+        })
+    }
+
+    /// Only accept events without a facet or with one of these facets, like the versions of the
+    /// docs that have a search index
+    pub fn with_facets(mut self, facets: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.facets = Some(facets.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Rotate the log once it is larger than this many bytes
+    pub fn with_max_size(mut self, max_size: u64) -> Self {
+        self.max_size = max_size;
+        self
+    }
+
+    /// Where the log at the path is moved when it is rotated, like `search_analytics.jsonl.1`
+    pub fn rotated_path(path: impl AsRef<Path>) -> PathBuf {
+        let mut rotated = OsString::from(path.as_ref());
+        rotated.push(".1");
+        PathBuf::from(rotated)
+    }
+
+    /// Append the event to the log. Events that might have personal data are dropped, and events
+    /// with a facet the log doesn't accept are an [`std::io::ErrorKind::InvalidInput`] error
+    pub fn record(&self, event: SearchEvent) -> std::io::Result<()> {
+        if let (Some(facets), Some(facet)) = (&self.facets, &event.facet) {
+            if !facets.contains(facet) {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("Unknown search facet {facet:?}"),
+                ));
+            }
+        }
+        let Some(event) = event.anonymized() else {
+            return Ok(());
+        };
+        let mut line = serde_json::to_string(&event)?;
+        line.push('\n');
+
+        let mut log = self.file.lock().unwrap_or_else(|err| err.into_inner());
+        if log.len > 0 && log.len + line.len() as u64 > self.max_size {
+            std::fs::rename(&self.path, Self::rotated_path(&self.path))?;
+            *log = LogFile {
+                file: open_append(&self.path)?,
+                len: 0,
+            };
+        }
+        // Each event is written in one call so events from concurrent requests don't interleave
+        log.file.write_all(line.as_bytes())?;
+        log.len += line.len() as u64;
+        Ok(())
+    }
+}
+
+fn open_append(path: &Path) -> std::io::Result<File> {
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+}
+
+/// How often a query was searched for in a facet
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct QueryStats {
+    pub query: String,
+    /// The number of times the query was searched for
+    pub searches: usize,
+    /// The number of searches that had no results
+    pub zero_results: usize,
+    /// The number of searches where a result was opened
+    pub clicks: usize,
+    rank_total: usize,
+}
+
+impl QueryStats {
+    /// The average rank of the results that were opened, starting at 0 for the best match
+    pub fn average_rank(&self) -> Option<f32> {
+        (self.clicks > 0).then(|| self.rank_total as f32 / self.clicks as f32)
+    }
+}
+
+/// Search events aggregated by facet and query, like the events in an [`AnalyticsLog`]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SearchReport {
+    facets: BTreeMap<String, HashMap<String, QueryStats>>,
+}
+
+impl SearchReport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Aggregate the events in a log written by [`AnalyticsLog`]. Lines that aren't events, like
+    /// a line that was cut off when the server stopped, are skipped
+    pub fn from_jsonl(reader: impl BufRead) -> std::io::Result<Self> {
+        let mut report = Self::new();
+        for line in reader.lines() {
+            if let Ok(event) = serde_json::from_str::<SearchEvent>(&line?) {
+                report.add(event);
+            }
+        }
+        Ok(report)
+    }
+
+    /// Add an event to the report. Events without a facet are counted under an empty facet
+    pub fn add(&mut self, event: SearchEvent) {
+        let stats = self
+            .facets
+            .entry(event.facet.unwrap_or_default())
+            .or_default()
+            .entry(event.query.clone())
+            .or_insert_with(|| QueryStats {
+                query: event.query,
+                ..Default::default()
+            });
+        stats.searches += 1;
+        if event.results == 0 {
+            stats.zero_results += 1;
+        }
+        if let Some(rank) = event.clicked_rank {
+            stats.clicks += 1;
+            stats.rank_total += rank;
+        }
+    }
+
+    /// Every facet with events, sorted
+    pub fn facets(&self) -> impl Iterator<Item = &str> {
+        self.facets.keys().map(String::as_str)
+    }
+
+    /// The queries of the facet, most searched first
+    pub fn top_queries(&self, facet: &str, limit: usize) -> Vec<&QueryStats> {
+        self.sorted_queries(facet, |stats| stats.searches, limit)
+    }
+
+    /// The queries of the facet that had no results, most often first. These are the pages to
+    /// write next
+    pub fn zero_result_queries(&self, facet: &str, limit: usize) -> Vec<&QueryStats> {
+        self.sorted_queries(facet, |stats| stats.zero_results, limit)
+    }
+
+    fn sorted_queries(
+        &self,
+        facet: &str,
+        count: impl Fn(&QueryStats) -> usize,
+        limit: usize,
+    ) -> Vec<&QueryStats> {
+        let mut queries: Vec<_> = self
+            .facets
+            .get(facet)
+            .into_iter()
+            .flat_map(HashMap::values)
+            .filter(|stats| count(stats) > 0)
+            .collect();
+        queries.sort_by(|a, b| count(b).cmp(&count(a)).then_with(|| a.query.cmp(&b.query)));
+        queries.truncate(limit);
+        queries
+    }
+}
@@ -0,0 +1,279 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+};
+
+use dioxus_search_shared::{
+    CodeSource, DocumentMatch, Excerpt, SearchError, SearchIndex, SearchMode, TermMatch,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{multi::merge_facets, FacetCount};
+
+/// A headless search API for a server. It searches the indexes of every version of the docs like
+/// [`crate::MultiSearchIndex`], but returns urls instead of routes so the results can be sent as
+/// JSON to clients that don't know the routes, like editor plugins or chat bots.
+///
+/// Responses are cached per query, so repeated queries don't search the indexes again:
+///
+/// ```rust, ignore
+/// let api = SearchApi::new()
+///     .with_index("0.7", SearchIndex::<Route>::from_compressed("searchable_0_7", &bytes_0_7)?)
+///     .with_index("0.6", SearchIndex::<Route>::from_compressed("searchable_0_6", &bytes_0_6)?);
+///
+/// let response = api.search(&SearchRequest::new("use_signal").in_version("0.7"))?;
+/// ```
+pub struct SearchApi<R> {
+    indexes: Vec<(String, SearchIndex<R>)>,
+    cache: Mutex<ResponseCache>,
+}
+
+impl<R> Default for SearchApi<R> {
+    fn default() -> Self {
+        Self {
+            indexes: Vec::new(),
+            cache: Mutex::new(ResponseCache::new(SearchApi::<R>::DEFAULT_CACHE_SIZE)),
+        }
+    }
+}
+
+impl<R> SearchApi<R> {
+    /// The number of responses that are cached by default
+    pub const DEFAULT_CACHE_SIZE: usize = 1024;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an index with a facet. Results from indexes added first win ties
+    pub fn insert(&mut self, facet: impl Into<String>, index: SearchIndex<R>) {
+        self.indexes.push((facet.into(), index));
+        self.cache_mut().clear();
+    }
+
+    /// Add an index with a facet. Results from indexes added first win ties
+    pub fn with_index(mut self, facet: impl Into<String>, index: SearchIndex<R>) -> Self {
+        self.insert(facet, index);
+        self
+    }
+
+    /// Set the number of responses that are cached. The oldest response is dropped when the cache
+    /// is full
+    pub fn with_cache_size(self, size: usize) -> Self {
+        *self.cache_mut() = ResponseCache::new(size);
+        self
+    }
+
+    /// The facets of every index in the order they were added
+    pub fn facets(&self) -> impl Iterator<Item = &str> {
+        self.indexes.iter().map(|(facet, _)| facet.as_str())
+    }
+
+    /// Search the index of the requested version, or every index if the request doesn't have a
+    /// version. Results from several indexes are merged like [`crate::MultiSearchResults::merge`]
+    pub fn search(&self, request: &SearchRequest) -> Result<Arc<SearchResponse>, SearchError> {
+        let request = request.normalized();
+        if let Some(response) = self.cache_mut().get(&request) {
+            return Ok(response);
+        }
+
+        let indexes = self
+            .indexes
+            .iter()
+            .filter(|(facet, _)| request.version.as_ref().is_none_or(|v| v == facet))
+            .collect::<Vec<_>>();
+        if let Some(version) = &request.version {
+            if indexes.is_empty() {
+                return Err(SearchError::new(format!("Unknown version {version}")));
+            }
+        }
+
+        let mut facet_matches = Vec::new();
+        let mut did_you_mean = None;
+        for (facet, index) in &indexes {
+            let matches = if request.q.is_empty() {
+                Vec::new()
+            } else {
+                index.search_documents(&request.q, request.mode)?
+            };
+            if matches.is_empty() && did_you_mean.is_none() {
+                did_you_mean = index.did_you_mean(&request.q);
+            }
+            facet_matches.push((facet.clone(), matches));
+        }
+
+        let mut response = SearchResponse::merge(request.q.clone(), facet_matches);
+        if response.results.is_empty() {
+            response.did_you_mean = did_you_mean;
+        }
+        let response = Arc::new(response);
+        self.cache_mut().insert(request, response.clone());
+        Ok(response)
+    }
+
+    fn cache_mut(&self) -> std::sync::MutexGuard<'_, ResponseCache> {
+        // The cache is only a map of responses, so it is still valid if another thread panicked
+        self.cache.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+/// The query string of a request to the search API, like `?q=use_signal&version=0.7&mode=code`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct SearchRequest {
+    /// The text of the query
+    pub q: String,
+    /// The facet of the index to search. Every index is searched if this is `None`
+    #[serde(default)]
+    pub version: Option<String>,
+    /// Search pages or code examples
+    #[serde(default)]
+    pub mode: SearchMode,
+}
+
+impl SearchRequest {
+    /// Search the pages of every index
+    pub fn new(q: impl Into<String>) -> Self {
+        Self {
+            q: q.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Only search the index with this facet
+    pub fn in_version(mut self, version: impl Into<String>) -> Self {
+        self.version = Some(version.into());
+        self
+    }
+
+    /// Search pages or code examples
+    pub fn in_mode(mut self, mode: SearchMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// The request with the whitespace in the query collapsed, so queries that only differ in
+    /// spacing share a cached response
+    fn normalized(&self) -> Self {
+        Self {
+            q: self.q.split_whitespace().collect::<Vec<_>>().join(" "),
+            version: self.version.clone().filter(|version| !version.is_empty()),
+            mode: self.mode,
+        }
+    }
+}
+
+/// The JSON response of the search API
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SearchResponse {
+    /// The query after whitespace is collapsed
+    pub query: String,
+    /// Every result, best match first
+    pub results: Vec<SearchApiResult>,
+    /// The number of results for each facet that was searched
+    pub facets: Vec<FacetCount>,
+    /// A similar query with results if this query doesn't have any
+    #[serde(default)]
+    pub did_you_mean: Option<String>,
+}
+
+impl SearchResponse {
+    /// Merge the matches from each index into one list, best match first, the same way as
+    /// [`crate::MultiSearchResults::merge`]
+    fn merge(query: String, facet_matches: Vec<(String, Vec<DocumentMatch>)>) -> Self {
+        let (results, facets) = merge_facets(facet_matches, |result| result.score);
+        Self {
+            query,
+            results: results
+                .into_iter()
+                .map(|(facet, score, result)| SearchApiResult::new(facet, score, result))
+                .collect(),
+            facets,
+            did_you_mean: None,
+        }
+    }
+}
+
+/// A result of the search API. It has the same fields as a [`dioxus_search_shared::SearchResult`],
+/// but with the url of the page instead of its route
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SearchApiResult {
+    /// The facet of the index the result came from
+    pub facet: String,
+    /// The url of the matched page, including the anchor of the section
+    pub url: String,
+    /// The id of the heading the matched section starts at, or `None` for the top of the page
+    pub anchor: Option<String>,
+    /// The titles of the page and headings the matched section is nested under
+    pub breadcrumb: Vec<String>,
+    /// The title of the page or the text of the matched section heading
+    pub title: String,
+    pub excerpts: Vec<Excerpt>,
+    /// The terms each word of the query matched, including any typos that were corrected
+    pub matches: Vec<TermMatch>,
+    /// The score relative to the best result from the same index, up to
+    /// [`crate::MultiSearchResults::MAX_SCORE`]
+    pub score: usize,
+    /// Where the code example came from for code searches
+    pub example: Option<CodeSource>,
+}
+
+impl SearchApiResult {
+    fn new(facet: String, score: usize, result: DocumentMatch) -> Self {
+        let url = match &result.anchor {
+            Some(anchor) => format!("{}#{anchor}", result.url),
+            None => result.url,
+        };
+        Self {
+            facet,
+            url,
+            anchor: result.anchor,
+            breadcrumb: result.breadcrumb,
+            title: result.title,
+            excerpts: result.excerpts,
+            matches: result.matches,
+            score,
+            example: result.example,
+        }
+    }
+}
+
+/// The most recent responses of a [`SearchApi`]
+struct ResponseCache {
+    size: usize,
+    responses: HashMap<SearchRequest, Arc<SearchResponse>>,
+    /// The cached requests, oldest first
+    order: VecDeque<SearchRequest>,
+}
+
+impl ResponseCache {
+    fn new(size: usize) -> Self {
+        Self {
+            size,
+            responses: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    fn get(&self, request: &SearchRequest) -> Option<Arc<SearchResponse>> {
+        self.responses.get(request).cloned()
+    }
+
+    fn insert(&mut self, request: SearchRequest, response: Arc<SearchResponse>) {
+        // Another thread may have answered the same request while this one was searching
+        if self.size == 0 || self.responses.contains_key(&request) {
+            return;
+        }
+        while self.order.len() >= self.size {
+            if let Some(oldest) = self.order.pop_front() {
+                self.responses.remove(&oldest);
+            }
+        }
+        self.order.push_back(request.clone());
+        self.responses.insert(request, response);
+    }
+
+    fn clear(&mut self) {
+        self.responses.clear();
+        self.order.clear();
+    }
+}
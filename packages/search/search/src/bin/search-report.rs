@@ -0,0 +1,69 @@
+//! List the queries readers searched for and didn't find in each facet of a search analytics
+//! log, so you know which pages to write. The log rotated out by `AnalyticsLog` is read too if
+//! it is still there:
+//!
+//! ```sh
+//! cargo run -p dioxus-search --bin search-report -- search_analytics.jsonl 20
+//! ```
+
+use std::{
+    io::{BufReader, Read},
+    process::ExitCode,
+};
+
+use dioxus_search::{AnalyticsLog, SearchReport};
+
+/// The number of queries listed for each facet if no limit is passed
+const DEFAULT_LIMIT: usize = 20;
+
+fn main() -> ExitCode {
+    let mut args = std::env::args().skip(1);
+    let Some(path) = args.next() else {
+        eprintln!("Usage: search-report <search_analytics.jsonl> [limit]");
+        return ExitCode::FAILURE;
+    };
+    let limit = match args.next().map(|limit| limit.parse::<usize>()) {
+        None => DEFAULT_LIMIT,
+        Some(Ok(limit)) => limit,
+        Some(Err(err)) => {
+            eprintln!("Invalid limit: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let report = std::fs::File::open(&path).and_then(|file| {
+        let events: Box<dyn Read> = match std::fs::File::open(AnalyticsLog::rotated_path(&path)) {
+            Ok(rotated) => Box::new(rotated.chain(file)),
+            Err(_) => Box::new(file),
+        };
+        SearchReport::from_jsonl(BufReader::new(events))
+    });
+    let report = match report {
+        Ok(report) => report,
+        Err(err) => {
+            eprintln!("Failed to read {path}: {err}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    for facet in report.facets() {
+        let queries = report.zero_result_queries(facet, limit);
+        if queries.is_empty() {
+            continue;
+        }
+        let facet = if facet.is_empty() {
+            "(no facet)"
+        } else {
+            facet
+        };
+        println!("{facet}");
+        for stats in queries {
+            println!(
+                "{:>6} of {:<6} {}",
+                stats.zero_results, stats.searches, stats.query
+            );
+        }
+        println!();
+    }
+    ExitCode::SUCCESS
+}
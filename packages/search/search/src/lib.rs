@@ -1,7 +1,26 @@
 pub use once_cell;
 pub use yazi;
 
-pub use dioxus_search_macro::load_search_index;
+pub use dioxus_search_macro::{load_search_index, try_load_search_index};
 pub use dioxus_search_shared::*;
 
+mod analytics;
+pub use analytics::*;
+
+mod api;
+pub use api::*;
+
+mod multi;
+pub use multi::*;
+
+mod sharded;
+pub use sharded::*;
+
+mod worker;
+pub use worker::*;
+
 pub type LazySearchIndex<R> = once_cell::sync::Lazy<SearchIndex<R>>;
+
+/// An index loaded with [`try_load_search_index!`], which keeps the error if the index couldn't
+/// be loaded
+pub type TryLazySearchIndex<R> = once_cell::sync::Lazy<Result<SearchIndex<R>, IndexError>>;
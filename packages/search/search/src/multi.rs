@@ -0,0 +1,187 @@
+use std::{fmt::Display, str::FromStr};
+
+use dioxus_router::routable::Routable;
+use dioxus_search_shared::{SearchError, SearchIndex, SearchResult};
+use serde::{Deserialize, Serialize};
+
+/// Several search indexes that are searched together, like the docs for each version of a
+/// library. Every index is tagged with a facet, and each result records the facet it came from.
+pub struct MultiSearchIndex<R> {
+    indexes: Vec<(String, SearchIndex<R>)>,
+}
+
+impl<R> Default for MultiSearchIndex<R> {
+    fn default() -> Self {
+        Self {
+            indexes: Vec::new(),
+        }
+    }
+}
+
+impl<R: Routable> MultiSearchIndex<R>
+where
+    <R as FromStr>::Err: Display,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an index with a facet. Results from indexes added first win ties
+    pub fn insert(&mut self, facet: impl Into<String>, index: SearchIndex<R>) {
+        self.indexes.push((facet.into(), index));
+    }
+
+    /// Add an index with a facet. Results from indexes added first win ties
+    pub fn with_index(mut self, facet: impl Into<String>, index: SearchIndex<R>) -> Self {
+        self.insert(facet, index);
+        self
+    }
+
+    /// The facets of every index in the order they were added
+    pub fn facets(&self) -> impl Iterator<Item = &str> {
+        self.indexes.iter().map(|(facet, _)| facet.as_str())
+    }
+
+    /// Search every index and merge the results into one list, best match first. See
+    /// [`MultiSearchResults::merge`]
+    pub fn search(&self, text: &str) -> Result<MultiSearchResults<R>, SearchError> {
+        let mut results = Vec::new();
+        for (facet, index) in &self.indexes {
+            results.push((facet.clone(), index.search(text)?));
+        }
+        Ok(MultiSearchResults::merge(results))
+    }
+
+    /// Search the code examples of every index and merge the results into one list, best match
+    /// first. See [`SearchIndex::search_code`]
+    pub fn search_code(&self, text: &str) -> Result<MultiSearchResults<R>, SearchError> {
+        let mut results = Vec::new();
+        for (facet, index) in &self.indexes {
+            results.push((facet.clone(), index.search_code(text)?));
+        }
+        Ok(MultiSearchResults::merge(results))
+    }
+}
+
+/// The merged results of a [`MultiSearchIndex`] search
+#[derive(Debug, Clone, PartialEq)]
+pub struct MultiSearchResults<R: Routable> {
+    /// Every result, best match first
+    pub results: Vec<FacetedResult<R>>,
+    /// The number of results for each facet in the order the indexes were added
+    pub facets: Vec<FacetCount>,
+}
+
+impl<R: Routable> Default for MultiSearchResults<R> {
+    fn default() -> Self {
+        Self {
+            results: Vec::new(),
+            facets: Vec::new(),
+        }
+    }
+}
+
+impl<R: Routable> MultiSearchResults<R> {
+    /// The score of the best result from each index
+    pub const MAX_SCORE: usize = MAX_SCORE;
+
+    /// Merge the results of searching several indexes into one list, best match first. Results
+    /// from facets that come first win ties.
+    ///
+    /// Scores from different indexes aren't comparable on their own, so each result is scored
+    /// relative to the best result from its own index before they are merged.
+    pub fn merge(facet_results: impl IntoIterator<Item = (String, Vec<SearchResult<R>>)>) -> Self {
+        let (results, facets) = merge_facets(facet_results, |result| result.score);
+        Self {
+            results: results
+                .into_iter()
+                .map(|(facet, score, result)| FacetedResult {
+                    facet,
+                    score,
+                    result,
+                })
+                .collect(),
+            facets,
+        }
+    }
+
+    /// Returns true if no index had any results
+    pub fn is_empty(&self) -> bool {
+        self.results.is_empty()
+    }
+
+    /// The results from the index with this facet, best match first
+    pub fn in_facet<'a>(&'a self, facet: &'a str) -> impl Iterator<Item = &'a SearchResult<R>> {
+        self.results
+            .iter()
+            .filter(move |result| result.facet == facet)
+            .map(|result| &result.result)
+    }
+
+    /// The number of results from the index with this facet
+    pub fn count(&self, facet: &str) -> usize {
+        self.facets
+            .iter()
+            .find(|count| count.facet == facet)
+            .map(|count| count.count)
+            .unwrap_or_default()
+    }
+}
+
+/// The score of the best result from each index once results are merged
+const MAX_SCORE: usize = 1000;
+
+/// Score each result relative to the best result from its own index and merge them into one list,
+/// best match first. Returns the facet and relative score of each result, and the number of
+/// results for each facet
+pub(crate) fn merge_facets<T>(
+    facet_results: impl IntoIterator<Item = (String, Vec<T>)>,
+    score: impl Fn(&T) -> usize,
+) -> (Vec<(String, usize, T)>, Vec<FacetCount>) {
+    let mut results = Vec::new();
+    let mut facets = Vec::new();
+
+    for (order, (facet, facet_results)) in facet_results.into_iter().enumerate() {
+        facets.push(FacetCount {
+            facet: facet.clone(),
+            count: facet_results.len(),
+        });
+
+        let best = facet_results
+            .iter()
+            .map(&score)
+            .max()
+            .unwrap_or_default()
+            .max(1);
+        for result in facet_results {
+            let relative = score(&result) * MAX_SCORE / best;
+            results.push((order, (facet.clone(), relative, result)));
+        }
+    }
+
+    // Results from facets that come first win ties
+    results
+        .sort_by(|(a_order, (_, a, _)), (b_order, (_, b, _))| b.cmp(a).then(a_order.cmp(b_order)));
+
+    (
+        results.into_iter().map(|(_, result)| result).collect(),
+        facets,
+    )
+}
+
+/// A search result and the facet of the index it came from
+#[derive(Debug, Clone, PartialEq)]
+pub struct FacetedResult<R: Routable> {
+    pub facet: String,
+    /// The score relative to the best result from the same index, up to
+    /// [`MultiSearchResults::MAX_SCORE`]
+    pub score: usize,
+    pub result: SearchResult<R>,
+}
+
+/// The number of results from the index with a facet
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FacetCount {
+    pub facet: String,
+    pub count: usize,
+}
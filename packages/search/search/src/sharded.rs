@@ -0,0 +1,137 @@
+use std::{
+    cell::RefCell, fmt::Display, future::Future, marker::PhantomData, pin::Pin, str::FromStr,
+};
+
+use dioxus_router::routable::Routable;
+use dioxus_search_shared::{
+    decompress_index, DocumentMatch, Query, SearchError, SearchMode, SearchResult, ShardedIndex,
+    DICTIONARY_SHARD,
+};
+
+type ShardFuture = Pin<Box<dyn Future<Output = Result<Vec<u8>, SearchError>>>>;
+
+/// A search index that only downloads the parts of the index each query needs.
+///
+/// [`SearchIndex::create`](crate::SearchIndex::create) writes the shards of an index to
+/// `dioxus_search/index_{name}/{shard}.bin`. Serve that directory with the rest of your assets
+/// and load the index with a function that fetches a shard by name:
+///
+/// ```rust, ignore
+/// let index = ShardedSearchIndex::<Route>::load(|shard| async move {
+///     let url = format!("/assets/dioxus_search/index_docs/{shard}.bin");
+///     let response = reqwest::get(url).await.map_err(SearchError::new)?;
+///     Ok(response.bytes().await.map_err(SearchError::new)?.to_vec())
+/// })
+/// .await?;
+/// ```
+///
+/// Only the dictionary is downloaded up front. Queries of one or two characters are answered
+/// from the dictionary with [`ShardedSearchIndex::search_instant`], and
+/// [`ShardedSearchIndex::search`] fetches the shards a query needs the first time they are used.
+pub struct ShardedSearchIndex<R> {
+    fetch: Box<dyn Fn(String) -> ShardFuture>,
+    index: RefCell<ShardedIndex>,
+    _marker: PhantomData<R>,
+}
+
+impl<R> ShardedSearchIndex<R> {
+    /// Fetch the dictionary of an index. `fetch` returns the compressed bytes of the shard with
+    /// the given name, exactly as they were written to disk
+    pub async fn load<F>(fetch: impl Fn(String) -> F + 'static) -> Result<Self, SearchError>
+    where
+        F: Future<Output = Result<Vec<u8>, SearchError>> + 'static,
+    {
+        let fetch: Box<dyn Fn(String) -> ShardFuture> =
+            Box::new(move |shard| Box::pin(fetch(shard)));
+        let dictionary = decompress_index(&fetch(DICTIONARY_SHARD.to_string()).await?)?;
+        let index = ShardedIndex::from_dictionary(&dictionary)?;
+        Ok(Self {
+            fetch,
+            index: RefCell::new(index),
+            _marker: PhantomData,
+        })
+    }
+
+    /// Fetch any shards the query needs that haven't been loaded yet and search the index without
+    /// parsing the urls of the matches into routes
+    pub async fn search_documents(
+        &self,
+        text: &str,
+        mode: SearchMode,
+    ) -> Result<Vec<DocumentMatch>, SearchError> {
+        let query = Query::parse(text).in_mode(mode);
+        loop {
+            let missing = self.index.borrow().missing_shards_for(&query);
+            if missing.is_empty() {
+                break;
+            }
+
+            let shards = futures::future::join_all(missing.into_iter().map(|shard| async {
+                let bytes = (self.fetch)(shard.clone()).await;
+                (shard, bytes)
+            }))
+            .await;
+
+            let mut index = self.index.borrow_mut();
+            for (shard, bytes) in shards {
+                let bytes = decompress_index(&bytes?)?;
+                index
+                    .insert_shard(&shard, &bytes)
+                    .map_err(SearchError::new)?;
+            }
+        }
+
+        Ok(self.index.borrow().search_query(&query))
+    }
+
+    /// Search with just the dictionary without parsing the urls of the matches into routes. See
+    /// [`ShardedSearchIndex::search_instant`]
+    pub fn instant_documents(&self, text: &str) -> Option<Vec<DocumentMatch>> {
+        self.index.borrow().instant_results(text)
+    }
+
+    /// Complete the last word of a query from the dictionary, most common first. Returns the whole
+    /// query with the last word completed
+    pub fn complete(&self, query: &str, limit: usize) -> Vec<String> {
+        self.index.borrow().complete(query, limit)
+    }
+
+    /// Suggest a similar query from the dictionary for a query without results
+    pub fn did_you_mean(&self, query: &str) -> Option<String> {
+        self.index.borrow().did_you_mean(query)
+    }
+
+    /// The popular searches stored in the index without parsing their urls into routes
+    pub fn popular_documents(&self) -> Vec<DocumentMatch> {
+        self.index.borrow().popular_searches()
+    }
+}
+
+impl<R: Routable> ShardedSearchIndex<R>
+where
+    <R as FromStr>::Err: Display,
+{
+    /// Search with just the dictionary. Returns `None` if the query is longer than a couple of
+    /// characters and needs [`ShardedSearchIndex::search`]. Instant results don't have excerpts
+    pub fn search_instant(&self, text: &str) -> Option<Vec<SearchResult<R>>> {
+        Some(SearchResult::from_matches(self.instant_documents(text)?))
+    }
+
+    /// The popular searches stored in the index, in order. Show these before anything is typed
+    pub fn popular_searches(&self) -> Vec<SearchResult<R>> {
+        SearchResult::from_matches(self.popular_documents())
+    }
+
+    /// Fetch any shards the query needs that haven't been loaded yet and search the index
+    pub async fn search(&self, text: &str) -> Result<Vec<SearchResult<R>>, SearchError> {
+        let matches = self.search_documents(text, SearchMode::Pages).await?;
+        Ok(SearchResult::from_matches(matches))
+    }
+
+    /// Fetch any shards the query needs that haven't been loaded yet and search the code examples
+    /// in the index
+    pub async fn search_code(&self, text: &str) -> Result<Vec<SearchResult<R>>, SearchError> {
+        let matches = self.search_documents(text, SearchMode::Code).await?;
+        Ok(SearchResult::from_matches(matches))
+    }
+}
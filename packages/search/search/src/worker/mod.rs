@@ -0,0 +1,566 @@
+//! Search without blocking the thread that renders the page. Decompressing shards and searching
+//! them can take long enough to drop frames while the reader is typing, so in the browser a
+//! [`SearchWorker`] sends queries to a dedicated web worker that hosts the indexes. On other
+//! targets the same requests are answered on the current thread.
+
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    fmt::Display,
+    future::Future,
+    marker::PhantomData,
+    pin::Pin,
+    rc::Rc,
+    str::FromStr,
+    task::{Context, Poll},
+};
+
+use dioxus_router::routable::Routable;
+use dioxus_search_shared::{DocumentMatch, SearchError, SearchMode, SearchResult};
+use futures::future::{AbortHandle, Abortable, FutureExt, LocalBoxFuture, Shared};
+use serde::{Deserialize, Serialize};
+
+use crate::ShardedSearchIndex;
+
+#[cfg(target_family = "wasm")]
+mod web;
+#[cfg(target_family = "wasm")]
+pub use web::{in_web_worker, run_search_worker};
+
+/// A message from a [`SearchWorker`] to the [`SearchWorkerHost`] that runs its searches
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum WorkerRequest {
+    /// Load the dictionary of the sharded index at `url` as `facet`
+    Load { id: u64, facet: String, url: String },
+    /// Search the index loaded as `facet`, fetching any shards the query needs
+    Search {
+        id: u64,
+        facet: String,
+        query: String,
+        mode: SearchMode,
+    },
+    /// Answer a short query from the dictionary of the index loaded as `facet`
+    Instant {
+        id: u64,
+        facet: String,
+        query: String,
+    },
+    /// Complete the last word of a query from the dictionary of the index loaded as `facet`
+    Complete {
+        id: u64,
+        facet: String,
+        query: String,
+        limit: usize,
+    },
+    /// Suggest a similar query from the dictionary of the index loaded as `facet`
+    DidYouMean {
+        id: u64,
+        facet: String,
+        query: String,
+    },
+    /// Read the popular searches stored in the index loaded as `facet`
+    Popular { id: u64, facet: String },
+    /// Stop a search that hasn't finished. Cancelled searches are never answered
+    Cancel { id: u64 },
+}
+
+/// The answer to the [`WorkerRequest`] with the same id
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorkerResponse {
+    pub id: u64,
+    pub result: Result<WorkerAnswer, String>,
+}
+
+/// What a [`SearchWorkerHost`] answers each kind of [`WorkerRequest`] with
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
+pub enum WorkerAnswer {
+    /// The index was loaded
+    Loaded,
+    /// The matches of a search or the popular searches, best match first
+    Matches(Vec<DocumentMatch>),
+    /// The matches for a short query, or `None` if the query needs a full search
+    Instant(Option<Vec<DocumentMatch>>),
+    /// The query with its last word completed, most common first
+    Completions(Vec<String>),
+    /// A similar query with results
+    DidYouMean(Option<String>),
+}
+
+type FetchFuture = LocalBoxFuture<'static, Result<Vec<u8>, SearchError>>;
+type LoadFuture = Shared<LocalBoxFuture<'static, Result<Rc<ShardedSearchIndex<()>>, String>>>;
+
+/// Loads and searches sharded indexes for a [`SearchWorker`]. In the browser the host lives in the
+/// web worker started by [`run_search_worker`], and everywhere else the [`SearchWorker`] owns it.
+pub struct SearchWorkerHost {
+    fetch: Rc<dyn Fn(String) -> FetchFuture>,
+    /// The index of each facet and the url it was loaded from
+    indexes: RefCell<HashMap<String, (String, LoadFuture)>>,
+    running: Rc<RefCell<HashMap<u64, AbortHandle>>>,
+}
+
+impl SearchWorkerHost {
+    /// `fetch` returns the bytes at a url. The shards of an index are fetched from
+    /// `{url}/{shard}.bin` under the url the index was loaded from
+    pub fn new<F>(fetch: impl Fn(String) -> F + 'static) -> Self
+    where
+        F: Future<Output = Result<Vec<u8>, SearchError>> + 'static,
+    {
+        Self {
+            fetch: Rc::new(move |url| fetch(url).boxed_local()),
+            indexes: RefCell::new(HashMap::new()),
+            running: Rc::new(RefCell::new(HashMap::new())),
+        }
+    }
+
+    /// Start loading the index at `url` as `facet`. Searches of the facet wait for the index to
+    /// load, so they can be sent right away. Loading a facet from the url it is already loaded or
+    /// loading from keeps the index and the shards it fetched, unless that load failed. Loading
+    /// it from another url replaces its index
+    pub fn load(&self, facet: String, url: String) -> LocalBoxFuture<'static, Result<(), String>> {
+        let existing = self.indexes.borrow().get(&facet).cloned();
+        if let Some((loaded_url, index)) = existing {
+            if loaded_url == url && !matches!(index.peek(), Some(Err(_))) {
+                return async move { index.await.map(|_| ()) }.boxed_local();
+            }
+        }
+
+        let fetch = self.fetch.clone();
+        let shards_url = url.clone();
+        let index = async move {
+            let index =
+                ShardedSearchIndex::load(move |shard| fetch(format!("{shards_url}/{shard}.bin")))
+                    .await
+                    .map_err(|err| err.to_string())?;
+            Ok(Rc::new(index))
+        }
+        .boxed_local()
+        .shared();
+        self.indexes
+            .borrow_mut()
+            .insert(facet, (url, index.clone()));
+        async move { index.await.map(|_| ()) }.boxed_local()
+    }
+
+    /// Search the index loaded as `facet` once it has loaded
+    pub fn search(
+        &self,
+        facet: &str,
+        query: String,
+        mode: SearchMode,
+    ) -> LocalBoxFuture<'static, Result<Vec<DocumentMatch>, String>> {
+        let index = self.index(facet);
+        async move {
+            index
+                .await?
+                .search_documents(&query, mode)
+                .await
+                .map_err(|err| err.to_string())
+        }
+        .boxed_local()
+    }
+
+    /// The index loaded as `facet`, once it has loaded
+    fn index(
+        &self,
+        facet: &str,
+    ) -> LocalBoxFuture<'static, Result<Rc<ShardedSearchIndex<()>>, String>> {
+        let index = self
+            .indexes
+            .borrow()
+            .get(facet)
+            .map(|(_, index)| index.clone());
+        let facet = facet.to_string();
+        async move {
+            index
+                .ok_or_else(|| format!("No index was loaded for {facet}"))?
+                .await
+        }
+        .boxed_local()
+    }
+
+    /// Answer a request from a [`SearchWorker`]. Resolves to `None` if the request was a
+    /// [`WorkerRequest::Cancel`] or the search it started was cancelled
+    pub fn handle(
+        &self,
+        request: WorkerRequest,
+    ) -> LocalBoxFuture<'static, Option<WorkerResponse>> {
+        match request {
+            WorkerRequest::Load { id, facet, url } => {
+                let load = self.load(facet, url);
+                async move {
+                    let result = load.await.map(|()| WorkerAnswer::Loaded);
+                    Some(WorkerResponse { id, result })
+                }
+                .boxed_local()
+            }
+            WorkerRequest::Search {
+                id,
+                facet,
+                query,
+                mode,
+            } => {
+                let (abort, registration) = AbortHandle::new_pair();
+                self.running.borrow_mut().insert(id, abort);
+                let search = Abortable::new(self.search(&facet, query, mode), registration);
+                let running = self.running.clone();
+                async move {
+                    let result = search.await;
+                    running.borrow_mut().remove(&id);
+                    Some(WorkerResponse {
+                        id,
+                        result: result.ok()?.map(WorkerAnswer::Matches),
+                    })
+                }
+                .boxed_local()
+            }
+            WorkerRequest::Instant { id, facet, query } => self.answer(id, &facet, move |index| {
+                WorkerAnswer::Instant(index.instant_documents(&query))
+            }),
+            WorkerRequest::Complete {
+                id,
+                facet,
+                query,
+                limit,
+            } => self.answer(id, &facet, move |index| {
+                WorkerAnswer::Completions(index.complete(&query, limit))
+            }),
+            WorkerRequest::DidYouMean { id, facet, query } => {
+                self.answer(id, &facet, move |index| {
+                    WorkerAnswer::DidYouMean(index.did_you_mean(&query))
+                })
+            }
+            WorkerRequest::Popular { id, facet } => self.answer(id, &facet, |index| {
+                WorkerAnswer::Matches(index.popular_documents())
+            }),
+            WorkerRequest::Cancel { id } => {
+                if let Some(search) = self.running.borrow_mut().remove(&id) {
+                    search.abort();
+                }
+                async { None }.boxed_local()
+            }
+        }
+    }
+
+    /// Answer a request from the dictionary of the index loaded as `facet` once it has loaded.
+    /// These answers don't fetch any shards, so they aren't cancelled
+    fn answer(
+        &self,
+        id: u64,
+        facet: &str,
+        answer: impl FnOnce(&ShardedSearchIndex<()>) -> WorkerAnswer + 'static,
+    ) -> LocalBoxFuture<'static, Option<WorkerResponse>> {
+        let index = self.index(facet);
+        async move {
+            let result = index.await.map(|index| answer(&index));
+            Some(WorkerResponse { id, result })
+        }
+        .boxed_local()
+    }
+}
+
+/// Searches sharded indexes without blocking the thread that renders the page.
+///
+/// In the browser the indexes are loaded and searched in a dedicated web worker. The worker runs
+/// the same wasm module as the page, so the `main` function of the app has to start the worker's
+/// side instead of the app when it runs in a worker:
+///
+/// ```rust, ignore
+/// fn main() {
+///     #[cfg(target_arch = "wasm32")]
+///     if dioxus_search::in_web_worker() {
+///         dioxus_search::run_search_worker(fetch_bytes);
+///         return;
+///     }
+///
+///     dioxus::launch(app);
+/// }
+/// ```
+///
+/// Then load each index by url and search it by facet. Every search returns a [`SearchHandle`]
+/// that can be cancelled, so a search for an outdated query can be dropped as soon as the reader
+/// types another character. Instant results, completions, suggestions and popular searches are
+/// answered from the dictionary the worker loaded, so the page never downloads an index itself:
+///
+/// ```rust, ignore
+/// let worker = SearchWorker::<Route>::new(fetch_bytes)?;
+/// worker.load("0.7", "/assets/dioxus_search/index_docs_0_7").await?;
+/// let results = worker.search("0.7", "use_signal").await?;
+/// ```
+///
+/// On other targets, or with [`SearchWorker::in_thread`], the searches run on the current thread
+/// while the returned futures are polled.
+pub struct SearchWorker<R> {
+    runtime: Runtime,
+    _marker: PhantomData<R>,
+}
+
+enum Runtime {
+    InThread {
+        host: Rc<SearchWorkerHost>,
+        next_id: Cell<u64>,
+    },
+    #[cfg(target_family = "wasm")]
+    Web(Rc<web::WebWorker>),
+}
+
+/// A request that was sent to the runtime, and a function that cancels it
+type Sent = (
+    LocalBoxFuture<'static, Result<WorkerAnswer, SearchError>>,
+    Box<dyn FnOnce()>,
+);
+
+impl<R> SearchWorker<R> {
+    /// Start a web worker to run searches in. `fetch` returns the bytes at a url, and is only
+    /// called from this thread if searches can't run in a web worker on this target
+    pub fn new<F>(fetch: impl Fn(String) -> F + 'static) -> Result<Self, SearchError>
+    where
+        F: Future<Output = Result<Vec<u8>, SearchError>> + 'static,
+    {
+        #[cfg(target_family = "wasm")]
+        {
+            let _ = fetch;
+            Ok(Self {
+                runtime: Runtime::Web(Rc::new(web::WebWorker::spawn()?)),
+                _marker: PhantomData,
+            })
+        }
+
+        #[cfg(not(target_family = "wasm"))]
+        Ok(Self::in_thread(fetch))
+    }
+
+    /// Run searches on this thread while the returned futures are polled. `fetch` returns the
+    /// bytes at a url
+    pub fn in_thread<F>(fetch: impl Fn(String) -> F + 'static) -> Self
+    where
+        F: Future<Output = Result<Vec<u8>, SearchError>> + 'static,
+    {
+        Self {
+            runtime: Runtime::InThread {
+                host: Rc::new(SearchWorkerHost::new(fetch)),
+                next_id: Cell::new(0),
+            },
+            _marker: PhantomData,
+        }
+    }
+
+    /// Load the sharded index that was written to `url` as `facet`. Its shards are fetched from
+    /// `{url}/{shard}.bin`. Searches of the facet wait for the index to load, so the handle only
+    /// needs to be awaited to find out if loading failed. Dropping it doesn't stop the load.
+    /// Loading a facet again from the same url keeps the shards that were already fetched
+    pub fn load(&self, facet: impl Into<String>, url: impl Into<String>) -> SearchHandle<()> {
+        let (facet, url) = (facet.into(), url.into());
+        self.send(|id| WorkerRequest::Load { id, facet, url }, |_| Some(()))
+    }
+
+    /// Search the pages or code examples of the index loaded as `facet` without parsing the urls
+    /// of the matches into routes
+    pub fn search_documents(
+        &self,
+        facet: &str,
+        text: &str,
+        mode: SearchMode,
+    ) -> SearchHandle<Vec<DocumentMatch>> {
+        self.send(
+            |id| WorkerRequest::Search {
+                id,
+                facet: facet.to_string(),
+                query: text.to_string(),
+                mode,
+            },
+            matches,
+        )
+    }
+
+    /// Complete the last word of a query from the dictionary of the index loaded as `facet`
+    pub fn complete(&self, facet: &str, query: &str, limit: usize) -> SearchHandle<Vec<String>> {
+        self.send(
+            |id| WorkerRequest::Complete {
+                id,
+                facet: facet.to_string(),
+                query: query.to_string(),
+                limit,
+            },
+            |answer| match answer {
+                WorkerAnswer::Completions(completions) => Some(completions),
+                _ => None,
+            },
+        )
+    }
+
+    /// Suggest a similar query from the dictionary of the index loaded as `facet`
+    pub fn did_you_mean(&self, facet: &str, query: &str) -> SearchHandle<Option<String>> {
+        self.send(
+            |id| WorkerRequest::DidYouMean {
+                id,
+                facet: facet.to_string(),
+                query: query.to_string(),
+            },
+            |answer| match answer {
+                WorkerAnswer::DidYouMean(suggestion) => Some(suggestion),
+                _ => None,
+            },
+        )
+    }
+
+    fn send<T>(
+        &self,
+        request: impl FnOnce(u64) -> WorkerRequest,
+        map: fn(WorkerAnswer) -> Option<T>,
+    ) -> SearchHandle<T> {
+        let (response, cancel) = match &self.runtime {
+            Runtime::InThread { host, next_id } => {
+                let id = next_id.get();
+                next_id.set(id + 1);
+                in_thread_request(host, id, request(id))
+            }
+            #[cfg(target_family = "wasm")]
+            Runtime::Web(worker) => worker.send(request),
+        };
+        SearchHandle {
+            response: Some(response),
+            cancel: Some(cancel),
+            map,
+        }
+    }
+}
+
+impl<R: Routable> SearchWorker<R>
+where
+    <R as FromStr>::Err: Display,
+{
+    /// Search the index loaded as `facet`, fetching any shards the query needs
+    pub fn search(&self, facet: &str, text: &str) -> SearchHandle<Vec<SearchResult<R>>> {
+        self.search_in_mode(facet, text, SearchMode::Pages)
+    }
+
+    /// Search the code examples in the index loaded as `facet`
+    pub fn search_code(&self, facet: &str, text: &str) -> SearchHandle<Vec<SearchResult<R>>> {
+        self.search_in_mode(facet, text, SearchMode::Code)
+    }
+
+    /// Answer a short query from the dictionary of the index loaded as `facet`. Resolves to
+    /// `None` if the query is too long and needs a full search
+    pub fn search_instant(
+        &self,
+        facet: &str,
+        text: &str,
+    ) -> SearchHandle<Option<Vec<SearchResult<R>>>> {
+        self.send(
+            |id| WorkerRequest::Instant {
+                id,
+                facet: facet.to_string(),
+                query: text.to_string(),
+            },
+            |answer| match answer {
+                WorkerAnswer::Instant(matches) => Some(matches.map(SearchResult::from_matches)),
+                _ => None,
+            },
+        )
+    }
+
+    /// The popular searches stored in the index loaded as `facet`
+    pub fn popular_searches(&self, facet: &str) -> SearchHandle<Vec<SearchResult<R>>> {
+        self.send(
+            |id| WorkerRequest::Popular {
+                id,
+                facet: facet.to_string(),
+            },
+            |answer| matches(answer).map(SearchResult::from_matches),
+        )
+    }
+
+    fn search_in_mode(
+        &self,
+        facet: &str,
+        text: &str,
+        mode: SearchMode,
+    ) -> SearchHandle<Vec<SearchResult<R>>> {
+        self.send(
+            |id| WorkerRequest::Search {
+                id,
+                facet: facet.to_string(),
+                query: text.to_string(),
+                mode,
+            },
+            |answer| matches(answer).map(SearchResult::from_matches),
+        )
+    }
+}
+
+/// The matches of a search or the popular searches
+fn matches(answer: WorkerAnswer) -> Option<Vec<DocumentMatch>> {
+    match answer {
+        WorkerAnswer::Matches(matches) => Some(matches),
+        _ => None,
+    }
+}
+
+fn in_thread_request(host: &Rc<SearchWorkerHost>, id: u64, request: WorkerRequest) -> Sent {
+    let response = host.handle(request).map(|response| match response {
+        Some(response) => response.result.map_err(SearchError::new),
+        None => Err(cancelled()),
+    });
+    let host = host.clone();
+    let cancel = move || {
+        // Cancelling only aborts the search, so the future can be dropped without polling it
+        drop(host.handle(WorkerRequest::Cancel { id }));
+    };
+    (response.boxed_local(), Box::new(cancel))
+}
+
+fn cancelled() -> SearchError {
+    SearchError::new("The search was cancelled")
+}
+
+/// A request running in a [`SearchWorker`]. Await it for the result, or call
+/// [`SearchHandle::cancel`] to stop it. Dropping the handle before it finishes cancels it too
+#[must_use = "searches are cancelled when their handle is dropped"]
+pub struct SearchHandle<T> {
+    response: Option<LocalBoxFuture<'static, Result<WorkerAnswer, SearchError>>>,
+    cancel: Option<Box<dyn FnOnce()>>,
+    /// Read the answer of the kind of request that was sent
+    map: fn(WorkerAnswer) -> Option<T>,
+}
+
+impl<T> SearchHandle<T> {
+    /// Stop the request if it hasn't finished. Awaiting the handle afterwards returns an error
+    pub fn cancel(&mut self) {
+        self.response = None;
+        if let Some(cancel) = self.cancel.take() {
+            cancel();
+        }
+    }
+
+    /// Whether the request has finished or was cancelled
+    pub fn is_finished(&self) -> bool {
+        self.response.is_none()
+    }
+}
+
+impl<T> Future for SearchHandle<T> {
+    type Output = Result<T, SearchError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let Some(response) = &mut self.response else {
+            return Poll::Ready(Err(cancelled()));
+        };
+        let result = futures::ready!(response.poll_unpin(cx));
+        self.response = None;
+        self.cancel = None;
+        let map = self.map;
+        Poll::Ready(result.and_then(|answer| {
+            map(answer).ok_or_else(|| SearchError::new("The worker answered a different request"))
+        }))
+    }
+}
+
+impl<T> Drop for SearchHandle<T> {
+    fn drop(&mut self) {
+        if !self.is_finished() {
+            self.cancel();
+        }
+    }
+}
@@ -0,0 +1,167 @@
+//! Both sides of a [`SearchWorker`](super::SearchWorker) in the browser. The page posts
+//! [`WorkerRequest`]s to a module worker as JSON, and the worker answers with
+//! [`WorkerResponse`]s from its [`SearchWorkerHost`].
+
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    future::Future,
+    rc::Rc,
+};
+
+use dioxus_search_shared::SearchError;
+use futures::{channel::oneshot, FutureExt};
+use wasm_bindgen::{prelude::*, JsCast};
+use web_sys::{
+    Blob, BlobPropertyBag, DedicatedWorkerGlobalScope, MessageEvent, Url, Worker, WorkerOptions,
+    WorkerType,
+};
+
+use super::{cancelled, SearchWorkerHost, Sent, WorkerAnswer, WorkerRequest, WorkerResponse};
+
+#[wasm_bindgen]
+extern "C" {
+    /// The url of the JS module wasm-bindgen generated for this wasm module
+    #[wasm_bindgen(thread_local_v2, js_namespace = ["import", "meta"], js_name = url)]
+    static MODULE_URL: String;
+}
+
+type Pending = Rc<RefCell<HashMap<u64, oneshot::Sender<Result<WorkerAnswer, String>>>>>;
+
+/// The page's side of the web worker
+pub(super) struct WebWorker {
+    worker: Worker,
+    pending: Pending,
+    next_id: Cell<u64>,
+    _onmessage: Closure<dyn FnMut(MessageEvent)>,
+}
+
+impl WebWorker {
+    /// Start a module worker that loads this wasm module again. Its `main` function calls
+    /// [`run_search_worker`] when [`in_web_worker`] is true
+    pub(super) fn spawn() -> Result<Self, SearchError> {
+        let module_url = MODULE_URL.with(String::clone);
+        let script = format!("import init from {module_url:?};\nawait init();\n");
+
+        let options = BlobPropertyBag::new();
+        options.set_type("text/javascript");
+        let parts = js_sys::Array::of1(&JsValue::from_str(&script));
+        let blob = Blob::new_with_str_sequence_and_options(&parts, &options).map_err(js_error)?;
+        let script_url = Url::create_object_url_with_blob(&blob).map_err(js_error)?;
+
+        let options = WorkerOptions::new();
+        options.set_type(WorkerType::Module);
+        let worker = Worker::new_with_options(&script_url, &options).map_err(js_error)?;
+
+        let pending = Pending::default();
+        let onmessage = Closure::<dyn FnMut(MessageEvent)>::new({
+            let pending = pending.clone();
+            move |event: MessageEvent| {
+                let Some(response) = event
+                    .data()
+                    .as_string()
+                    .and_then(|json| serde_json::from_str::<WorkerResponse>(&json).ok())
+                else {
+                    return;
+                };
+                // The search may have been cancelled while the worker was answering it
+                if let Some(sender) = pending.borrow_mut().remove(&response.id) {
+                    let _ = sender.send(response.result);
+                }
+            }
+        });
+        worker.set_onmessage(Some(onmessage.as_ref().unchecked_ref()));
+
+        Ok(Self {
+            worker,
+            pending,
+            next_id: Cell::new(0),
+            _onmessage: onmessage,
+        })
+    }
+
+    pub(super) fn send(self: &Rc<Self>, request: impl FnOnce(u64) -> WorkerRequest) -> Sent {
+        let id = self.next_id.get();
+        self.next_id.set(id + 1);
+
+        let (sender, receiver) = oneshot::channel();
+        self.pending.borrow_mut().insert(id, sender);
+        if let Err(err) = self.post(&request(id)) {
+            self.pending.borrow_mut().remove(&id);
+            return (async move { Err(err) }.boxed_local(), Box::new(|| {}));
+        }
+
+        let response = receiver.map(|response| match response {
+            Ok(result) => result.map_err(SearchError::new),
+            // The worker was stopped before it answered
+            Err(oneshot::Canceled) => Err(cancelled()),
+        });
+        let worker = self.clone();
+        let cancel = move || {
+            if worker.pending.borrow_mut().remove(&id).is_some() {
+                let _ = worker.post(&WorkerRequest::Cancel { id });
+            }
+        };
+        (response.boxed_local(), Box::new(cancel))
+    }
+
+    fn post(&self, request: &WorkerRequest) -> Result<(), SearchError> {
+        let json = serde_json::to_string(request).map_err(SearchError::new)?;
+        self.worker
+            .post_message(&JsValue::from_str(&json))
+            .map_err(js_error)
+    }
+}
+
+impl Drop for WebWorker {
+    fn drop(&mut self) {
+        self.worker.terminate();
+    }
+}
+
+/// Whether this wasm module was loaded by a dedicated web worker instead of a page
+pub fn in_web_worker() -> bool {
+    js_sys::global().is_instance_of::<DedicatedWorkerGlobalScope>()
+}
+
+/// Answer the requests of the [`SearchWorker`](super::SearchWorker) that started this web worker.
+/// `fetch` returns the bytes at a url. Call this from `main` when [`in_web_worker`] is true
+/// instead of launching the app, then return. The worker keeps answering requests until the
+/// [`SearchWorker`](super::SearchWorker) is dropped
+pub fn run_search_worker<F>(fetch: impl Fn(String) -> F + 'static)
+where
+    F: Future<Output = Result<Vec<u8>, SearchError>> + 'static,
+{
+    let scope: DedicatedWorkerGlobalScope = js_sys::global().unchecked_into();
+    let host = SearchWorkerHost::new(fetch);
+
+    let onmessage = Closure::<dyn FnMut(MessageEvent)>::new({
+        let scope = scope.clone();
+        move |event: MessageEvent| {
+            let Some(request) = event
+                .data()
+                .as_string()
+                .and_then(|json| serde_json::from_str::<WorkerRequest>(&json).ok())
+            else {
+                return;
+            };
+            let response = host.handle(request);
+            let scope = scope.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                let Some(response) = response.await else {
+                    return;
+                };
+                if let Ok(json) = serde_json::to_string(&response) {
+                    let _ = scope.post_message(&JsValue::from_str(&json));
+                }
+            });
+        }
+    });
+    scope.set_onmessage(Some(onmessage.as_ref().unchecked_ref()));
+    // The handler lives as long as the worker
+    onmessage.forget();
+}
+
+fn js_error(err: JsValue) -> SearchError {
+    SearchError::new(format!("{err:?}"))
+}
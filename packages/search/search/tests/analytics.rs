@@ -0,0 +1,178 @@
+//! Report searches with `SearchAnalytics`, log them with `AnalyticsLog` and aggregate the log into
+//! a `SearchReport`
+
+use std::{cell::RefCell, io::BufReader, rc::Rc};
+
+use dioxus_search::{AnalyticsLog, SearchAnalytics, SearchEvent, SearchReport};
+
+fn event(facet: &str, query: &str, results: usize, clicked_rank: Option<usize>) -> SearchEvent {
+    SearchEvent {
+        facet: Some(facet.into()),
+        query: query.into(),
+        results,
+        clicked_rank,
+    }
+}
+
+/// Analytics that collect the reported events
+fn collect() -> (SearchAnalytics, Rc<RefCell<Vec<SearchEvent>>>) {
+    let events = Rc::new(RefCell::new(Vec::new()));
+    let analytics = SearchAnalytics::new({
+        let events = events.clone();
+        move |event| events.borrow_mut().push(event)
+    });
+    (analytics, events)
+}
+
+#[test]
+fn queries_are_reported_once_they_are_refined() {
+    let (analytics, events) = collect();
+    // Typing a query only reports the whole query
+    analytics.searched(Some("0.7"), "use_s", 12);
+    analytics.searched(Some("0.7"), "use_sig", 4);
+    analytics.searched(Some("0.7"), "use_signal", 3);
+    // Pressing backspace doesn't replace it
+    analytics.searched(Some("0.7"), "use_sign", 3);
+    assert!(events.borrow().is_empty());
+
+    // Searching for something else reports the previous query
+    analytics.searched(Some("0.7"), "  Hot   RELOAD ", 0);
+    assert_eq!(*events.borrow(), [event("0.7", "use_signal", 3, None)]);
+
+    // Opening a result reports the query with its rank
+    analytics.clicked(2);
+    assert_eq!(events.borrow()[1], event("0.7", "hot reload", 0, Some(2)));
+    analytics.flush();
+    assert_eq!(events.borrow().len(), 2);
+
+    // The same query in another facet is another search
+    analytics.searched(Some("0.7"), "router", 8);
+    analytics.searched(Some("0.6"), "router", 5);
+    drop(analytics);
+    assert_eq!(
+        events.borrow()[2..],
+        [
+            event("0.7", "router", 8, None),
+            event("0.6", "router", 5, None)
+        ]
+    );
+}
+
+#[test]
+fn personal_data_is_never_reported() {
+    let (analytics, events) = collect();
+    analytics.searched(None, "jane@example.com", 0);
+    analytics.searched(None, "call 5551234567", 0);
+    analytics.searched(None, "", 0);
+    analytics.flush();
+    assert!(events.borrow().is_empty());
+
+    // Short numbers like versions are fine, and long queries are cut
+    let anonymized = event("0.7", &"signal ".repeat(20), 1, None)
+        .anonymized()
+        .unwrap();
+    assert_eq!(anonymized.query.chars().count(), SearchEvent::MAX_QUERY_LEN);
+    assert!(event("0.7", "dioxus 0.7.1", 1, None).anonymized().is_some());
+}
+
+#[test]
+fn report_the_top_zero_result_queries() {
+    let dir = std::env::temp_dir().join(format!("dioxus_search_analytics_{}", std::process::id()));
+    let path = dir.join("search_analytics.jsonl");
+    let _ = std::fs::remove_file(&path);
+
+    let log = AnalyticsLog::open(&path).unwrap();
+    for event in [
+        event("0.7", "liveview", 0, None),
+        event("0.7", "LiveView", 0, None),
+        event("0.7", "tauri", 0, None),
+        event("0.7", "use_signal", 3, Some(0)),
+        event("0.7", "use_signal", 3, Some(1)),
+        event("0.7", "use_signal", 3, None),
+        event("0.6", "server functions", 0, None),
+        event("0.6", "me@example.com", 0, None),
+    ] {
+        log.record(event).unwrap();
+    }
+    drop(log);
+
+    // A line cut off when the server stopped is skipped
+    let mut contents = std::fs::read_to_string(&path).unwrap();
+    assert!(!contents.contains("example.com"));
+    contents.push_str("{\"facet\":\"0.7\",\"que");
+    std::fs::write(&path, contents).unwrap();
+
+    let file = std::fs::File::open(&path).unwrap();
+    let report = SearchReport::from_jsonl(BufReader::new(file)).unwrap();
+    assert_eq!(report.facets().collect::<Vec<_>>(), ["0.6", "0.7"]);
+
+    let missing = report.zero_result_queries("0.7", 10);
+    let missing: Vec<_> = missing
+        .iter()
+        .map(|stats| (stats.query.as_str(), stats.zero_results))
+        .collect();
+    assert_eq!(missing, [("liveview", 2), ("tauri", 1)]);
+    assert_eq!(report.zero_result_queries("0.7", 1).len(), 1);
+
+    let top = report.top_queries("0.7", 1);
+    assert_eq!(top[0].query, "use_signal");
+    assert_eq!(top[0].clicks, 2);
+    assert_eq!(top[0].average_rank(), Some(0.5));
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn unknown_facets_are_rejected() {
+    let dir = std::env::temp_dir().join(format!("dioxus_search_facets_{}", std::process::id()));
+    let path = dir.join("search_analytics.jsonl");
+    let log = AnalyticsLog::open(&path)
+        .unwrap()
+        .with_facets(["0.7", "blog"]);
+
+    log.record(event("0.7", "router", 3, None)).unwrap();
+    log.record(SearchEvent {
+        facet: None,
+        ..event("", "router", 3, None)
+    })
+    .unwrap();
+    let err = log.record(event("0.1", "router", 3, None));
+    assert_eq!(err.unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+    drop(log);
+
+    assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 2);
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn the_log_is_rotated_once_it_is_too_large() {
+    let dir = std::env::temp_dir().join(format!("dioxus_search_rotate_{}", std::process::id()));
+    let path = dir.join("search_analytics.jsonl");
+    let rotated = AnalyticsLog::rotated_path(&path);
+    assert_eq!(rotated, dir.join("search_analytics.jsonl.1"));
+
+    let line = serde_json::to_string(&event("0.7", "router", 3, None)).unwrap() + "\n";
+    let log = AnalyticsLog::open(&path)
+        .unwrap()
+        .with_max_size(2 * line.len() as u64);
+    for _ in 0..5 {
+        log.record(event("0.7", "router", 3, None)).unwrap();
+    }
+    drop(log);
+
+    // Five events fill the log twice, and only the last full log is kept
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), line);
+    assert_eq!(std::fs::read_to_string(&rotated).unwrap(), line.repeat(2));
+
+    // Opening the log again counts what is already in it
+    let log = AnalyticsLog::open(&path)
+        .unwrap()
+        .with_max_size(2 * line.len() as u64);
+    log.record(event("0.7", "router", 3, None)).unwrap();
+    log.record(event("0.7", "router", 3, None)).unwrap();
+    drop(log);
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), line);
+    assert_eq!(std::fs::read_to_string(&rotated).unwrap(), line.repeat(2));
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
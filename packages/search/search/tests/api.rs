@@ -0,0 +1,152 @@
+//! Query the search API against an index of the fixture book in `search-shared/tests/fixtures`
+
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use dioxus_search::{
+    book_page_url, BookVars, NativeIndex, SearchApi, SearchIndex, SearchMode, SearchRequest,
+    TokenizerConfig,
+};
+use mdbook_shared::MdBook;
+
+fn fixture_index() -> SearchIndex<()> {
+    let path =
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../search-shared/tests/fixtures/book");
+    let vars = BookVars::load(&path).unwrap();
+    let book = MdBook::new(path).unwrap();
+    let index = NativeIndex::from_book(
+        &book,
+        &vars,
+        Path::new(env!("CARGO_MANIFEST_DIR")),
+        |path| book_page_url("/learn/0.7", path),
+        TokenizerConfig::default(),
+    )
+    .unwrap();
+    SearchIndex::from_backend("fixture", index)
+}
+
+fn fixture_api() -> SearchApi<()> {
+    SearchApi::new()
+        .with_index("0.7", fixture_index())
+        .with_index("0.6", fixture_index())
+}
+
+#[test]
+fn search_one_version() {
+    let api = fixture_api();
+    let response = api
+        .search(&SearchRequest::new("signal").in_version("0.7"))
+        .unwrap();
+
+    assert_eq!(response.query, "signal");
+    assert_eq!(response.facets.len(), 1);
+    assert_eq!(response.facets[0].facet, "0.7");
+    assert_eq!(response.facets[0].count, response.results.len());
+
+    let best = &response.results[0];
+    assert_eq!(best.facet, "0.7");
+    assert!(best.url.starts_with("/learn/0.7/signals"));
+    assert_eq!(best.score, 1000);
+    // The excerpts have the same highlighted segments as a search in the browser
+    assert!(best
+        .excerpts
+        .iter()
+        .flat_map(|excerpt| &excerpt.text)
+        .any(|segment| segment.highlighted && segment.text.to_lowercase().starts_with("signal")));
+}
+
+#[test]
+fn search_every_version() {
+    let api = fixture_api();
+    let response = api.search(&SearchRequest::new("signal")).unwrap();
+
+    let facets: Vec<_> = response.facets.iter().map(|facet| &facet.facet).collect();
+    assert_eq!(facets, ["0.7", "0.6"]);
+    // Both indexes have the same pages, so the first index wins every tie
+    assert_eq!(response.results[0].facet, "0.7");
+    assert_eq!(response.results[1].facet, "0.6");
+    assert_eq!(response.results[0].url, response.results[1].url);
+}
+
+#[test]
+fn suggest_queries_without_results() {
+    let api = fixture_api();
+    let response = api
+        .search(&SearchRequest::new("\"signal compnents\""))
+        .unwrap();
+    assert!(response.results.is_empty());
+    assert_eq!(
+        response.did_you_mean.as_deref(),
+        Some("\"signal component\"")
+    );
+
+    let response = api.search(&SearchRequest::new("signal")).unwrap();
+    assert_eq!(response.did_you_mean, None);
+}
+
+#[test]
+fn unknown_version() {
+    let api = fixture_api();
+    let error = api
+        .search(&SearchRequest::new("signal").in_version("0.1"))
+        .unwrap_err();
+    assert_eq!(error.to_string(), "Unknown version 0.1");
+}
+
+#[test]
+fn search_code_from_query_string() {
+    let api = fixture_api();
+    let request: SearchRequest =
+        serde_json::from_str(r#"{"q": "Routable", "version": "0.7", "mode": "code"}"#).unwrap();
+    assert_eq!(request.mode, SearchMode::Code);
+
+    let response = api.search(&request).unwrap();
+    assert!(!response.results.is_empty());
+    assert!(response
+        .results
+        .iter()
+        .all(|result| result.example.is_some() && result.url.starts_with("/learn/0.7/router")));
+}
+
+#[test]
+fn responses_are_json() {
+    let api = fixture_api();
+    let response = api
+        .search(&SearchRequest::new("router").in_version("0.7"))
+        .unwrap();
+
+    let json = serde_json::to_value(&*response).unwrap();
+    let result = &json["results"][0];
+    assert_eq!(result["facet"], "0.7");
+    assert!(result["url"]
+        .as_str()
+        .unwrap()
+        .starts_with("/learn/0.7/router"));
+    assert!(result["excerpts"][0]["text"][0]["highlighted"].is_boolean());
+
+    let parsed: dioxus_search::SearchResponse = serde_json::from_value(json).unwrap();
+    assert_eq!(parsed, *response);
+}
+
+#[test]
+fn responses_are_cached_per_query() {
+    let api = fixture_api().with_cache_size(1);
+    let first = api.search(&SearchRequest::new("signal")).unwrap();
+
+    // Queries that only differ in whitespace share a response
+    let again = api.search(&SearchRequest::new("  signal ")).unwrap();
+    assert!(Arc::ptr_eq(&first, &again));
+
+    // A different version or mode is a different query
+    let code = api
+        .search(&SearchRequest::new("signal").in_mode(SearchMode::Code))
+        .unwrap();
+    assert!(!Arc::ptr_eq(&first, &code));
+
+    // The cache only has room for one response, so the first one was dropped
+    let evicted = api.search(&SearchRequest::new("signal")).unwrap();
+    assert!(!Arc::ptr_eq(&first, &evicted));
+    assert_eq!(first, evicted);
+}
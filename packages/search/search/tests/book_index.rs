@@ -0,0 +1,28 @@
+//! Build an index of the fixture book in `search-shared/tests/fixtures` while the tests compile
+
+use dioxus_search::{load_search_index, LazySearchIndex, SearchMode, TryLazySearchIndex};
+
+static INDEX: LazySearchIndex<()> = load_search_index!(
+    "fixture_book",
+    book = "../search-shared/tests/fixtures/book",
+    prefix = "/learn/0.7",
+);
+
+static TRY_INDEX: TryLazySearchIndex<()> = dioxus_search::try_load_search_index!(
+    "fixture_book_try",
+    book = "../search-shared/tests/fixtures/book/",
+);
+
+#[test]
+fn index_is_built_while_compiling() {
+    let results = INDEX.search_documents("signal", SearchMode::Pages).unwrap();
+    assert!(results[0].url.starts_with("/learn/0.7/signals"));
+    assert!(!results[0].excerpts.is_empty());
+}
+
+#[test]
+fn books_without_a_prefix_are_at_the_root() {
+    let index = TRY_INDEX.as_ref().unwrap();
+    let results = index.search_documents("signal", SearchMode::Pages).unwrap();
+    assert!(results[0].url.starts_with("/signals"));
+}
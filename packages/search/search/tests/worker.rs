@@ -0,0 +1,275 @@
+//! Run searches through a `SearchWorker` on the current thread, which answers the same requests a
+//! web worker does, against the shards of the fixture book in `search-shared/tests/fixtures`
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use dioxus_search::{
+    book_page_url, BookVars, DocumentMatch, NativeIndex, SearchError, SearchMode, SearchWorker,
+    SearchWorkerHost, TokenizerConfig, WorkerAnswer, WorkerRequest, WorkerResponse,
+    DICTIONARY_SHARD,
+};
+use futures::{executor::block_on, future::LocalBoxFuture, FutureExt};
+use mdbook_shared::MdBook;
+
+const INDEX_URL: &str = "/assets/dioxus_search/index_fixture";
+
+/// The compressed shards of the fixture book by url, like they are served next to the site
+fn fixture_shards() -> HashMap<String, Vec<u8>> {
+    let path =
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../search-shared/tests/fixtures/book");
+    let vars = BookVars::load(&path).unwrap();
+    let book = MdBook::new(path).unwrap();
+    let index = NativeIndex::from_book(
+        &book,
+        &vars,
+        Path::new(env!("CARGO_MANIFEST_DIR")),
+        |path| book_page_url("/learn/0.7", path),
+        TokenizerConfig::default(),
+    )
+    .unwrap();
+    index
+        .to_shards()
+        .into_iter()
+        .map(|(shard, bytes)| {
+            let compressed = dioxus_search::yazi::compress(
+                &bytes,
+                dioxus_search::yazi::Format::Zlib,
+                dioxus_search::yazi::CompressionLevel::Default,
+            )
+            .unwrap();
+            (format!("{INDEX_URL}/{shard}.bin"), compressed)
+        })
+        .collect()
+}
+
+/// Serve the fixture shards. If `stall` is true, only the dictionary is ever served
+fn fixture_fetch(
+    stall: bool,
+) -> impl Fn(String) -> LocalBoxFuture<'static, Result<Vec<u8>, SearchError>> {
+    let shards = Rc::new(fixture_shards());
+    move |url| {
+        if stall && !url.ends_with(&format!("/{DICTIONARY_SHARD}.bin")) {
+            return futures::future::pending().boxed_local();
+        }
+        let bytes = shards
+            .get(&url)
+            .cloned()
+            .ok_or_else(|| SearchError::new(format!("{url} not found")));
+        async move { bytes }.boxed_local()
+    }
+}
+
+#[test]
+fn search_in_thread() {
+    let worker = SearchWorker::<()>::new(fixture_fetch(false)).unwrap();
+    block_on(async {
+        worker.load("0.7", INDEX_URL).await.unwrap();
+
+        let results = worker
+            .search_documents("0.7", "signal", SearchMode::Pages)
+            .await
+            .unwrap();
+        assert!(results[0].url.starts_with("/learn/0.7/signals"));
+
+        let results = worker
+            .search_documents("0.7", "Routable", SearchMode::Code)
+            .await
+            .unwrap();
+        assert!(!results.is_empty());
+        assert!(results.iter().all(|result| result.example.is_some()));
+    });
+}
+
+#[test]
+fn searches_wait_for_the_index_to_load() {
+    let worker = SearchWorker::<()>::in_thread(fixture_fetch(false));
+    // The load is never awaited, so the search loads the index
+    drop(worker.load("0.7", INDEX_URL));
+    let results = block_on(worker.search_documents("0.7", "signal", SearchMode::Pages)).unwrap();
+    assert!(!results.is_empty());
+
+    let error = block_on(worker.search_documents("0.6", "signal", SearchMode::Pages)).unwrap_err();
+    assert_eq!(error.to_string(), "No index was loaded for 0.6");
+
+    let error = block_on(worker.load("0.5", "/missing")).unwrap_err();
+    assert_eq!(error.to_string(), "/missing/dictionary.bin not found");
+}
+
+#[test]
+fn loading_a_facet_again_keeps_its_shards() {
+    let fetched = Rc::new(std::cell::RefCell::new(Vec::new()));
+    let fetch = fixture_fetch(false);
+    let worker = SearchWorker::<()>::in_thread({
+        let fetched = fetched.clone();
+        move |url: String| {
+            fetched.borrow_mut().push(url.clone());
+            fetch(url)
+        }
+    });
+    block_on(async {
+        worker.load("0.7", INDEX_URL).await.unwrap();
+        worker
+            .search_documents("0.7", "signal", SearchMode::Pages)
+            .await
+            .unwrap();
+        let after_search = fetched.borrow().len();
+
+        // Loading the same url again, like after navigating, doesn't fetch anything
+        worker.load("0.7", INDEX_URL).await.unwrap();
+        worker
+            .search_documents("0.7", "signal", SearchMode::Pages)
+            .await
+            .unwrap();
+        assert_eq!(fetched.borrow().len(), after_search);
+
+        // A failed load is tried again
+        worker.load("0.6", "/missing").await.unwrap_err();
+        worker.load("0.6", "/missing").await.unwrap_err();
+        let missing = fetched
+            .borrow()
+            .iter()
+            .filter(|url| url.starts_with("/missing"))
+            .count();
+        assert_eq!(missing, 2);
+    });
+}
+
+#[test]
+fn dictionary_requests() {
+    // Only the dictionary is served, so these are answered without fetching any other shards
+    let worker = SearchWorker::<()>::in_thread(fixture_fetch(true));
+    block_on(async {
+        drop(worker.load("0.7", INDEX_URL));
+
+        let completions = worker.complete("0.7", "use_sig", 4).await.unwrap();
+        assert!(completions.contains(&"use_signal".to_string()));
+        assert_eq!(
+            worker
+                .did_you_mean("0.7", "signel")
+                .await
+                .unwrap()
+                .as_deref(),
+            Some("signal")
+        );
+
+        let error = worker.complete("0.6", "use_sig", 4).await.unwrap_err();
+        assert_eq!(error.to_string(), "No index was loaded for 0.6");
+    });
+
+    let host = SearchWorkerHost::new(fixture_fetch(true));
+    drop(host.handle(WorkerRequest::Load {
+        id: 0,
+        facet: "0.7".into(),
+        url: INDEX_URL.into(),
+    }));
+    let instant = |id, query: &str| {
+        let response = block_on(host.handle(WorkerRequest::Instant {
+            id,
+            facet: "0.7".into(),
+            query: query.into(),
+        }));
+        match response.unwrap().result.unwrap() {
+            WorkerAnswer::Instant(matches) => matches,
+            answer => panic!("expected instant results, got {answer:?}"),
+        }
+    };
+    assert!(!instant(1, "si").unwrap().is_empty());
+    assert_eq!(instant(2, "signals and memos"), None);
+
+    let popular = block_on(host.handle(WorkerRequest::Popular {
+        id: 3,
+        facet: "0.7".into(),
+    }));
+    assert!(matches!(
+        popular.unwrap().result,
+        Ok(WorkerAnswer::Matches(_))
+    ));
+}
+
+#[test]
+fn cancel_a_search() {
+    let worker = SearchWorker::<()>::in_thread(fixture_fetch(true));
+    block_on(async {
+        worker.load("0.7", INDEX_URL).await.unwrap();
+
+        let mut search = worker.search_documents("0.7", "signal", SearchMode::Pages);
+        // The shards the query needs are never fetched, so the search can't finish
+        assert!(futures::poll!(&mut search).is_pending());
+        assert!(!search.is_finished());
+
+        search.cancel();
+        assert!(search.is_finished());
+        let error = search.await.unwrap_err();
+        assert_eq!(error.to_string(), "The search was cancelled");
+    });
+}
+
+#[test]
+fn cancelled_requests_are_not_answered() {
+    let host = SearchWorkerHost::new(fixture_fetch(true));
+    let load = host.handle(WorkerRequest::Load {
+        id: 0,
+        facet: "0.7".into(),
+        url: INDEX_URL.into(),
+    });
+    assert_eq!(
+        block_on(load),
+        Some(WorkerResponse {
+            id: 0,
+            result: Ok(WorkerAnswer::Loaded),
+        })
+    );
+
+    let search = host.handle(WorkerRequest::Search {
+        id: 1,
+        facet: "0.7".into(),
+        query: "signal".into(),
+        mode: SearchMode::Pages,
+    });
+    assert_eq!(block_on(host.handle(WorkerRequest::Cancel { id: 1 })), None);
+    assert_eq!(block_on(search), None);
+}
+
+#[test]
+fn messages_are_json() {
+    let request = WorkerRequest::Search {
+        id: 3,
+        facet: "0.7".into(),
+        query: "use_signal".into(),
+        mode: SearchMode::Code,
+    };
+    let json = serde_json::to_value(&request).unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!({
+            "type": "search",
+            "id": 3,
+            "facet": "0.7",
+            "query": "use_signal",
+            "mode": "code",
+        })
+    );
+    assert_eq!(
+        serde_json::from_value::<WorkerRequest>(json).unwrap(),
+        request
+    );
+
+    // Responses carry the matches with their highlighted excerpts
+    let worker = SearchWorker::<()>::in_thread(fixture_fetch(false));
+    drop(worker.load("0.7", INDEX_URL));
+    let results: Vec<DocumentMatch> =
+        block_on(worker.search_documents("0.7", "signal", SearchMode::Pages)).unwrap();
+    let response = WorkerResponse {
+        id: 3,
+        result: Ok(WorkerAnswer::Matches(results)),
+    };
+    let json = serde_json::to_string(&response).unwrap();
+    assert_eq!(
+        serde_json::from_str::<WorkerResponse>(&json).unwrap(),
+        response
+    );
+}